@@ -0,0 +1,421 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::Duration;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use benchlib::aggregate;
+use benchlib::benching::{BenchVec, CompareThresholds, DurationDifference, Verdict};
+use benchlib::criterion;
+use benchlib::hyperfine;
+use benchlib::statistics;
+
+/// The durations of a benchmark in nanoseconds with the statistics
+/// the Rust library reports
+#[pyclass(name = "BenchVec", module = "benchlib")]
+#[derive(Clone)]
+struct PyBenchVec {
+    inner: BenchVec,
+}
+
+impl PyBenchVec {
+    /// Returns the durations or an error if there are none,
+    /// because the statistics of an empty BenchVec are undefined
+    fn non_empty(&self) -> PyResult<&BenchVec> {
+        if self.inner.is_empty() {
+            Err(PyValueError::new_err(
+                "the BenchVec doesn't contain durations",
+            ))
+        } else {
+            Ok(&self.inner)
+        }
+    }
+}
+
+impl From<BenchVec> for PyBenchVec {
+    fn from(inner: BenchVec) -> Self {
+        Self { inner }
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos() as u64
+}
+
+#[pymethods]
+impl PyBenchVec {
+    /// Creates a BenchVec from durations in nanoseconds and the
+    /// measurement overhead contained in every duration
+    #[new]
+    #[pyo3(signature = (durations, overhead = 0))]
+    fn new(durations: Vec<u64>, overhead: u64) -> Self {
+        let durations = durations
+            .into_iter()
+            .map(Duration::from_nanos)
+            .collect::<Vec<Duration>>();
+        let mut inner = BenchVec::from_vec(&durations);
+        inner.set_overhead(Duration::from_nanos(overhead));
+
+        Self { inner }
+    }
+
+    /// The durations in nanoseconds
+    #[getter]
+    fn durations(&self) -> Vec<u64> {
+        self.inner.inner.iter().map(|d| nanos(*d)).collect()
+    }
+
+    /// The measurement overhead in nanoseconds
+    #[getter]
+    fn overhead(&self) -> u64 {
+        nanos(self.inner.overhead())
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns the average without the measurement overhead in nanoseconds
+    fn average(&self) -> PyResult<u64> {
+        Ok(nanos(self.non_empty()?.average()))
+    }
+
+    /// Returns the average including the measurement overhead in nanoseconds
+    fn raw_average(&self) -> PyResult<u64> {
+        Ok(nanos(self.non_empty()?.raw_average()))
+    }
+
+    /// Returns the sample standard deviation in nanoseconds
+    fn standard_deviation(&self) -> PyResult<f64> {
+        Ok(self.non_empty()?.standard_deviation())
+    }
+
+    /// Returns the sample standard deviation relative to the average
+    fn coefficient_of_variation(&self) -> PyResult<f64> {
+        Ok(self.non_empty()?.coefficient_of_variation())
+    }
+
+    /// Returns the given percentile (0 to 100) without the measurement
+    /// overhead in nanoseconds
+    fn percentile(&self, percentile: f64) -> PyResult<u64> {
+        Ok(nanos(self.non_empty()?.percentile(percentile)))
+    }
+
+    /// Returns the shortest duration without the measurement overhead
+    fn min(&self) -> PyResult<u64> {
+        Ok(nanos(self.non_empty()?.min()))
+    }
+
+    /// Returns the longest duration without the measurement overhead
+    fn max(&self) -> PyResult<u64> {
+        Ok(nanos(self.non_empty()?.max()))
+    }
+
+    /// Formats the median, tail percentiles and maximum
+    fn tail_latencies(&self) -> PyResult<String> {
+        Ok(self.non_empty()?.tail_latencies())
+    }
+
+    /// Returns the Mann-Kendall z statistic and the relative change of a
+    /// significant drift of the durations over the run or None
+    fn drift(&self) -> Option<(f64, f64)> {
+        statistics::detect_drift(&self.inner).map(|drift| (drift.z, drift.relative_change))
+    }
+
+    /// Compares this benchmark to a previous one like the Bencher
+    /// compares results to a baseline
+    #[pyo3(signature = (previous, improvement = 0.05, regression = 0.05))]
+    fn compare(
+        &self,
+        previous: &PyBenchVec,
+        improvement: f64,
+        regression: f64,
+    ) -> PyResult<PyComparison> {
+        let thresholds = CompareThresholds {
+            improvement,
+            regression,
+        };
+        let difference = DurationDifference::with_thresholds(
+            self.non_empty()?,
+            previous.non_empty()?,
+            thresholds,
+        );
+
+        Ok(PyComparison { inner: difference })
+    }
+
+    fn __str__(&self) -> PyResult<String> {
+        Ok(self.non_empty()?.to_string())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "BenchVec(<{} durations>, overhead={})",
+            self.inner.len(),
+            self.overhead()
+        )
+    }
+}
+
+/// The difference of a benchmark to a previous one
+#[pyclass(name = "Comparison", module = "benchlib")]
+#[derive(Clone)]
+struct PyComparison {
+    inner: DurationDifference,
+}
+
+#[pymethods]
+impl PyComparison {
+    /// The difference of the averages in nanoseconds,
+    /// positive if the benchmark got slower
+    #[getter]
+    fn difference(&self) -> i64 {
+        let difference = nanos(self.inner.inner) as i64;
+        if self.inner.positive {
+            difference
+        } else {
+            -difference
+        }
+    }
+
+    /// The change of the average relative to the previous benchmark
+    #[getter]
+    fn relative(&self) -> f64 {
+        self.inner.relative
+    }
+
+    /// If the difference is significant at the 5% level
+    #[getter]
+    fn significant(&self) -> bool {
+        self.inner.significant
+    }
+
+    /// One of `improvement`, `minor`, `regression` or `insignificant`
+    #[getter]
+    fn verdict(&self) -> &'static str {
+        match self.inner.verdict {
+            Verdict::Improvement => "improvement",
+            Verdict::Minor => "minor",
+            Verdict::Regression => "regression",
+            Verdict::Insignificant => "insignificant",
+        }
+    }
+
+    fn __str__(&self) -> String {
+        self.inner.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Comparison(difference={}, relative={}, verdict='{}')",
+            self.difference(),
+            self.relative(),
+            self.verdict()
+        )
+    }
+}
+
+/// Loads the results written with `Bencher::write_raw_output_to`
+/// as dictionary of the benchmark names and their durations
+#[pyfunction]
+fn load_raw(path: &str) -> PyResult<BTreeMap<String, PyBenchVec>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(aggregate::parse_line)
+        .map(|(name, durations)| (name, durations.into()))
+        .collect())
+}
+
+/// Loads the samples criterion saved under the baseline name,
+/// `new` for the latest run or `base` for the previous one
+#[pyfunction]
+#[pyo3(signature = (dir, baseline = "new"))]
+fn load_criterion(dir: &str, baseline: &str) -> PyResult<BTreeMap<String, PyBenchVec>> {
+    Ok(criterion::read_baseline(dir, baseline)?
+        .into_iter()
+        .map(|(name, durations)| (name, durations.into()))
+        .collect())
+}
+
+/// Loads the results of hyperfine's `--export-json` output
+/// as list of the command names and their durations
+#[pyfunction]
+fn load_hyperfine(path: &str) -> PyResult<Vec<(String, PyBenchVec)>> {
+    Ok(hyperfine::read_results(path)?
+        .into_iter()
+        .map(|(name, durations)| (name, durations.into()))
+        .collect())
+}
+
+/// Returns the t statistic of Welch's test for a difference
+/// of the means of two benchmarks
+#[pyfunction]
+fn welch_t(left: &PyBenchVec, right: &PyBenchVec) -> f64 {
+    statistics::welch_t(
+        &statistics::nanos(&left.inner),
+        &statistics::nanos(&right.inner),
+    )
+}
+
+/// Analyzes saved benchmark results with the statistics of benchlib-rs
+#[pymodule]
+#[pyo3(name = "benchlib")]
+fn benchlib_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyBenchVec>()?;
+    module.add_class::<PyComparison>()?;
+    module.add_function(wrap_pyfunction!(load_raw, module)?)?;
+    module.add_function(wrap_pyfunction!(load_criterion, module)?)?;
+    module.add_function(wrap_pyfunction!(load_hyperfine, module)?)?;
+    module.add_function(wrap_pyfunction!(welch_t, module)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::fs::{self, remove_file};
+    use std::time::Duration;
+
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+
+    use benchlib::aggregate;
+    use benchlib::benching::BenchVec;
+
+    use super::benchlib_module;
+
+    /// Runs the Python code with the bindings imported as `benchlib`
+    /// and fails if it raises an exception
+    fn run_python(code: &str) {
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "benchlib").unwrap();
+            benchlib_module(&module).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("benchlib", module).unwrap();
+            let code = CString::new(code).unwrap();
+            if let Err(error) = py.run(&code, Some(&globals), None) {
+                error.print(py);
+                panic!("the Python code raised an exception");
+            }
+        });
+    }
+
+    fn bench_vec(nanos: &[u64], overhead: u64) -> BenchVec {
+        let durations = nanos
+            .iter()
+            .map(|n| Duration::from_nanos(*n))
+            .collect::<Vec<Duration>>();
+        let mut durations = BenchVec::from_vec(&durations);
+        durations.set_overhead(Duration::from_nanos(overhead));
+
+        durations
+    }
+
+    #[test]
+    fn it_loads_saved_results() {
+        let sort = bench_vec(&[100, 110, 120, 130, 140, 150], 10);
+        fs::write(
+            "test-python-raw.tsv",
+            aggregate::format_line("sort", &sort)
+                + &aggregate::format_line("empty", &BenchVec::new()),
+        )
+        .unwrap();
+        fs::write(
+            "test-python-hyperfine.json",
+            r#"{"results":[{"command":"sort -n data.txt","times":[0.001,0.002]}]}"#,
+        )
+        .unwrap();
+        run_python(
+            r#"
+results = benchlib.load_raw("test-python-raw.tsv")
+assert sorted(results) == ["empty", "sort"]
+assert results["sort"].durations == [100, 110, 120, 130, 140, 150]
+assert results["sort"].overhead == 10
+assert len(results["empty"]) == 0
+
+[(command, durations)] = benchlib.load_hyperfine("test-python-hyperfine.json")
+assert command == "sort -n data.txt"
+assert durations.durations == [1000000, 2000000]
+
+try:
+    benchlib.load_raw("test-python-missing.tsv")
+    assert False
+except FileNotFoundError:
+    pass
+"#,
+        );
+        remove_file("test-python-raw.tsv").unwrap();
+        remove_file("test-python-hyperfine.json").unwrap();
+    }
+
+    #[test]
+    fn it_computes_the_statistics_of_the_library() {
+        let nanos = [100, 110, 120, 130, 140, 150, 160, 170, 180, 190];
+        let durations = bench_vec(&nanos, 10);
+        run_python(&format!(
+            r#"
+import statistics
+
+durations = benchlib.BenchVec({:?}, overhead=10)
+assert len(durations) == 10
+assert durations.average() == {}
+assert durations.raw_average() == {}
+assert abs(durations.standard_deviation() - statistics.stdev({:?})) < 1e-9
+assert durations.coefficient_of_variation() == {:?}
+assert durations.percentile(90) == {}
+assert durations.min() == {} and durations.max() == {}
+assert durations.tail_latencies() == {:?}
+assert str(durations) == {:?}
+
+try:
+    benchlib.BenchVec([]).average()
+    assert False
+except ValueError:
+    pass
+"#,
+            nanos,
+            durations.average().as_nanos(),
+            durations.raw_average().as_nanos(),
+            nanos,
+            durations.coefficient_of_variation(),
+            durations.percentile(90f64).as_nanos(),
+            durations.min().as_nanos(),
+            durations.max().as_nanos(),
+            durations.tail_latencies(),
+            durations.to_string(),
+        ));
+    }
+
+    #[test]
+    fn it_compares_results() {
+        let previous = bench_vec(&[200, 210, 190, 205, 195, 200, 210, 190], 0);
+        let current = bench_vec(&[100, 105, 95, 100, 102, 98, 100, 100], 0);
+        run_python(&format!(
+            r#"
+previous = benchlib.BenchVec({:?})
+current = benchlib.BenchVec({:?})
+comparison = current.compare(previous)
+assert comparison.verdict == "improvement"
+assert comparison.significant
+assert comparison.difference == -{}
+assert str(comparison) == {:?}
+assert current.compare(previous, improvement=0.6).verdict == "minor"
+assert benchlib.welch_t(current, previous) < 0
+"#,
+            previous
+                .inner
+                .iter()
+                .map(|d| d.as_nanos())
+                .collect::<Vec<u128>>(),
+            current
+                .inner
+                .iter()
+                .map(|d| d.as_nanos())
+                .collect::<Vec<u128>>(),
+            (previous.average() - current.average()).as_nanos(),
+            current.compare(previous.clone()).to_string(),
+        ));
+    }
+}
@@ -0,0 +1,106 @@
+use std::io;
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// Returns an error if the core doesn't fit into the affinity mask
+#[cfg(target_os = "linux")]
+pub fn check_core(core: usize) -> io::Result<()> {
+    if core >= libc::CPU_SETSIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "core {} exceeds the {} cores of the affinity mask",
+                core,
+                libc::CPU_SETSIZE
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns an error if the core doesn't fit into the affinity mask
+#[cfg(not(target_os = "linux"))]
+pub fn check_core(_core: usize) -> io::Result<()> {
+    Ok(())
+}
+
+/// Restricts the calling thread to the given cores
+#[cfg(target_os = "linux")]
+pub fn set_current_affinity(cores: &[usize]) -> io::Result<()> {
+    for &core in cores {
+        check_core(core)?;
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        for &core in cores {
+            libc::CPU_SET(core, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Restricts the calling thread to the given cores
+#[cfg(not(target_os = "linux"))]
+pub fn set_current_affinity(_cores: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "thread affinity is not supported on this platform",
+    ))
+}
+
+/// Returns the cores the calling thread is allowed to run on
+#[cfg(target_os = "linux")]
+pub fn current_affinity() -> io::Result<Vec<usize>> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok((0..libc::CPU_SETSIZE as usize)
+            .filter(|core| libc::CPU_ISSET(*core, &set))
+            .collect())
+    }
+}
+
+/// Returns the cores the calling thread is allowed to run on
+#[cfg(not(target_os = "linux"))]
+pub fn current_affinity() -> io::Result<Vec<usize>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "thread affinity is not supported on this platform",
+    ))
+}
+
+/// Pins the calling thread to a single core
+pub fn pin_current_thread(core: usize) -> io::Result<()> {
+    set_current_affinity(&[core])
+}
+
+/// Builds a rayon thread pool with one worker thread per given core
+/// where each worker is pinned to its core. Returns an error if no core
+/// is given, because rayon would choose the number of threads then,
+/// or if a worker can't be pinned to its core.
+pub fn pinned_thread_pool(cores: &[usize]) -> io::Result<ThreadPool> {
+    if cores.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "a pinned thread pool needs at least one core",
+        ));
+    }
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(cores.len())
+        .build()
+        .map_err(io::Error::other)?;
+    pool.broadcast(|context| pin_current_thread(cores[context.index()]))
+        .into_iter()
+        .collect::<io::Result<()>>()?;
+
+    Ok(pool)
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::benching::BenchVec;
+use crate::units::{Format, FormatWith};
+
+/// Formats the durations of a benchmark as a tab separated line
+/// of the name, the overhead and the durations in nanoseconds
+pub fn format_line(name: &str, durations: &BenchVec) -> String {
+    let nanos = durations
+        .inner
+        .iter()
+        .map(|d| d.as_nanos().to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+
+    format!("{}\t{}\t{}\n", name, durations.overhead().as_nanos(), nanos)
+}
+
+/// Parses a line that was formatted with `format_line`
+pub fn parse_line(line: &str) -> Option<(String, BenchVec)> {
+    let mut columns = line.split('\t');
+    let name = columns.next()?.to_string();
+    let overhead = columns.next()?.parse::<u64>().ok()?;
+    let durations = columns
+        .next()?
+        .split(',')
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse::<u64>().map(Duration::from_nanos))
+        .collect::<Result<Vec<Duration>, _>>()
+        .ok()?;
+    let mut durations = BenchVec::from_vec(&durations);
+    durations.set_overhead(Duration::from_nanos(overhead));
+
+    Some((name, durations))
+}
+
+/// Appends the durations to the stored ones. If their measurement overheads
+/// differ, each overhead is subtracted from its own durations first, so the
+/// merged durations don't contain an overhead anymore.
+fn merge(stored: &mut BenchVec, durations: &BenchVec) {
+    if stored.overhead() != durations.overhead() {
+        *stored = without_overhead(stored);
+        stored.append(without_overhead(durations));
+    } else {
+        stored.append(durations.clone());
+    }
+}
+
+/// Returns the durations with the measurement overhead subtracted from each
+fn without_overhead(durations: &BenchVec) -> BenchVec {
+    BenchVec::from_vec(
+        &durations
+            .inner
+            .iter()
+            .map(|d| d.checked_sub(durations.overhead()).unwrap_or_default())
+            .collect::<Vec<Duration>>(),
+    )
+}
+
+/// The results of the same benchmarks run by several processes or MPI ranks
+#[derive(Debug, Clone, Default)]
+pub struct RankResults {
+    entries: BTreeMap<String, BTreeMap<usize, BenchVec>>,
+}
+
+impl RankResults {
+    /// Creates a new empty set of results
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the durations a rank measured for a benchmark.
+    /// Durations of the same rank and benchmark are appended.
+    pub fn add(&mut self, rank: usize, name: &str, durations: &BenchVec) -> &mut Self {
+        let stored = self
+            .entries
+            .entry(name.to_string())
+            .or_default()
+            .entry(rank)
+            .or_insert_with(|| {
+                let mut empty = BenchVec::new();
+                empty.set_overhead(durations.overhead());
+                empty
+            });
+        merge(stored, durations);
+
+        self
+    }
+
+    /// Adds all results of a rank from a file written with
+    /// `Bencher::write_raw_output_to`. Lines that can't be parsed are skipped.
+    pub fn load_file<P: AsRef<Path>>(&mut self, rank: usize, path: P) -> io::Result<&mut Self> {
+        for line in fs::read_to_string(path)?.lines() {
+            if let Some((name, durations)) = parse_line(line) {
+                self.add(rank, &name, &durations);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Returns the names of all benchmarks
+    pub fn names(&self) -> Vec<&str> {
+        self.entries.keys().map(|k| k.as_str()).collect()
+    }
+
+    /// Returns the durations of each rank for a benchmark
+    pub fn ranks(&self, name: &str) -> Option<&BTreeMap<usize, BenchVec>> {
+        self.entries.get(name)
+    }
+
+    /// Returns the durations of all ranks combined for a benchmark.
+    /// Ranks with different measurement overheads are combined without them.
+    pub fn combined(&self, name: &str) -> Option<BenchVec> {
+        let ranks = self.entries.get(name)?;
+        let mut combined = BenchVec::new();
+        if let Some(first) = ranks.values().next() {
+            combined.set_overhead(first.overhead());
+        }
+        for durations in ranks.values() {
+            merge(&mut combined, durations);
+        }
+
+        Some(combined)
+    }
+
+    /// Returns the rank with the highest average duration for a benchmark
+    pub fn slowest_rank(&self, name: &str) -> Option<usize> {
+        self.entries
+            .get(name)?
+            .iter()
+            .max_by_key(|(_, durations)| durations.average())
+            .map(|(rank, _)| *rank)
+    }
+}
+
+impl Display for RankResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for RankResults {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        for (name, ranks) in &self.entries {
+            writeln!(f, "{}", name)?;
+            for (rank, durations) in ranks {
+                writeln!(f, "  rank {:>4}: {}", rank, durations.with_format(*format))?;
+            }
+            if let Some(combined) = self.combined(name) {
+                writeln!(f, "  all ranks: {}", combined.with_format(*format))?;
+            }
+        }
+
+        Ok(())
+    }
+}
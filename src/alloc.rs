@@ -0,0 +1,115 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// A global allocator that counts allocations and allocated bytes.
+/// It needs to be registered as global allocator for the counts to be reported:
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator::new(std::alloc::System);
+/// ```
+pub struct CountingAllocator<A: GlobalAlloc> {
+    inner: A,
+}
+
+impl<A: GlobalAlloc> CountingAllocator<A> {
+    /// Creates a new counting allocator wrapping the given allocator
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+impl Default for CountingAllocator<System> {
+    fn default() -> Self {
+        Self::new(System)
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            record_allocation(layout.size());
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_allocation(layout.size());
+        }
+
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_allocation(new_size);
+        }
+
+        new_ptr
+    }
+}
+
+/// Records an allocation of the given size and updates the peak of live bytes
+fn record_allocation(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(size, Ordering::Relaxed);
+    let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_LIVE_BYTES.fetch_max(live, Ordering::Relaxed);
+}
+
+/// A snapshot of the allocation counters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllocationStats {
+    pub allocations: usize,
+    pub allocated_bytes: usize,
+    pub live_bytes: usize,
+    pub peak_live_bytes: usize,
+}
+
+impl AllocationStats {
+    /// Takes a snapshot of the current allocation counters
+    pub fn now() -> Self {
+        Self {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+            live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+            peak_live_bytes: PEAK_LIVE_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns if the counting allocator is registered and has counted allocations
+    pub fn is_counting() -> bool {
+        ALLOCATIONS.load(Ordering::Relaxed) > 0
+    }
+
+    /// Resets the peak of live bytes to the currently live bytes
+    pub fn reset_peak() {
+        PEAK_LIVE_BYTES.store(LIVE_BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    /// Returns the allocations made since the given snapshot.
+    /// The peak is returned relative to the live bytes of the snapshot.
+    pub fn since(&self, start: &Self) -> Self {
+        Self {
+            allocations: self.allocations - start.allocations,
+            allocated_bytes: self.allocated_bytes - start.allocated_bytes,
+            live_bytes: self.live_bytes.saturating_sub(start.live_bytes),
+            peak_live_bytes: self.peak_live_bytes.saturating_sub(start.live_bytes),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt::{self, Display};
+use std::hint::black_box;
+use std::time::Duration;
+
+use rayon::prelude::*;
+
+use crate::clock::Instant;
+use crate::units::{Format, FormatWith};
+
+/// The memory bandwidth in bytes per second achieved by the kernels of STREAM
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamResult {
+    /// a = b
+    pub copy: f64,
+    /// a = q * b
+    pub scale: f64,
+    /// a = b + c
+    pub add: f64,
+    /// a = b + q * c
+    pub triad: f64,
+}
+
+impl StreamResult {
+    /// Returns the highest bandwidth of all kernels as achievable bandwidth
+    pub fn best(&self) -> f64 {
+        self.copy.max(self.scale).max(self.add).max(self.triad)
+    }
+
+    /// Returns the given rate in bytes per second as fraction of the achievable bandwidth
+    pub fn fraction(&self, bytes_per_second: f64) -> f64 {
+        bytes_per_second / self.best()
+    }
+}
+
+impl Display for StreamResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for StreamResult {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(
+            f,
+            "Copy {} GB/s, Scale {} GB/s, Add {} GB/s, Triad {} GB/s",
+            format.number(self.copy / 1e9),
+            format.number(self.scale / 1e9),
+            format.number(self.add / 1e9),
+            format.number(self.triad / 1e9)
+        )
+    }
+}
+
+/// Measures the memory bandwidth with the kernels of STREAM on arrays of the
+/// given number of f64 elements using the rayon thread pool. The arrays should
+/// be several times larger than the last level cache. Each kernel is run the
+/// given number of times and its fastest run is reported.
+pub fn measure(elements: usize, repetitions: usize) -> StreamResult {
+    let elements = elements.max(1);
+    let mut a = vec![1f64; elements];
+    let mut b = vec![2f64; elements];
+    let c = vec![0f64; elements];
+    let q = 3f64;
+    let mut best = [Duration::MAX; 4];
+    for _ in 0..repetitions.max(1) {
+        let times = [
+            time(|| a.par_iter_mut().zip(&b).for_each(|(a, b)| *a = *b)),
+            time(|| b.par_iter_mut().zip(&a).for_each(|(b, a)| *b = q * *a)),
+            time(|| {
+                a.par_iter_mut()
+                    .zip(&b)
+                    .zip(&c)
+                    .for_each(|((a, b), c)| *a = *b + *c)
+            }),
+            time(|| {
+                a.par_iter_mut()
+                    .zip(&b)
+                    .zip(&c)
+                    .for_each(|((a, b), c)| *a = *b + q * *c)
+            }),
+        ];
+        for (best, time) in best.iter_mut().zip(times.iter()) {
+            *best = (*best).min(*time);
+        }
+    }
+    black_box(&a);
+    let bytes = (elements * std::mem::size_of::<f64>()) as f64;
+    let rate = |arrays: f64, duration: Duration| arrays * bytes / duration.as_secs_f64();
+
+    StreamResult {
+        copy: rate(2f64, best[0]),
+        scale: rate(2f64, best[1]),
+        add: rate(3f64, best[2]),
+        triad: rate(3f64, best[3]),
+    }
+}
+
+fn time<F: FnMut()>(mut func: F) -> Duration {
+    let start = Instant::now();
+    func();
+
+    start.elapsed()
+}
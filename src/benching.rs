@@ -1,12 +1,74 @@
-use howlong::*;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Display};
 use std::fs::File;
+use std::hint::black_box;
 use std::io;
-use std::io::{BufWriter, Write};
-use std::time::Instant;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::sync::{Barrier, Mutex};
+use std::thread;
+use std::time::Duration;
 
-use rayon::prelude::*;
-use termion::{color, style};
+use crate::affinity;
+use crate::aggregate::{self, RankResults};
+#[cfg(feature = "alloc-counter")]
+use crate::alloc::AllocationStats;
+use crate::bandwidth::{self, StreamResult};
+use crate::cache;
+use crate::cachegrind;
+use crate::chart::{self, DistributionChart};
+use crate::ci::{self, CiEnvironment};
+use crate::clock::{Clock, Instant, ProcessCpuClock};
+use crate::complexity::SizeSweepResult;
+use crate::concurrent::{self, ConcurrentResult, Spawner, StdSpawner, ThreadTimes};
+use crate::coordination::ProcessBarrier;
+use crate::criterion;
+use crate::energy::EnergyMeter;
+use crate::fingerprint::ResultCache;
+use crate::frequency::FrequencySampler;
+use crate::group::GroupSummary;
+#[cfg(feature = "heap-profiling")]
+use crate::heap;
+use crate::hyperfine;
+use crate::interference::{BackgroundLoad, LoadGenerator};
+use crate::isoefficiency::IsoEfficiencyResult;
+use crate::isolation;
+#[cfg(feature = "jemalloc")]
+use crate::jemalloc::JemallocStats;
+use crate::json::{self, JsonLinesReporter};
+use crate::load::OpenLoopResult;
+use crate::massif::{self, MassifSnapshot};
+use crate::memory::RssSampler;
+use crate::metrics::Metrics;
+#[cfg(all(feature = "numa", target_os = "linux"))]
+use crate::numa;
+#[cfg(all(feature = "perf", target_os = "linux"))]
+use crate::perf::{PerfCounters, PerfCounts};
+#[cfg(target_os = "linux")]
+use crate::perf_record::PerfRecorder;
+use crate::priority::{self, Priority};
+use crate::probes;
+#[cfg(feature = "http-push")]
+use crate::push::{self, RunMetadata};
+use crate::reporter::{self, ColorChoice, ConsoleReporter, Level, Reporter};
+#[cfg(unix)]
+use crate::rusage::ResourceUsage;
+use crate::sampling;
+use crate::scaling::{ScalingMode, ScalingResult};
+#[cfg(feature = "rayon-stats")]
+use crate::scheduler::SchedulerStats;
+use crate::statistics;
+use crate::template::ResultTemplate;
+use crate::terminal::Theme;
+use crate::throughput::Throughput;
+use crate::topology::{CpuTopology, Occupancy, Placement};
+use crate::units::{Format, FormatWith, NumberFormat, Precision, TimeUnit};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
 
 #[derive(Debug, Clone)]
 pub struct BenchDuration {}
@@ -14,6 +76,7 @@ pub struct BenchDuration {}
 #[derive(Debug, Clone)]
 pub struct BenchVec {
     pub inner: Vec<Duration>,
+    overhead: Duration,
 }
 
 /// A struct that stores a vector of Durations for benchmarks
@@ -21,12 +84,31 @@ pub struct BenchVec {
 impl BenchVec {
     /// Creates a new empty BenchVec
     pub fn new() -> Self {
-        Self { inner: Vec::new() }
+        Self {
+            inner: Vec::new(),
+            overhead: Duration::from_secs(0),
+        }
     }
 
     /// Creates a BenchVec from an existing vector of Durations
-    pub fn from_vec(vec: &Vec<Duration>) -> Self {
-        Self { inner: vec.clone() }
+    pub fn from_vec(vec: &[Duration]) -> Self {
+        Self {
+            inner: vec.to_vec(),
+            overhead: Duration::from_secs(0),
+        }
+    }
+
+    /// Sets the measurement overhead contained in every stored duration.
+    /// It is subtracted from the statistics instead of the single durations.
+    pub fn set_overhead(&mut self, overhead: Duration) -> &mut Self {
+        self.overhead = overhead;
+
+        self
+    }
+
+    /// Returns the measurement overhead contained in every stored duration
+    pub fn overhead(&self) -> Duration {
+        self.overhead
     }
 
     /// Adds an element to the BenchVec
@@ -48,243 +130,3359 @@ impl BenchVec {
         self.inner.len()
     }
 
+    /// Returns if the BenchVec doesn't contain any elements
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
     /// Returns the sum of all stored elements
     pub fn sum(&self) -> Duration {
-        self.inner.par_iter().sum::<Duration>()
+        sampling::sum(&self.inner)
     }
 
-    /// Returns the average of all durations
+    /// Returns the average of all durations without the measurement overhead
     pub fn average(&self) -> Duration {
-        self.sum() / self.inner.len() as u32
+        self.raw_average()
+            .checked_sub(self.overhead)
+            .unwrap_or_default()
+    }
+
+    /// Returns the average of all durations including the measurement overhead
+    /// or zero if there are no durations
+    pub fn raw_average(&self) -> Duration {
+        sampling::average(&self.inner).unwrap_or_default()
     }
 
-    /// Returns the standard deviation of all durations
+    /// Returns the sample standard deviation of all durations
     pub fn standard_deviation(&self) -> f64 {
-        (self.sum().as_nanos() as f64 / (self.len() as f64 - 1f64)).sqrt()
+        sampling::standard_deviation(&self.inner)
+    }
+
+    /// Returns the sample standard deviation relative to the average
+    pub fn coefficient_of_variation(&self) -> f64 {
+        self.standard_deviation() / (self.average().as_nanos() as f64).max(1f64)
+    }
+
+    /// Returns the given percentile (0 to 100) of all durations
+    /// without the measurement overhead using the nearest rank
+    /// or zero if there are no durations
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let mut sorted = self.inner.clone();
+        sorted.sort_unstable();
+
+        sampling::nearest_rank(&sorted, percentile)
+            .unwrap_or_default()
+            .checked_sub(self.overhead)
+            .unwrap_or_default()
+    }
+
+    /// Returns the longest duration without the measurement overhead
+    pub fn max(&self) -> Duration {
+        self.percentile(100f64)
+    }
+
+    /// Returns the shortest duration without the measurement overhead
+    pub fn min(&self) -> Duration {
+        self.percentile(0f64)
+    }
+
+    /// Formats the median, tail percentiles and maximum of all durations
+    pub fn tail_latencies(&self) -> String {
+        self.tail_latencies_with(&Format::default())
+    }
+
+    /// Formats the median, tail percentiles and maximum of all durations
+    /// with the given format
+    pub fn tail_latencies_with(&self, format: &Format) -> String {
+        format!(
+            "p50 {}, p90 {}, p99 {}, p99.9 {}, max {}",
+            format.duration(self.percentile(50f64)),
+            format.duration(self.percentile(90f64)),
+            format.duration(self.percentile(99f64)),
+            format.duration(self.percentile(99.9)),
+            format.duration(self.max())
+        )
     }
 
     /// Compares two benchmarks by calculating the average
     pub fn compare(&self, other: Self) -> DurationDifference {
-        let avg1 = self.average();
-        let avg2 = other.average();
-        if avg1 > avg2 {
-            DurationDifference {
-                inner: avg1 - avg2,
-                positive: true,
-            }
-        } else {
-            DurationDifference {
-                inner: avg2 - avg1,
-                positive: false,
-            }
-        }
+        DurationDifference::new(self, &other)
+    }
+}
+
+impl Default for BenchVec {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Display for BenchVec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for BenchVec {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
         let avg_duration = self.average();
         let standard_deviation = self.standard_deviation();
         write!(
             f,
-            "{:?} (±{:.2}ns ~ {:.2}%)",
-            avg_duration,
-            standard_deviation,
-            (standard_deviation / avg_duration.as_nanos() as f64) * 100f64
+            "{} (±{} ~ {}%)",
+            format.duration(avg_duration),
+            format.nanos(standard_deviation),
+            format.number(self.coefficient_of_variation() * 100f64)
         )
     }
 }
 
+/// The relative changes of the average duration above which a comparison
+/// is an improvement or a regression. The default is 5% for both.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompareThresholds {
+    pub improvement: f64,
+    pub regression: f64,
+}
+
+impl Default for CompareThresholds {
+    fn default() -> Self {
+        Self {
+            improvement: 0.05,
+            regression: 0.05,
+        }
+    }
+}
+
+/// The assessment of the difference between two benchmarks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Significantly faster by more than the improvement threshold
+    Improvement,
+    /// Significantly different but within the thresholds
+    Minor,
+    /// Significantly slower by more than the regression threshold
+    Regression,
+    /// The difference is not statistically significant
+    Insignificant,
+}
+
 #[derive(Debug, Clone)]
 pub struct DurationDifference {
     pub inner: Duration,
     pub positive: bool,
+    /// The change of the average relative to the previous benchmark
+    pub relative: f64,
+    /// If the difference is significant at the 5% level
+    pub significant: bool,
+    pub verdict: Verdict,
 }
 
 impl DurationDifference {
     pub fn new(left: &BenchVec, right: &BenchVec) -> Self {
+        Self::with_thresholds(left, right, CompareThresholds::default())
+    }
+
+    /// Computes the difference of the left to the right benchmark and
+    /// assesses it with the given thresholds
+    pub fn with_thresholds(
+        left: &BenchVec,
+        right: &BenchVec,
+        thresholds: CompareThresholds,
+    ) -> Self {
         let left_avg = left.average();
         let right_avg = right.average();
-        if left_avg > right_avg {
-            Self {
-                inner: left_avg - right_avg,
-                positive: true,
-            }
+        let (inner, positive) = if left_avg > right_avg {
+            (left_avg - right_avg, true)
         } else {
-            Self {
-                inner: right_avg - left_avg,
-                positive: false,
-            }
+            (right_avg - left_avg, false)
+        };
+        let relative = (left_avg.as_nanos() as f64 - right_avg.as_nanos() as f64)
+            / (right_avg.as_nanos() as f64).max(1f64);
+        let t = statistics::welch_t(&statistics::nanos(left), &statistics::nanos(right));
+        let significant = t.abs() > statistics::normal_quantile(0.975);
+        let verdict = if !significant {
+            Verdict::Insignificant
+        } else if relative > thresholds.regression {
+            Verdict::Regression
+        } else if -relative > thresholds.improvement {
+            Verdict::Improvement
+        } else {
+            Verdict::Minor
+        };
+
+        Self {
+            inner,
+            positive,
+            relative,
+            significant,
+            verdict,
         }
     }
 }
 
 impl Display for DurationDifference {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for DurationDifference {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
         write!(
             f,
-            "{}{:?}",
+            "{}{} ({}{}%{})",
             if self.positive { "+" } else { "-" },
-            self.inner
+            format.duration(self.inner),
+            if self.relative >= 0f64 { "+" } else { "" },
+            format.number(self.relative * 100f64),
+            if self.significant {
+                ""
+            } else {
+                ", not significant"
+            }
         )
     }
 }
 
+/// The statistics that are emphasized when reporting results
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportMode {
+    /// The average duration with its standard deviation
+    Average,
+    /// The tail percentiles and the maximum duration
+    TailLatency,
+}
+
+/// The samples collected while benchmarking a closure
+struct Samples {
+    started: Instant,
+    durations: BenchVec,
+    cpu_durations: BenchVec,
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    perf_counts: PerfCounts,
+}
+
+/// A linear model of the overhead that the measurement adds to a sample
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OverheadModel {
+    /// The fixed overhead of reading the clock
+    pub intercept: Duration,
+    /// The overhead of every call of the measured closure
+    pub per_call: Duration,
+}
+
+impl OverheadModel {
+    /// Fits the model to pairs of call counts and measured durations
+    /// with a least squares regression
+    pub fn fit(samples: &[(usize, Duration)]) -> Self {
+        let n = samples.len() as f64;
+        let mean_calls = samples.iter().map(|(c, _)| *c as f64).sum::<f64>() / n;
+        let mean_nanos = samples
+            .iter()
+            .map(|(_, d)| d.as_nanos() as f64)
+            .sum::<f64>()
+            / n;
+        let covariance = samples
+            .iter()
+            .map(|(c, d)| (*c as f64 - mean_calls) * (d.as_nanos() as f64 - mean_nanos))
+            .sum::<f64>();
+        let variance = samples
+            .iter()
+            .map(|(c, _)| (*c as f64 - mean_calls).powi(2))
+            .sum::<f64>();
+        let slope = if variance > 0f64 {
+            (covariance / variance).max(0f64)
+        } else {
+            0f64
+        };
+        let intercept = (mean_nanos - slope * mean_calls).max(0f64);
+
+        Self {
+            intercept: Duration::from_nanos(intercept as u64),
+            per_call: Duration::from_nanos(slope as u64),
+        }
+    }
+
+    /// Returns the overhead of a sample containing a single call
+    pub fn per_sample(&self) -> Duration {
+        self.intercept + self.per_call
+    }
+}
+
 pub struct Bencher {
     measurements: Vec<BenchVec>,
+    names: Vec<String>,
+    groups: Vec<GroupSummary>,
+    group: Option<usize>,
+    annotations: BTreeMap<String, Vec<String>>,
+    summary_chart: bool,
+    merging: bool,
+    current: Option<String>,
+    warnings: Vec<String>,
+    defer_warnings: bool,
+    scaling_results: Vec<ScalingResult>,
+    concurrent_results: Vec<ConcurrentResult>,
+    size_results: Vec<SizeSweepResult>,
+    iso_efficiency_results: Vec<IsoEfficiencyResult>,
+    open_loop_results: Vec<OpenLoopResult>,
     iterations: usize,
     max_auto_iterations: usize,
-    bench_duration: Duration,
+    overhead: OverheadModel,
+    resolution: Duration,
+    calibration_probes: usize,
+    recalibration_interval: Option<Duration>,
+    last_calibration: Instant,
+    report_cpu_time: bool,
+    progress: bool,
+    distribution_chart: Option<DistributionChart>,
+    result_template: Option<ResultTemplate>,
+    compare_thresholds: CompareThresholds,
+    baseline: Option<BTreeMap<String, BenchVec>>,
+    regressions_as_errors: bool,
+    location: Option<&'static Location<'static>>,
+    format: Format,
+    report_mode: ReportMode,
+    throughput: Option<Throughput>,
+    memory_bandwidth: Option<StreamResult>,
+    track_rss: bool,
+    background_load: Option<BackgroundLoad>,
+    process_barrier: Option<ProcessBarrier>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    report_rusage: bool,
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    perf_counters: Option<PerfCounters>,
+    energy_meter: Option<EnergyMeter>,
+    pinned_core: Option<usize>,
+    priority: Option<Priority>,
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    numa_node: Option<usize>,
+    frequency_threshold: Option<f64>,
+    isolated: bool,
+    result_cache: Option<ResultCache>,
+    cache_thrash_buffer: Option<Vec<u8>>,
+    command_warmup: usize,
+    cachegrind: bool,
+    massif: Option<(PathBuf, Vec<String>)>,
+    massif_snapshots: BTreeMap<String, MassifSnapshot>,
+    #[cfg(target_os = "linux")]
+    perf_record_dir: Option<PathBuf>,
+    perf_records: BTreeMap<String, PathBuf>,
+    clock: Box<dyn Clock>,
+    custom_clock: bool,
+    #[cfg(feature = "tracy")]
+    tracy: tracy_client::Client,
+    spawner: Box<dyn Spawner>,
+    reporters: Vec<Box<dyn Reporter>>,
     writer: Option<BufWriter<File>>,
+    scaling_writer: Option<BufWriter<File>>,
+    raw_writer: Option<BufWriter<File>>,
+    bmf_writer: Option<BufWriter<File>>,
+    plot_dir: Option<PathBuf>,
+    #[cfg(all(feature = "profiling", unix))]
+    flamegraph_dir: Option<PathBuf>,
+    #[cfg(feature = "heap-profiling")]
+    heap_profile_dir: Option<PathBuf>,
 }
 
-pub const BENCH_FILE_HEAD: &str = "name\tduration\tstandard_deviation\n";
+/// The sampling frequency of the flamegraph profiler in Hz. It's prime, so the
+/// samples don't align with periodic work of the benchmark.
+#[cfg(all(feature = "profiling", unix))]
+const FLAMEGRAPH_FREQUENCY: i32 = 997;
+
+/// The number of allocation hot spots attached to a heap profiled benchmark
+#[cfg(feature = "heap-profiling")]
+const HEAP_HOT_SPOTS: usize = 3;
+
+pub const BENCH_FILE_HEAD: &str = "name\tduration\tstandard_deviation\tmetrics\tnotes\n";
+pub const SCALING_FILE_HEAD: &str = "name\tthreads\tduration\tstandard_deviation\tspeedup\tefficiency\tmode\tkarp_flatt\tload_imbalance\toccupancy\tplacement\n";
+
+impl Default for Bencher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Bencher {
+    /// Creates a Bencher with the default settings
     pub fn new() -> Self {
+        #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+        let clock = crate::clock::InstantClock::new();
+        #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+        let clock = crate::clock::PerformanceClock::new();
+        let calibration_probes = 1000;
+        let (overhead, resolution) = Self::calibrate(&clock, calibration_probes);
         Self {
-            bench_duration: Self::calculate_bench_duration(),
+            overhead,
+            resolution,
             measurements: Vec::new(),
+            names: Vec::new(),
+            groups: Vec::new(),
+            group: None,
+            annotations: BTreeMap::new(),
+            summary_chart: true,
+            merging: false,
+            current: None,
+            warnings: Vec::new(),
+            defer_warnings: true,
+            scaling_results: Vec::new(),
+            concurrent_results: Vec::new(),
+            size_results: Vec::new(),
+            iso_efficiency_results: Vec::new(),
+            open_loop_results: Vec::new(),
             iterations: 100,
             max_auto_iterations: 10000,
+            calibration_probes,
+            recalibration_interval: None,
+            last_calibration: Instant::now(),
+            report_cpu_time: false,
+            progress: true,
+            distribution_chart: None,
+            result_template: None,
+            compare_thresholds: CompareThresholds::default(),
+            baseline: None,
+            regressions_as_errors: false,
+            location: None,
+            format: Format::default(),
+            report_mode: ReportMode::Average,
+            throughput: None,
+            memory_bandwidth: None,
+            track_rss: false,
+            background_load: None,
+            process_barrier: None,
+            report_rusage: false,
+            #[cfg(all(feature = "perf", target_os = "linux"))]
+            perf_counters: None,
+            energy_meter: None,
+            pinned_core: None,
+            priority: None,
+            #[cfg(all(feature = "numa", target_os = "linux"))]
+            numa_node: None,
+            frequency_threshold: None,
+            isolated: false,
+            result_cache: None,
+            cache_thrash_buffer: None,
+            command_warmup: 3,
+            cachegrind: false,
+            massif: None,
+            massif_snapshots: BTreeMap::new(),
+            #[cfg(target_os = "linux")]
+            perf_record_dir: None,
+            perf_records: BTreeMap::new(),
+            clock: Box::new(clock),
+            custom_clock: false,
+            #[cfg(feature = "tracy")]
+            tracy: tracy_client::Client::start(),
+            spawner: Box::new(StdSpawner),
+            reporters: vec![reporter::default_reporter()],
             writer: None,
+            scaling_writer: None,
+            raw_writer: None,
+            bmf_writer: None,
+            plot_dir: None,
+            #[cfg(all(feature = "profiling", unix))]
+            flamegraph_dir: None,
+            #[cfg(feature = "heap-profiling")]
+            heap_profile_dir: None,
         }
     }
 
-    /// Calculates the time it takes to measure a benchmark
-    fn calculate_bench_duration() -> Duration {
-        let mut durations = BenchVec::new();
-        for _ in 0..1000 {
-            let start = HighResolutionClock::now();
-            durations.push(HighResolutionClock::now() - start);
+    /// Calculates the measurement overhead and the resolution of the clock.
+    /// Processes under cachegrind or massif never measure time, so they skip
+    /// both and their counts don't depend on how long the calibration spins.
+    fn calibrate(clock: &dyn Clock, probes: usize) -> (OverheadModel, Duration) {
+        if cachegrind::target().is_some() || massif::target().is_some() {
+            return (OverheadModel::default(), Duration::from_secs(0));
         }
 
-        durations.average()
+        (Self::calculate_overhead(clock, probes), clock.resolution())
     }
 
-    /// Sets the number of iterations a benchmark will be run
-    /// If set to 0 it iterates until the standard deviation is below 1%
-    pub fn set_iterations(&mut self, iterations: usize) -> &mut Self {
-        self.iterations = iterations;
+    /// Calculates the time it takes to measure a benchmark by regressing
+    /// the durations of measured batches of empty closure calls
+    fn calculate_overhead(clock: &dyn Clock, probes: usize) -> OverheadModel {
+        let empty = || {};
+        let samples = (0..probes.max(1))
+            .map(|i| {
+                let calls = i % 16 + 1;
+                let start = clock.now();
+                for _ in 0..calls {
+                    black_box(empty)();
+                }
+                (calls, clock.elapsed(start))
+            })
+            .collect::<Vec<(usize, Duration)>>();
+
+        OverheadModel::fit(&samples)
+    }
+
+    /// Sets the clock that is used to measure benchmarks.
+    /// The measurement overhead is recalculated for the new clock.
+    /// The concurrent and open loop modes always measure the wall time.
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) -> &mut Self {
+        if clock.falls_back_to_wall_time() {
+            self.report(
+                Level::Warning,
+                "The clock measures the wall time on this platform, not the CPU time",
+            );
+        }
+        self.clock = Box::new(clock);
+        self.custom_clock = true;
+
+        self.recalibrate()
+    }
+
+    /// Sets the threading backend that runs the threads of the multi-threaded
+    /// benchmark modes, so the overhead of different backends can be compared
+    pub fn set_spawner<S: Spawner + 'static>(&mut self, spawner: S) -> &mut Self {
+        self.spawner = Box::new(spawner);
 
         self
     }
 
-    pub fn set_max_iterations(&mut self, iterations: usize) -> &mut Self {
-        self.max_auto_iterations = iterations;
+    /// Replaces all reporters with the given one that
+    /// receives the results and messages of the Bencher
+    pub fn set_reporter<R: Reporter + 'static>(&mut self, reporter: R) -> &mut Self {
+        self.reporters = vec![Box::new(reporter)];
+
+        self.update_format()
+    }
+
+    /// Sets if stdout only carries a JSON object per line for every result
+    /// and comparison while the messages and the progress are printed to
+    /// stderr. This replaces all reporters, disabling it restores the default one.
+    pub fn set_machine_readable(&mut self, enabled: bool) -> &mut Self {
+        self.reporters = if enabled {
+            vec![Box::new(JsonLinesReporter::new())]
+        } else {
+            vec![reporter::default_reporter()]
+        };
+
+        self.update_format()
+    }
+
+    /// Writes the human-readable output to the given writer instead of stdout,
+    /// so it can be captured by tests, embedded into other applications or
+    /// written to a log file. This replaces all reporters with a console
+    /// reporter for the writer.
+    pub fn set_output<W: Write + Send + 'static>(&mut self, writer: W) -> &mut Self {
+        self.reporters = vec![Box::new(ConsoleReporter::with_writer(writer))];
+
+        self.update_format()
+    }
+
+    /// Attaches another reporter that receives the results
+    /// and messages of the Bencher besides the existing ones
+    pub fn add_reporter<R: Reporter + 'static>(&mut self, reporter: R) -> &mut Self {
+        self.reporters.push(Box::new(reporter));
+
+        self.update_format()
+    }
+
+    /// Sets if the progress of running benchmarks is passed to the reporters.
+    /// The console shows it as progress bar with the estimated remaining time
+    /// if stdout is a terminal. In the automatic mode the progress is relative
+    /// to the maximum number of iterations, so the console shows a status line
+    /// with the current mean, coefficient of variation and iteration count
+    /// instead, which tells if the benchmark is about to converge.
+    pub fn set_progress(&mut self, enabled: bool) -> &mut Self {
+        self.progress = enabled;
 
         self
     }
 
-    /// Benchmarks a closure a configured number of times.
-    /// The result will be printed to the console with the given name.
-    pub fn bench<T, F: FnMut() -> T>(&mut self, name: &str, mut func: F) -> &mut Self {
-        let mut durations = BenchVec::new();
-        println!(
-            "\n{}{}{}{}",
-            color::Fg(color::LightBlue),
-            style::Bold,
-            name,
-            style::Reset
-        );
-        if self.iterations == 0 {
-            let mut count = 0;
-            while count < self.max_auto_iterations {
-                let start = Instant::now();
-                func();
-                let duration = start.elapsed();
-                if duration > self.bench_duration {
-                    durations.push(duration - self.bench_duration);
-                } else {
-                    durations.push(duration);
-                }
-                if (durations.standard_deviation() / durations.average().as_nanos() as f64) < 0.01
-                    && count > 1
-                {
-                    break;
-                }
-                count += 1;
+    /// Sets a template that the result line of each benchmark is rendered with
+    /// instead of the statistics of the report mode. If set to None the
+    /// report mode decides about the line.
+    pub fn set_result_template(&mut self, template: Option<ResultTemplate>) -> &mut Self {
+        self.result_template = template;
+
+        self
+    }
+
+    /// Sets the unit all reported durations are formatted in. With the
+    /// automatic unit each duration uses the most readable one, a fixed unit
+    /// makes the values of a report comparable by column.
+    pub fn set_time_unit(&mut self, unit: TimeUnit) -> &mut Self {
+        self.format.time_unit = unit;
+
+        self.update_format()
+    }
+
+    /// Sets the precision of all printed and exported statistics. Two decimals
+    /// are too coarse for nanosecond benchmarks and too noisy for benchmarks
+    /// that run for seconds.
+    pub fn set_precision(&mut self, precision: Precision) -> &mut Self {
+        self.format.precision = precision;
+
+        self.update_format()
+    }
+
+    /// Sets the relative changes above which a comparison is reported as an
+    /// improvement or a regression. Significant changes within the thresholds
+    /// are highlighted as minor and insignificant changes are greyed out.
+    pub fn set_compare_thresholds(&mut self, improvement: f64, regression: f64) -> &mut Self {
+        self.compare_thresholds = CompareThresholds {
+            improvement,
+            regression,
+        };
+
+        self
+    }
+
+    /// Sets the thousands and decimal separators of the numbers in the console
+    /// output, e.g. `NumberFormat::from_env()` for the format of the locale.
+    /// Exported files are not affected.
+    pub fn set_number_format(&mut self, format: NumberFormat) -> &mut Self {
+        self.format.number_format = format;
+
+        self.update_format()
+    }
+
+    /// Returns the unit, precision and separators the results of this
+    /// Bencher are formatted with
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Passes the format to all reporters, including newly attached ones
+    fn update_format(&mut self) -> &mut Self {
+        for reporter in &mut self.reporters {
+            reporter.set_format(self.format);
+        }
+
+        self
+    }
+
+    /// Sets a chart of the distribution of the samples that is reported after
+    /// each benchmark, so its shape is visible without exporting the samples.
+    /// If set to None no chart is reported.
+    pub fn set_distribution_chart(&mut self, chart: Option<DistributionChart>) -> &mut Self {
+        self.distribution_chart = chart;
+
+        self
+    }
+
+    /// Sets if the reporters use colors. With the automatic choice colors are
+    /// disabled if stdout is not a terminal or the NO_COLOR variable is set.
+    pub fn set_color(&mut self, choice: ColorChoice) -> &mut Self {
+        for reporter in &mut self.reporters {
+            reporter.set_color(choice);
+        }
+
+        self
+    }
+
+    /// Sets the colors and attributes the reporters use for headings, results
+    /// and comparisons, e.g. `Theme::light()` for light terminal backgrounds
+    pub fn set_theme(&mut self, theme: Theme) -> &mut Self {
+        for reporter in &mut self.reporters {
+            reporter.set_theme(theme);
+        }
+
+        self
+    }
+
+    /// Sets if the console prints exactly one aligned line per benchmark with
+    /// its name, mean, standard deviation and iterations and nothing else
+    /// besides warnings, e.g. for CI logs and grading scripts
+    pub fn set_compact(&mut self, enabled: bool) -> &mut Self {
+        for reporter in &mut self.reporters {
+            reporter.set_compact(enabled);
+        }
+
+        self
+    }
+
+    /// Sets if the reporters use the non-interactive output for CI services,
+    /// without colors, progress bars and with a line per benchmark. It is
+    /// enabled by default if a CI service is detected from its environment
+    /// variables, disabling it restores the interactive output.
+    pub fn set_ci_mode(&mut self, enabled: bool) -> &mut Self {
+        for reporter in &mut self.reporters {
+            reporter.set_ci(enabled);
+        }
+
+        self
+    }
+
+    /// Sets if warnings are only printed in a section at the end of the run
+    /// by `finish`, so they don't scroll away between the results. Disabling
+    /// it passes every warning to the reporters when it occurs, in addition
+    /// to the section. They are deferred by default.
+    pub fn set_defer_warnings(&mut self, enabled: bool) -> &mut Self {
+        self.defer_warnings = enabled;
+
+        self
+    }
+
+    /// Returns all warnings of the run with the name of the benchmark
+    /// they occurred in, if any
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Passes a message to all reporters. Warnings are collected and
+    /// only passed on if they aren't deferred.
+    fn report(&mut self, level: Level, message: &str) {
+        if level == Level::Warning {
+            self.warnings.push(match &self.current {
+                Some(name) => format!("{}: {}", name, message),
+                None => message.to_string(),
+            });
+            if self.defer_warnings {
+                return;
             }
-            println!("{}After {} iterations{}", style::Faint, count, style::Reset);
-        } else {
-            for _ in 0..self.iterations {
-                let start = Instant::now();
-                func();
-                let duration = start.elapsed();
-                if duration > self.bench_duration {
-                    durations.push(duration - self.bench_duration);
-                } else {
-                    durations.push(duration);
+        }
+        for reporter in &mut self.reporters {
+            reporter.on_message(level, message);
+        }
+    }
+
+    /// Announces the start of a benchmark to all reporters
+    fn start_bench(&mut self, name: &str) {
+        self.current = Some(name.to_string());
+        for reporter in &mut self.reporters {
+            reporter.on_bench_start(name);
+        }
+    }
+
+    /// Tells all reporters that a benchmark ended without durations
+    fn end_bench(&mut self, name: &str) {
+        for reporter in &mut self.reporters {
+            reporter.on_bench_end(name);
+        }
+    }
+
+    /// Reports the result line of a benchmark with the result template
+    /// or with the statistics of the report mode
+    fn report_result(&mut self, name: &str, durations: &BenchVec) {
+        let line = match (&self.result_template, self.report_mode) {
+            (Some(template), _) => template.render(name, durations, &self.format),
+            (None, ReportMode::Average) => {
+                format!("Result: {}", durations.with_format(self.format))
+            }
+            (None, ReportMode::TailLatency) => {
+                format!("Result: {}", durations.tail_latencies_with(&self.format))
+            }
+        };
+        self.report(Level::Result, &line);
+    }
+
+    /// Writes and stores the result of a benchmark and passes it to all reporters.
+    /// A merged result is written once with the overhead of the new durations.
+    fn record(&mut self, name: &str, durations: BenchVec, metrics: Option<&Metrics>) {
+        if self.merging {
+            if let Some(index) = self.names.iter().rposition(|n| n == name) {
+                let overhead = durations.overhead();
+                self.measurements[index]
+                    .append(durations)
+                    .set_overhead(overhead);
+                let merged = self.measurements[index].clone();
+                self.write_result(name, &merged, metrics);
+                self.report(
+                    Level::Result,
+                    &format!("Merged: {}", merged.with_format(self.format)),
+                );
+                for reporter in &mut self.reporters {
+                    reporter.on_bench_complete(name, &merged);
+                }
+                for group in &mut self.groups {
+                    if let Some(member) = group.members.iter_mut().find(|(n, _)| n == name) {
+                        member.1 = merged.average();
+                    }
                 }
+                return;
             }
         }
-        println!("Result: {}", durations);
-        if let Some(writer) = &mut self.writer {
-            let _ = writer.write(
-                format!(
-                    "{}\t{:?}\t{:.2}ns\n",
+        self.write_result(name, &durations, metrics);
+        for reporter in &mut self.reporters {
+            reporter.on_bench_complete(name, &durations);
+        }
+        self.compare_to_baseline(name, &durations);
+        if let Some(group) = self.group {
+            self.groups[group].push(name, durations.average());
+        }
+        self.measurements.push(durations);
+        self.names.push(name.to_string());
+    }
+
+    /// Compares the result of a benchmark to its result in the baseline
+    /// and annotates regressions on GitHub Actions via the reporters.
+    fn compare_to_baseline(&mut self, name: &str, durations: &BenchVec) {
+        let location = self.location.take();
+        let baseline = match self.baseline.as_ref().and_then(|b| b.get(name)) {
+            Some(baseline) => baseline,
+            None => return,
+        };
+        let diff =
+            DurationDifference::with_thresholds(durations, baseline, self.compare_thresholds);
+        let previous = format!("{} (baseline)", name);
+        for reporter in &mut self.reporters {
+            reporter.on_compare(name, &previous, &diff);
+        }
+        if diff.verdict == Verdict::Regression && ci::detect() == Some(CiEnvironment::GitHubActions)
+        {
+            let annotation = ci::github_annotation(
+                if self.regressions_as_errors {
+                    "error"
+                } else {
+                    "warning"
+                },
+                location.map(|l| (l.file(), l.line())),
+                "Benchmark regression",
+                &format!(
+                    "{} is slower than the baseline: {}",
                     name,
-                    durations.average(),
-                    durations.standard_deviation()
-                )
-                .as_bytes(),
+                    diff.with_format(self.format)
+                ),
             );
+            for reporter in &mut self.reporters {
+                reporter.on_ci_command(&annotation);
+            }
         }
-        self.measurements.push(durations);
+    }
+
+    /// Recalculates the time it takes to measure a benchmark
+    pub fn recalibrate(&mut self) -> &mut Self {
+        (self.overhead, self.resolution) =
+            Self::calibrate(self.clock.as_ref(), self.calibration_probes);
+        self.last_calibration = Instant::now();
 
         self
     }
 
-    /// Compares the last two benchmarks
-    /// If the number of benchmarks is below 2 it doesn't do anything
-    pub fn compare(&mut self) -> &mut Self {
-        if self.measurements.len() > 1 {
-            let left = self.measurements.get(self.measurements.len() - 1).unwrap();
-            let right = self.measurements.get(self.measurements.len() - 2).unwrap();
-            let diff = DurationDifference::new(left, right);
-            println!("Difference: {}", diff);
-        }
+    /// Sets the number of probes used to calculate the measurement overhead
+    /// and recalibrates with the new number of probes
+    pub fn set_calibration_probes(&mut self, probes: usize) -> &mut Self {
+        self.calibration_probes = probes;
+
+        self.recalibrate()
+    }
+
+    /// Sets an interval after which the measurement overhead is recalculated
+    /// before the next benchmark. If set to None the overhead is only
+    /// calculated when the clock or the number of probes changes.
+    pub fn set_recalibration_interval(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.recalibration_interval = interval;
 
         self
     }
 
-    /// Prints the settings of the Bencher
-    pub fn print_settings(&mut self) -> &mut Self {
-        println!(
-            "\n{}{}Benchmarking Settings{}",
-            color::Fg(color::Green),
-            style::Underline,
-            style::Reset
-        );
-        println!("Benchmarking accuracy delay:\t {:?}", self.bench_duration);
-        println!(
-            "Number of iterations:\t {}",
-            if self.iterations > 0 {
-                self.iterations.to_string()
-            } else {
-                "auto".to_string()
-            }
-        );
-        if self.iterations == 0 {
-            println!("Maximum number of iterations: {}", self.max_auto_iterations)
-        }
+    /// Sets the number of iterations a benchmark will be run
+    /// If set to 0 it iterates until the standard deviation is below 1%
+    pub fn set_iterations(&mut self, iterations: usize) -> &mut Self {
+        self.iterations = iterations;
 
         self
     }
 
-    /// Adds a file to write the output to
-    pub fn write_output_to(&mut self, mut writer: BufWriter<File>) -> &mut Self {
-        writer.write(BENCH_FILE_HEAD.as_bytes()).unwrap();
-        self.writer = Some(writer);
+    pub fn set_max_iterations(&mut self, iterations: usize) -> &mut Self {
+        self.max_auto_iterations = iterations;
 
         self
     }
 
-    pub fn flush(&mut self) -> io::Result<()> {
-        if let Some(writer) = &mut self.writer {
-            writer.flush()
-        } else {
-            Ok(())
+    /// Sets if the CPU time of the process should be reported
+    /// in addition to the wall time of each benchmark
+    pub fn set_cpu_time_reporting(&mut self, report: bool) -> &mut Self {
+        if report && ProcessCpuClock::new().falls_back_to_wall_time() {
+            self.report(
+                Level::Warning,
+                "The CPU time is measured as wall time on this platform",
+            );
         }
+        self.report_cpu_time = report;
+
+        self
+    }
+
+    /// Sets which statistics are emphasized when reporting results
+    pub fn set_report_mode(&mut self, mode: ReportMode) -> &mut Self {
+        self.report_mode = mode;
+
+        self
+    }
+
+    /// Sets the amount of data processed by one iteration of the following
+    /// benchmarks, so their results are additionally reported as a rate
+    pub fn set_throughput(&mut self, throughput: Option<Throughput>) -> &mut Self {
+        self.throughput = throughput;
+
+        self
+    }
+
+    /// Measures the achievable memory bandwidth with STREAM kernels on arrays
+    /// of the given number of f64 elements. Afterwards benchmarks with a
+    /// throughput in bytes are additionally reported as fraction of it.
+    pub fn measure_memory_bandwidth(&mut self, elements: usize) -> &mut Self {
+        let result = bandwidth::measure(elements, 10);
+        self.report(
+            Level::Info,
+            &format!("Memory bandwidth: {}", result.with_format(self.format)),
+        );
+        self.memory_bandwidth = Some(result);
+
+        self
     }
+
+    /// Returns the memory bandwidth if it was measured
+    pub fn memory_bandwidth(&self) -> Option<&StreamResult> {
+        self.memory_bandwidth.as_ref()
+    }
+
+    /// Sets if the peak resident set size of the process should be
+    /// sampled and reported for each benchmark
+    pub fn set_rss_tracking(&mut self, track: bool) -> &mut Self {
+        self.track_rss = track;
+
+        self
+    }
+
+    /// Sets a load that runs in background threads while measuring,
+    /// to study how robust an implementation is to interference.
+    /// If set to None no background load is started.
+    pub fn set_background_load(&mut self, load: Option<BackgroundLoad>) -> &mut Self {
+        self.background_load = load;
+
+        self
+    }
+
+    /// Sets a barrier that separately started benchmark processes wait on
+    /// before each benchmark, so their measured sections begin at the same time.
+    /// If set to None the benchmarks start without waiting.
+    pub fn set_process_barrier(&mut self, barrier: Option<ProcessBarrier>) -> &mut Self {
+        self.process_barrier = barrier;
+
+        self
+    }
+
+    /// Sets if page faults, context switches and user and system time
+    /// of the process should be reported for each benchmark
+    pub fn set_rusage_reporting(&mut self, report: bool) -> &mut Self {
+        self.report_rusage = report;
+
+        self
+    }
+
+    /// Sets if hardware performance counters (instructions, cycles, cache misses
+    /// and branch misses) should be counted around the measured region
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    pub fn set_perf_counters(&mut self, enabled: bool) -> &mut Self {
+        self.perf_counters = if enabled {
+            match PerfCounters::new() {
+                Ok(counters) => Some(counters),
+                Err(e) => {
+                    self.report(
+                        Level::Warning,
+                        &format!("Failed to open performance counters: {}", e),
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        self
+    }
+
+    /// Sets if the energy consumed by the CPU packages should be read
+    /// from the RAPL counters and reported for each benchmark
+    pub fn set_energy_measurement(&mut self, enabled: bool) -> &mut Self {
+        self.energy_meter = if enabled {
+            let meter = EnergyMeter::new();
+            if meter.is_none() {
+                self.report(Level::Warning, "RAPL energy counters are not available");
+            }
+            meter
+        } else {
+            None
+        };
+
+        self
+    }
+
+    /// Sets a core the benchmarking thread is pinned to while measuring.
+    /// The previous affinity is restored after each benchmark.
+    /// Worker threads can be pinned with `affinity::pinned_thread_pool`.
+    /// Returns an error if the core doesn't fit into the affinity mask.
+    pub fn set_pinned_core(&mut self, core: Option<usize>) -> io::Result<&mut Self> {
+        if let Some(core) = core {
+            affinity::check_core(core)?;
+        }
+        self.pinned_core = core;
+
+        Ok(self)
+    }
+
+    /// Sets a scheduling priority the process is elevated to while measuring.
+    /// The previous priority is restored after each benchmark.
+    pub fn set_priority(&mut self, priority: Option<Priority>) -> &mut Self {
+        self.priority = priority;
+
+        self
+    }
+
+    /// Sets a NUMA node that execution and memory allocation are bound to
+    /// while measuring. The default policy is restored after each benchmark.
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    pub fn set_numa_node(&mut self, node: Option<usize>) -> &mut Self {
+        self.numa_node = node;
+
+        self
+    }
+
+    /// Sets a threshold for the relative variation of the CPU frequency during
+    /// a benchmark above which the result is marked as potentially unreliable.
+    /// If set to None the frequency isn't monitored.
+    pub fn set_frequency_monitoring(&mut self, threshold: Option<f64>) -> &mut Self {
+        self.frequency_threshold = threshold;
+
+        self
+    }
+
+    /// Sets if each benchmark should be run in a separate child process.
+    /// The current executable is re-run with the benchmark selected and the
+    /// durations are collected from its output.
+    pub fn set_process_isolation(&mut self, isolated: bool) -> &mut Self {
+        self.isolated = isolated;
+
+        self
+    }
+
+    /// Enables the incremental mode where results are stored in the given cache file
+    /// and reused for benchmarks whose binary and name didn't change since
+    pub fn set_incremental<P: AsRef<Path>>(&mut self, cache_file: P) -> io::Result<&mut Self> {
+        self.result_cache = Some(ResultCache::load(cache_file)?);
+
+        Ok(self)
+    }
+
+    /// Loads the results of a previous run from a file written with
+    /// `write_raw_output_to`. Every benchmark with a result in the baseline is
+    /// compared to it with the compare thresholds. On GitHub Actions
+    /// regressions are annotated at the call of the benchmark.
+    pub fn set_baseline<P: AsRef<Path>>(&mut self, path: P) -> io::Result<&mut Self> {
+        let baseline = std::fs::read_to_string(path)?
+            .lines()
+            .filter_map(aggregate::parse_line)
+            .collect::<BTreeMap<String, BenchVec>>();
+        self.baseline = Some(baseline);
+
+        Ok(self)
+    }
+
+    /// Loads a baseline saved by criterion, e.g. `new` or `base` from
+    /// `target/criterion`, so benchmarks migrated from criterion are compared
+    /// to their history. Benchmarks must be named like their criterion id,
+    /// e.g. `group/function`. The results are added to an already loaded baseline.
+    pub fn set_criterion_baseline<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+        baseline: &str,
+    ) -> io::Result<&mut Self> {
+        let results = criterion::read_baseline(dir, baseline)?;
+        self.baseline
+            .get_or_insert_with(BTreeMap::new)
+            .extend(results);
+
+        Ok(self)
+    }
+
+    /// Sets if regressions against the baseline are annotated as errors
+    /// instead of warnings on GitHub Actions
+    pub fn set_regressions_as_errors(&mut self, enabled: bool) -> &mut Self {
+        self.regressions_as_errors = enabled;
+
+        self
+    }
+
+    /// Sets the size of a buffer that is written before each iteration
+    /// to evict the caches, so cold-cache performance is measured.
+    /// Specific memory regions can be flushed with `cache::flush_region` in a hook.
+    /// If set to None the caches aren't evicted.
+    pub fn set_cold_cache(&mut self, thrash_size: Option<usize>) -> &mut Self {
+        self.cache_thrash_buffer = thrash_size.map(|size| vec![0u8; size]);
+
+        self
+    }
+
+    /// Sets the number of unmeasured warmup runs for benchmarked commands.
+    /// At least one run is made to check if the command can be spawned.
+    pub fn set_command_warmup(&mut self, runs: usize) -> &mut Self {
+        self.command_warmup = runs;
+
+        self
+    }
+
+    /// Sets if benchmarks should be run once under cachegrind to report
+    /// instruction and cache access counts instead of measuring time.
+    /// The current executable is re-run with the benchmark selected for that
+    /// and once more with an empty closure in its place, whose counts are
+    /// subtracted, so only the counts of the benchmark are reported. The
+    /// processes under cachegrind skip the calibration of the clock, which
+    /// spins for a varying time, so the difference of both is deterministic.
+    pub fn set_cachegrind(&mut self, enabled: bool) -> &mut Self {
+        self.cachegrind = enabled;
+
+        self
+    }
+
+    /// Sets the benchmarks that are run once more under valgrind's massif
+    /// before they are measured, so their heap growth can be analyzed. An empty
+    /// list selects all benchmarks. The massif output files are kept in the
+    /// given directory and the peak snapshot is attached to each benchmark as
+    /// note. If set to None or valgrind isn't available no snapshots are made.
+    pub fn set_massif(&mut self, dir: Option<PathBuf>, names: &[&str]) -> &mut Self {
+        self.massif = dir.map(|dir| (dir, names.iter().map(|n| n.to_string()).collect()));
+
+        self
+    }
+
+    /// Returns the peak heap snapshot massif made of a benchmark
+    pub fn massif_snapshot(&self, name: &str) -> Option<&MassifSnapshot> {
+        self.massif_snapshots.get(name)
+    }
+
+    /// Sets a directory that `perf record` writes a profile with call graphs
+    /// to for each benchmark. perf is attached to the process and only records
+    /// while the iterations run, so the profile doesn't contain setup code.
+    /// The path of each profile is attached to its benchmark as note.
+    /// If set to None no profiles are recorded. Only supported on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn set_perf_record(&mut self, dir: Option<PathBuf>) -> &mut Self {
+        self.perf_record_dir = dir;
+
+        self
+    }
+
+    /// Returns the path of the profile perf recorded for a benchmark
+    pub fn perf_record(&self, name: &str) -> Option<&Path> {
+        self.perf_records.get(name).map(|path| path.as_path())
+    }
+
+    /// Benchmarks a closure a configured number of times.
+    /// The result will be printed to the console with the given name.
+    #[track_caller]
+    pub fn bench<T, F: FnMut() -> T>(&mut self, name: &str, func: F) -> &mut Self {
+        self.run_bench(name, || {}, func, || {}, None)
+    }
+
+    /// Benchmarks a closure that is executed inside the given rayon thread pool,
+    /// so parallel iterators in the closure use the workers of that pool.
+    /// Entering the pool is part of the measured region.
+    #[track_caller]
+    pub fn bench_in_pool<T, F>(&mut self, name: &str, pool: &ThreadPool, mut func: F) -> &mut Self
+    where
+        F: FnMut() -> T + Send,
+        T: Send,
+    {
+        self.bench(name, || pool.install(&mut func))
+    }
+
+    /// Benchmarks a closure inside a dedicated rayon thread pool with the given
+    /// number of threads instead of the global pool sized by RAYON_NUM_THREADS
+    #[track_caller]
+    pub fn bench_with_threads<T, F>(&mut self, name: &str, threads: usize, func: F) -> &mut Self
+    where
+        F: FnMut() -> T + Send,
+        T: Send,
+    {
+        match self.thread_pool(name, threads) {
+            Some(pool) => self.bench_in_pool(name, &pool, func),
+            None => self,
+        }
+    }
+
+    /// Benchmarks a closure that reports auxiliary metrics for each iteration
+    /// like processed elements or retries via the given handle
+    #[track_caller]
+    pub fn bench_with_metrics<T, F>(&mut self, name: &str, mut func: F) -> &mut Self
+    where
+        F: FnMut(&mut Metrics) -> T,
+    {
+        let metrics = RefCell::new(Metrics::new());
+        self.run_bench(
+            name,
+            || {},
+            || func(&mut metrics.borrow_mut()),
+            || {},
+            Some(&metrics),
+        )
+    }
+
+    /// Records durations that were measured outside of the Bencher, e.g. the
+    /// elapsed times of CUDA or OpenCL events, as the result of a benchmark.
+    /// They are reported and written like measured results, so device and
+    /// host timings can be compared in the same report.
+    #[track_caller]
+    pub fn record_external(&mut self, name: &str, durations: &[Duration]) -> &mut Self {
+        if durations.is_empty() {
+            return self;
+        }
+        self.location = Some(Location::caller());
+        self.record_imported(name, BenchVec::from_vec(durations), "(external timing)")
+    }
+
+    /// Imports the results of hyperfine's `--export-json` output as benchmarks
+    /// named by their commands, so command line tools can be compared to
+    /// in-process benchmarks in the same report
+    #[track_caller]
+    pub fn import_hyperfine<P: AsRef<Path>>(&mut self, path: P) -> io::Result<&mut Self> {
+        self.location = Some(Location::caller());
+        for (name, durations) in hyperfine::read_results(path)? {
+            if !durations.is_empty() {
+                self.record_imported(&name, durations, "(imported from hyperfine)");
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Reports, writes and records durations that weren't measured by the Bencher
+    fn record_imported(&mut self, name: &str, durations: BenchVec, note: &str) -> &mut Self {
+        self.start_bench(name);
+        self.report(Level::Note, note);
+        self.report_result(name, &durations);
+        if let Some(throughput) = &self.throughput {
+            self.report(
+                Level::Info,
+                &format!(
+                    "Throughput: {}",
+                    throughput.format_rate_with(durations.average(), &self.format)
+                ),
+            );
+        }
+        self.record(name, durations, None);
+
+        self
+    }
+
+    /// Benchmarks a closure that returns the duration of each iteration
+    /// measured by a device timer, e.g. the elapsed time between two GPU events.
+    /// The returned durations are recorded instead of the host time.
+    #[track_caller]
+    pub fn bench_external<F: FnMut() -> Duration>(&mut self, name: &str, mut func: F) -> &mut Self {
+        let mut durations = BenchVec::new();
+        if self.iterations == 0 {
+            while durations.len() < self.max_auto_iterations {
+                durations.push(func());
+                if durations.len() > 2 && durations.coefficient_of_variation() < 0.01 {
+                    break;
+                }
+            }
+        } else {
+            for _ in 0..self.iterations {
+                durations.push(func());
+            }
+        }
+
+        self.record_external(name, &durations.inner)
+    }
+
+    /// Benchmarks a closure with hooks that are executed before and after
+    /// each iteration outside of the measured region.
+    /// Shell commands can be used as hooks via `shell_hook`.
+    /// Hooks returning an error are reported as warnings.
+    #[track_caller]
+    pub fn bench_with_hooks<T, P, PR, F, C, CR>(
+        &mut self,
+        name: &str,
+        prepare: P,
+        func: F,
+        cleanup: C,
+    ) -> &mut Self
+    where
+        P: FnMut() -> PR,
+        PR: HookOutcome,
+        F: FnMut() -> T,
+        C: FnMut() -> CR,
+        CR: HookOutcome,
+    {
+        self.run_bench(name, prepare, func, cleanup, None)
+    }
+
+    /// Runs the measurement of a benchmark
+    #[track_caller]
+    fn run_bench<T, P, PR, F, C, CR>(
+        &mut self,
+        name: &str,
+        mut prepare: P,
+        mut func: F,
+        mut cleanup: C,
+        metrics: Option<&RefCell<Metrics>>,
+    ) -> &mut Self
+    where
+        P: FnMut() -> PR,
+        PR: HookOutcome,
+        F: FnMut() -> T,
+        C: FnMut() -> CR,
+        CR: HookOutcome,
+    {
+        self.location = Some(Location::caller());
+        if let Some(target) = cachegrind::target().or_else(massif::target) {
+            if target == name {
+                // the baseline stops at the same call site without the benchmark
+                if !cachegrind::is_baseline() {
+                    func();
+                }
+                std::process::exit(0);
+            }
+            return self;
+        }
+        let isolation_target = isolation::target();
+        if let Some(target) = &isolation_target {
+            if target != name {
+                return self;
+            }
+        } else if self.isolated {
+            return self.bench_isolated(name);
+        }
+        if self.cachegrind && isolation_target.is_none() {
+            return self.bench_cachegrind(name);
+        }
+        if isolation_target.is_none() {
+            self.snapshot_massif(name);
+        }
+        if isolation_target.is_none() {
+            if let Some(durations) = self.result_cache.as_ref().and_then(|c| c.get(name)) {
+                let durations = durations.clone();
+                self.start_bench(name);
+                self.report(
+                    Level::Result,
+                    &format!("Result (unchanged): {}", durations.with_format(self.format)),
+                );
+                self.record(name, durations, None);
+                return self;
+            }
+        }
+        // VTune only collects while the iterations run, so the setup
+        // and reporting of the Bencher are excluded from the profile
+        #[cfg(feature = "itt")]
+        ittapi::pause();
+        if let Some(interval) = self.recalibration_interval {
+            if self.last_calibration.elapsed() >= interval {
+                self.recalibrate();
+            }
+        }
+        let previous_affinity = self.pinned_core.and_then(|core| {
+            let previous = affinity::current_affinity().ok();
+            if let Err(e) = affinity::pin_current_thread(core) {
+                self.report(
+                    Level::Warning,
+                    &format!("Failed to pin the thread to core {}: {}", core, e),
+                );
+            }
+            previous
+        });
+        let priority_guard = self
+            .priority
+            .and_then(|priority| match priority::elevate(priority) {
+                Ok(guard) => Some(guard),
+                Err(e) => {
+                    self.report(
+                        Level::Warning,
+                        &format!("Failed to elevate the priority to {:?}: {}", priority, e),
+                    );
+                    None
+                }
+            });
+        #[cfg(all(feature = "numa", target_os = "linux"))]
+        let numa_guard = self.numa_node.and_then(|node| match numa::bind(node) {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                self.report(
+                    Level::Warning,
+                    &format!("Failed to bind to NUMA node {}: {}", node, e),
+                );
+                None
+            }
+        });
+        let mut samples = Samples {
+            started: Instant::now(),
+            durations: BenchVec::new(),
+            cpu_durations: BenchVec::new(),
+            #[cfg(all(feature = "perf", target_os = "linux"))]
+            perf_counts: PerfCounts::default(),
+        };
+        samples.durations.set_overhead(self.overhead.per_sample());
+        #[cfg(feature = "alloc-counter")]
+        let allocations_start = {
+            AllocationStats::reset_peak();
+            AllocationStats::now()
+        };
+        #[cfg(feature = "jemalloc")]
+        let jemalloc_start = JemallocStats::now();
+        #[cfg(feature = "rayon-stats")]
+        let scheduler_start = SchedulerStats::now();
+        #[cfg(unix)]
+        let rusage_start = ResourceUsage::now();
+        let energy_start = self
+            .energy_meter
+            .as_ref()
+            .map(|meter| (meter.read(), Instant::now()));
+        let frequency_sampler = self
+            .frequency_threshold
+            .map(|_| FrequencySampler::start(Duration::from_millis(10)));
+        let rss_sampler = if self.track_rss {
+            Some(RssSampler::start(Duration::from_millis(1)))
+        } else {
+            None
+        };
+        self.start_bench(name);
+        if let Some(Err(e)) = self.process_barrier.as_mut().map(|b| b.wait(name)) {
+            self.report(
+                Level::Warning,
+                &format!("Failed to wait for the other processes: {}", e),
+            );
+        }
+        let load_generator = self.background_load.map(|load| {
+            self.report(Level::Note, &format!("Under background load of {}", load));
+            LoadGenerator::start(load)
+        });
+        #[cfg(all(feature = "profiling", unix))]
+        let profiler = self.flamegraph_dir.as_ref().map(|_| {
+            pprof::ProfilerGuardBuilder::default()
+                .frequency(FLAMEGRAPH_FREQUENCY)
+                .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+                .build()
+        });
+        #[cfg(target_os = "linux")]
+        let mut perf_recorder = self.start_perf_record(name);
+        #[cfg(feature = "itt")]
+        ittapi::resume();
+        if self.iterations == 0 {
+            let mut count = 0;
+            while count < self.max_auto_iterations {
+                self.sample(name, &mut prepare, &mut func, &mut cleanup, &mut samples);
+                if let Some(metrics) = metrics {
+                    metrics.borrow_mut().finish_iteration();
+                }
+                let durations = &samples.durations;
+                if durations.coefficient_of_variation() < 0.01 && count > 1 {
+                    break;
+                }
+                count += 1;
+            }
+            self.report(Level::Note, &format!("After {} iterations", count));
+        } else {
+            for _ in 0..self.iterations {
+                self.sample(name, &mut prepare, &mut func, &mut cleanup, &mut samples);
+                if let Some(metrics) = metrics {
+                    metrics.borrow_mut().finish_iteration();
+                }
+            }
+        }
+        #[cfg(feature = "itt")]
+        ittapi::pause();
+        #[cfg(target_os = "linux")]
+        if let Some(recorder) = perf_recorder.take() {
+            self.finish_perf_record(name, recorder);
+        }
+        if let Some(generator) = load_generator {
+            generator.stop();
+        }
+        #[cfg(all(feature = "profiling", unix))]
+        if let Some(profiler) = profiler {
+            self.write_flamegraph(name, profiler);
+        }
+        // the guard only restores the priority on unix, where it can be elevated
+        #[cfg_attr(not(unix), allow(clippy::drop_non_drop))]
+        drop(priority_guard);
+        #[cfg(all(feature = "numa", target_os = "linux"))]
+        drop(numa_guard);
+        if let Some(cores) = previous_affinity {
+            let _ = affinity::set_current_affinity(&cores);
+        }
+        let Samples {
+            durations,
+            cpu_durations,
+            ..
+        } = samples;
+        #[cfg(feature = "jemalloc")]
+        let jemalloc = jemalloc_start
+            .zip(JemallocStats::now())
+            .map(|(start, end)| end.since(&start));
+        #[cfg(feature = "rayon-stats")]
+        let scheduler = SchedulerStats::now().since(&scheduler_start);
+        #[cfg(unix)]
+        let rusage = ResourceUsage::now().since(&rusage_start);
+        let peak_rss = rss_sampler.and_then(|sampler| sampler.stop());
+        let frequency = frequency_sampler.and_then(|sampler| sampler.stop());
+        let energy =
+            self.energy_meter
+                .as_ref()
+                .zip(energy_start)
+                .map(|(meter, (start, start_time))| {
+                    (
+                        meter.joules_between(&start, &meter.read()),
+                        start_time.elapsed(),
+                    )
+                });
+        #[cfg(feature = "alloc-counter")]
+        let allocations = AllocationStats::now().since(&allocations_start);
+        self.report_result(name, &durations);
+        if let Some(chart) = self.distribution_chart {
+            let samples = durations
+                .inner
+                .iter()
+                .map(|d| d.checked_sub(durations.overhead()).unwrap_or_default())
+                .collect::<Vec<Duration>>();
+            self.report(Level::Info, &chart.render(&samples, &self.format));
+        }
+        if let Some(throughput) = &self.throughput {
+            match (throughput, &self.memory_bandwidth) {
+                (Throughput::Bytes(_), Some(bandwidth)) => self.report(
+                    Level::Info,
+                    &format!(
+                        "Throughput: {} ({}% of the memory bandwidth)",
+                        throughput.format_rate_with(durations.average(), &self.format),
+                        self.format.number(
+                            bandwidth.fraction(throughput.per_second(durations.average())) * 100f64
+                        )
+                    ),
+                ),
+                _ => self.report(
+                    Level::Info,
+                    &format!(
+                        "Throughput: {}",
+                        throughput.format_rate_with(durations.average(), &self.format)
+                    ),
+                ),
+            }
+        }
+        #[cfg(unix)]
+        {
+            if self.report_rusage {
+                self.report(Level::Info, &rusage.with_format(self.format).to_string());
+            }
+        }
+        #[cfg(all(feature = "perf", target_os = "linux"))]
+        {
+            if self.perf_counters.is_some() {
+                let [instructions, cycles, cache_misses, branch_misses] =
+                    samples.perf_counts.per_iteration(durations.len());
+                self.report(Level::Info, &format!("Instructions: {}, Cycles: {}, Cache misses: {}, Branch misses: {} (per iteration)", self.format.number(instructions), self.format.number(cycles), self.format.number(cache_misses), self.format.number(branch_misses)));
+            }
+        }
+        if let (Some(threshold), Some(frequency)) = (self.frequency_threshold, frequency) {
+            if frequency.variation() > threshold || frequency.throttle_events > 0 {
+                self.report(Level::Warning, &format!("The CPU frequency varied between {} and {} MHz ({}%) with {} throttling events. The result is potentially unreliable.", frequency.min_khz / 1000, frequency.max_khz / 1000, self.format.number(frequency.variation() * 100f64), frequency.throttle_events));
+            }
+        }
+        if let Some((joules, elapsed)) = energy {
+            self.report(
+                Level::Info,
+                &format!(
+                    "Energy: {} J ({} W average, {} mJ per iteration)",
+                    self.format.number(joules),
+                    self.format.number(joules / elapsed.as_secs_f64()),
+                    self.format
+                        .number(joules * 1000f64 / durations.len() as f64)
+                ),
+            );
+        }
+        #[cfg(feature = "jemalloc")]
+        {
+            if let Some(jemalloc) = jemalloc {
+                self.report(Level::Info, &jemalloc.with_format(self.format).to_string());
+            }
+        }
+        #[cfg(feature = "rayon-stats")]
+        {
+            if scheduler.jobs > 0 {
+                self.report(
+                    Level::Info,
+                    &format!(
+                        "{} ({} jobs per iteration)",
+                        scheduler.with_format(self.format),
+                        self.format
+                            .number(scheduler.jobs as f64 / durations.len() as f64)
+                    ),
+                );
+            }
+        }
+        if let Some(peak_rss) = peak_rss {
+            self.report(Level::Info, &format!("Peak RSS: {} bytes", peak_rss));
+        }
+        #[cfg(feature = "alloc-counter")]
+        {
+            if AllocationStats::is_counting() {
+                self.report(
+                    Level::Info,
+                    &format!(
+                        "Allocations: {} per iteration ({} bytes), peak {} bytes live",
+                        self.format
+                            .number(allocations.allocations as f64 / durations.len() as f64),
+                        self.format
+                            .number(allocations.allocated_bytes as f64 / durations.len() as f64),
+                        self.format.count(allocations.peak_live_bytes)
+                    ),
+                );
+            }
+        }
+        if self.report_cpu_time {
+            self.report(
+                Level::Info,
+                &format!(
+                    "CPU time: {} ({}x wall time)",
+                    cpu_durations.with_format(self.format),
+                    self.format.number(
+                        cpu_durations.average().as_nanos() as f64
+                            / durations.average().as_nanos() as f64
+                    )
+                ),
+            );
+        }
+        if let Some(drift) = statistics::detect_drift(&durations) {
+            self.report(Level::Warning, &format!("The durations drifted by {}{}% during the run (Mann-Kendall z = {}). The average is not representative.", if drift.relative_change >= 0f64 { "+" } else { "" }, self.format.number(drift.relative_change * 100f64), self.format.number(drift.z)));
+        }
+        if durations.average() < self.resolution * 10 {
+            self.report(Level::Warning, &format!("The iteration time is within 10x of the clock resolution ({}). Consider batching several calls per iteration.", self.format.duration(self.resolution)));
+        }
+        if let Some(cycles_per_nanosecond) = self.clock.cycles_per_nanosecond() {
+            self.report(
+                Level::Info,
+                &format!(
+                    "Cycles: {}",
+                    self.format
+                        .number(durations.average().as_nanos() as f64 * cycles_per_nanosecond)
+                ),
+            );
+        }
+        let metrics = metrics.map(|metrics| metrics.borrow().clone());
+        if let Some(metrics) = &metrics {
+            if !metrics.is_empty() {
+                self.report(
+                    Level::Info,
+                    &format!("Metrics: {}", metrics.with_format(self.format)),
+                );
+            }
+        }
+        if isolation_target.is_some() {
+            self.end_bench(name);
+            isolation::report(name, &durations);
+            std::process::exit(0);
+        }
+        if let Some(cache) = &mut self.result_cache {
+            if let Err(e) = cache.insert(name, &durations) {
+                self.report(
+                    Level::Warning,
+                    &format!("Failed to write the result cache: {}", e),
+                );
+            }
+        }
+        self.record(name, durations, metrics.as_ref());
+
+        self
+    }
+
+    /// Writes the result of a benchmark to the output file
+    fn write_result(&mut self, name: &str, durations: &BenchVec, metrics: Option<&Metrics>) {
+        let format = &self.format;
+        if let Some(writer) = &mut self.raw_writer {
+            let _ = writer.write_all(aggregate::format_line(name, durations).as_bytes());
+        }
+        if let Some(writer) = &mut self.writer {
+            let _ = writer.write_all(
+                format!(
+                    "{}\t{}ns\t{}ns\t{}\t{}\n",
+                    name,
+                    durations.average().as_nanos(),
+                    format.exported(durations.standard_deviation()),
+                    metrics
+                        .map(|m| m.to_column_with(format))
+                        .unwrap_or_default(),
+                    self.annotations
+                        .get(name)
+                        .map(|notes| notes.join("; ").replace(['\t', '\n'], " "))
+                        .unwrap_or_default()
+                )
+                .as_bytes(),
+            );
+        }
+    }
+
+    /// Runs a benchmark in a child process and prints the collected result
+    fn bench_isolated(&mut self, name: &str) -> &mut Self {
+        self.start_bench(name);
+        match isolation::run(name) {
+            Ok(durations) => {
+                self.report_result(name, &durations);
+                self.record(name, durations, None);
+            }
+            Err(e) => {
+                self.report(
+                    Level::Warning,
+                    &format!("Failed to run the benchmark in a child process: {}", e),
+                );
+                self.end_bench(name);
+            }
+        }
+
+        self
+    }
+
+    /// Runs a benchmark under cachegrind and prints the counted events
+    /// without the events of a run where no benchmark was selected
+    fn bench_cachegrind(&mut self, name: &str) -> &mut Self {
+        self.start_bench(name);
+        match cachegrind::run_baseline(name)
+            .and_then(|baseline| cachegrind::run(name).map(|stats| (stats, baseline)))
+        {
+            Ok((stats, baseline)) => match stats.since(&baseline) {
+                Some(counts) => self.report(Level::Info, &counts.to_string()),
+                None => {
+                    self.report(
+                        Level::Warning,
+                        "The baseline counted more events than the benchmark, so the counts include the baseline.",
+                    );
+                    self.report(Level::Info, &stats.to_string());
+                }
+            },
+            Err(e) => self.report(Level::Warning, &format!("Failed to run cachegrind: {}", e)),
+        }
+        self.end_bench(name);
+
+        self
+    }
+
+    /// Attaches perf to the process and enables it if profiles are recorded
+    #[cfg(target_os = "linux")]
+    fn start_perf_record(&mut self, name: &str) -> Option<PerfRecorder> {
+        let dir = self.perf_record_dir.as_ref()?;
+        let file_name = file_stem(name);
+        let path = dir.join(format!("perf.{}.data", file_name));
+        let recorder = std::fs::create_dir_all(dir)
+            .and_then(|_| PerfRecorder::start(&path))
+            .and_then(|mut recorder| {
+                recorder.enable()?;
+                Ok(recorder)
+            });
+        match recorder {
+            Ok(recorder) => Some(recorder),
+            Err(e) => {
+                self.report(
+                    Level::Warning,
+                    &format!("Failed to start perf record: {}", e),
+                );
+                None
+            }
+        }
+    }
+
+    /// Stops perf after the iterations and attaches the profile to the benchmark
+    #[cfg(target_os = "linux")]
+    fn finish_perf_record(&mut self, name: &str, mut recorder: PerfRecorder) {
+        match recorder.disable().and_then(|_| recorder.finish()) {
+            Ok(path) => {
+                self.annotate(name, &format!("perf record: {}", path.display()));
+                self.perf_records.insert(name.to_string(), path);
+            }
+            Err(e) => self.report(
+                Level::Warning,
+                &format!("Failed to record the profile with perf: {}", e),
+            ),
+        }
+    }
+
+    /// Runs a benchmark under massif if it's selected and attaches
+    /// the peak heap snapshot to it
+    fn snapshot_massif(&mut self, name: &str) {
+        let dir = match &self.massif {
+            Some((dir, names)) if names.is_empty() || names.iter().any(|n| n == name) => {
+                dir.clone()
+            }
+            _ => return,
+        };
+        match massif::run(name, &dir) {
+            Ok(snapshot) => {
+                self.annotate(name, &format!("massif: {}", snapshot));
+                self.massif_snapshots.insert(name.to_string(), snapshot);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                self.report(
+                    Level::Note,
+                    "valgrind is not available, no massif snapshots are made",
+                );
+                self.massif = None;
+            }
+            Err(e) => self.report(
+                Level::Warning,
+                &format!("Failed to run massif for {}: {}", name, e),
+            ),
+        }
+    }
+
+    /// Runs a single iteration of a closure and records its durations
+    fn sample<T, P, PR, F, C, CR>(
+        &mut self,
+        name: &str,
+        prepare: &mut P,
+        func: &mut F,
+        cleanup: &mut C,
+        samples: &mut Samples,
+    ) where
+        P: FnMut() -> PR,
+        PR: HookOutcome,
+        F: FnMut() -> T,
+        C: FnMut() -> CR,
+        CR: HookOutcome,
+    {
+        // every sample is a zone named after the benchmark and a frame in Tracy
+        #[cfg(feature = "tracy")]
+        let zone = self
+            .tracy
+            .clone()
+            .span_alloc(Some(name), "sample", file!(), line!(), 0);
+        if let Some(e) = prepare().failure() {
+            self.report(Level::Warning, &format!("The prepare hook failed: {}", e));
+        }
+        if let Some(buffer) = &mut self.cache_thrash_buffer {
+            cache::thrash(buffer);
+        }
+        for reporter in &mut self.reporters {
+            reporter.on_sample_start(name);
+        }
+        let cpu_clock = ProcessCpuClock::new();
+        let cpu_start = if self.report_cpu_time {
+            Some(cpu_clock.now())
+        } else {
+            None
+        };
+        #[cfg(all(feature = "perf", target_os = "linux"))]
+        if let Some(counters) = &mut self.perf_counters {
+            let _ = counters.start();
+        }
+        let start = self.clock.now();
+        func();
+        samples.durations.push(self.clock.elapsed(start));
+        #[cfg(all(feature = "perf", target_os = "linux"))]
+        if let Some(counters) = &mut self.perf_counters {
+            if let Ok(counts) = counters.stop() {
+                samples.perf_counts += counts;
+            }
+        }
+        if let Some(cpu_start) = cpu_start {
+            samples.cpu_durations.push(cpu_clock.elapsed(cpu_start));
+        }
+        if let Some(&duration) = samples.durations.inner.last() {
+            for reporter in &mut self.reporters {
+                reporter.on_sample(name, duration);
+            }
+        }
+        if let Some(e) = cleanup().failure() {
+            self.report(Level::Warning, &format!("The cleanup hook failed: {}", e));
+        }
+        #[cfg(feature = "tracy")]
+        {
+            drop(zone);
+            self.tracy.frame_mark();
+        }
+        if self.progress && self.iterations == 0 {
+            let elapsed = samples.started.elapsed();
+            for reporter in &mut self.reporters {
+                reporter.on_statistics(name, &samples.durations, elapsed);
+            }
+        }
+        if self.progress {
+            let total = if self.iterations > 0 {
+                self.iterations
+            } else {
+                self.max_auto_iterations
+            };
+            let elapsed = samples.started.elapsed();
+            for reporter in &mut self.reporters {
+                reporter.on_progress(name, samples.durations.len(), total, elapsed);
+            }
+        }
+    }
+
+    /// Returns if the iterations of the benchmark run in this process. They don't
+    /// in child processes that target another benchmark or run under cachegrind
+    /// or massif, and not in the parent if they are delegated to a child process.
+    fn measures_here(&self, name: &str) -> bool {
+        if cachegrind::target().is_some() || massif::target().is_some() {
+            return false;
+        }
+        match isolation::target() {
+            Some(target) => target == name,
+            None => !self.isolated && !self.cachegrind,
+        }
+    }
+
+    /// Benchmarks an external command by spawning it a configured number of times
+    /// after some unmeasured warmup runs. The output of the command is discarded.
+    #[track_caller]
+    pub fn bench_command(&mut self, name: &str, mut command: Command) -> &mut Self {
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if !self.measures_here(name) {
+            return self.bench(name, || command.status());
+        }
+        match command.status() {
+            Ok(status) if !status.success() => self.report(
+                Level::Warning,
+                &format!("The command of {} exited with {}", name, status),
+            ),
+            Ok(_) => {}
+            Err(e) => {
+                self.report(
+                    Level::Warning,
+                    &format!("Failed to run the command of {}: {}", name, e),
+                );
+                return self;
+            }
+        }
+        for _ in 1..self.command_warmup {
+            let _ = command.status();
+        }
+
+        self.bench(name, || command.status())
+    }
+
+    /// Benchmarks a closure after an unreported pilot run that determines the
+    /// number of iterations needed to detect the given difference of averages
+    /// with a significance level of 5% and a power of 80%.
+    /// The number of iterations is limited by the maximum number of iterations
+    /// and at least one iteration is run.
+    #[track_caller]
+    pub fn bench_with_pilot<T, F: FnMut() -> T>(
+        &mut self,
+        name: &str,
+        pilot_iterations: usize,
+        difference: Duration,
+        mut func: F,
+    ) -> &mut Self {
+        if !self.measures_here(name) {
+            return self.bench(name, func);
+        }
+        let mut pilot = BenchVec::new();
+        for _ in 0..pilot_iterations.max(2) {
+            let start = self.clock.now();
+            func();
+            pilot.push(self.clock.elapsed(start));
+        }
+        let required = statistics::required_iterations(&pilot, difference, 0.05, 0.8)
+            .min(self.max_auto_iterations)
+            .max(1);
+        let iterations = self.iterations;
+        self.report(
+            Level::Note,
+            &format!(
+                "Pilot: {} iterations required to detect a difference of {}",
+                self.format.count(required),
+                self.format.duration(difference)
+            ),
+        );
+        self.iterations = required;
+        self.bench(name, func);
+        self.iterations = iterations;
+
+        self
+    }
+
+    /// Benchmarks a closure once per given thread count and reports
+    /// the results as a scaling table. The closure receives the thread count
+    /// it should use for the current measurement.
+    #[track_caller]
+    pub fn bench_scaling<T, F>(&mut self, name: &str, threads: &[usize], mut func: F) -> &mut Self
+    where
+        F: FnMut(usize) -> T,
+    {
+        self.run_scaling(ScalingResult::new(name), threads, None, |thread_count| {
+            func(thread_count)
+        })
+    }
+
+    /// Benchmarks a closure for each of the given thread counts where every
+    /// point of the sweep runs inside a dedicated rayon thread pool of that size
+    #[track_caller]
+    pub fn bench_scaling_in_pools<T, F>(
+        &mut self,
+        name: &str,
+        threads: &[usize],
+        mut func: F,
+    ) -> &mut Self
+    where
+        F: FnMut(usize) -> T + Send,
+        T: Send,
+    {
+        let pools = self.thread_pools(name, threads);
+        let threads = pools.iter().map(|(t, _)| *t).collect::<Vec<usize>>();
+        self.run_scaling(ScalingResult::new(name), &threads, None, |thread_count| {
+            let (_, pool) = pools.iter().find(|(t, _)| *t == thread_count).unwrap();
+            pool.install(|| func(thread_count))
+        })
+    }
+
+    /// Benchmarks a closure for each of the given thread counts inside a
+    /// dedicated rayon thread pool whose workers are pinned to the CPUs
+    /// with the given placement, so filling one socket first can be
+    /// compared to spreading the threads across the sockets. Only the CPUs
+    /// of the affinity mask of the process are used. Thread counts that
+    /// can't be pinned are skipped with a warning.
+    #[track_caller]
+    pub fn bench_scaling_with_placement<T, F>(
+        &mut self,
+        name: &str,
+        threads: &[usize],
+        placement: Placement,
+        mut func: F,
+    ) -> &mut Self
+    where
+        F: FnMut(usize) -> T + Send,
+        T: Send,
+    {
+        let topology = CpuTopology::detect();
+        let topology = match affinity::current_affinity() {
+            Ok(allowed) => topology.restricted_to(&allowed),
+            Err(_) => topology,
+        };
+        let mut pools = Vec::new();
+        for thread_count in self.valid_thread_counts(name, threads) {
+            match affinity::pinned_thread_pool(&topology.placement(placement, thread_count)) {
+                Ok(pool) => pools.push((thread_count, pool)),
+                Err(e) => self.report(
+                    Level::Warning,
+                    &format!(
+                        "Skipped {} threads in the sweep of {}, they can't be pinned with the {} placement: {}",
+                        thread_count, name, placement, e
+                    ),
+                ),
+            }
+        }
+        let threads = pools.iter().map(|(t, _)| *t).collect::<Vec<usize>>();
+        let mut result = ScalingResult::new(name);
+        result.set_placement(placement);
+        self.run_scaling(result, &threads, None, |thread_count| {
+            let (_, pool) = pools.iter().find(|(t, _)| *t == thread_count).unwrap();
+            pool.install(|| func(thread_count))
+        })
+    }
+
+    /// Runs a pinned scaling sweep for each of the given placements, so the
+    /// placement becomes a dimension of the sweep. Every sweep is recorded
+    /// as a separate scaling result with its placement.
+    #[track_caller]
+    pub fn bench_scaling_placements<T, F>(
+        &mut self,
+        name: &str,
+        threads: &[usize],
+        placements: &[Placement],
+        mut func: F,
+    ) -> &mut Self
+    where
+        F: FnMut(usize, Placement) -> T + Send,
+        T: Send,
+    {
+        for &placement in placements {
+            self.bench_scaling_with_placement(name, threads, placement, |thread_count| {
+                func(thread_count, placement)
+            });
+        }
+
+        self
+    }
+
+    /// Benchmarks a closure for each of the given thread counts inside a
+    /// dedicated rayon thread pool and counts cycles and cache misses on every
+    /// worker thread, so coherence traffic and false sharing can be attributed
+    /// to the threads that cause it. The counters of a pool only run while
+    /// its thread count is measured.
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    #[track_caller]
+    pub fn bench_scaling_with_perf_counters<T, F>(
+        &mut self,
+        name: &str,
+        threads: &[usize],
+        mut func: F,
+    ) -> &mut Self
+    where
+        F: FnMut(usize) -> T + Send,
+        T: Send,
+    {
+        let mut pools = self
+            .thread_pools(name, threads)
+            .into_iter()
+            .map(|(thread_count, pool)| {
+                let counters = pool
+                    .broadcast(|_| PerfCounters::new())
+                    .into_iter()
+                    .filter_map(|counters| counters.ok())
+                    .collect::<Vec<PerfCounters>>();
+                (thread_count, pool, counters)
+            })
+            .collect::<Vec<(usize, ThreadPool, Vec<PerfCounters>)>>();
+        if pools
+            .iter()
+            .any(|(thread_count, _, counters)| counters.len() < *thread_count)
+        {
+            self.report(
+                Level::Warning,
+                "Failed to open the performance counters of some worker threads",
+            );
+        }
+        let mut result = ScalingResult::new(name);
+        for (thread_count, pool, counters) in &mut pools {
+            for counters in counters.iter_mut() {
+                let _ = counters.start();
+            }
+            self.measure_scaling_point(&mut result, *thread_count, None, &mut |thread_count| {
+                pool.install(|| func(thread_count))
+            });
+            let counts = counters
+                .iter_mut()
+                .filter_map(|counters| counters.stop().ok())
+                .collect::<Vec<PerfCounts>>();
+            result.thread_counters.insert(*thread_count, counts);
+        }
+        let threads = pools.iter().map(|(t, _, _)| *t).collect::<Vec<usize>>();
+        self.report_scaling(result, &threads);
+        let report = self
+            .scaling_results
+            .last()
+            .unwrap()
+            .thread_counters_report_with(&self.format);
+        self.report(Level::Info, report.trim_end());
+
+        self
+    }
+
+    /// Benchmarks a closure for each of the given thread counts with a
+    /// problem size that grows proportionally to the thread count.
+    /// The closure receives the thread count and the problem size
+    /// `base_size * threads` and the scaled speedup of Gustafson's law is reported.
+    #[track_caller]
+    pub fn bench_weak_scaling<T, F>(
+        &mut self,
+        name: &str,
+        threads: &[usize],
+        base_size: usize,
+        mut func: F,
+    ) -> &mut Self
+    where
+        F: FnMut(usize, usize) -> T,
+    {
+        self.bench_scaling_with_mode(
+            name,
+            threads,
+            ScalingMode::Weak { base_size },
+            |thread_count, size| func(thread_count, size.unwrap()),
+        )
+    }
+
+    /// Benchmarks a closure for each of the given thread counts in the given regime.
+    /// The closure receives the thread count and the problem size, which is
+    /// only set for weak scaling, so one definition can be run in both regimes.
+    #[track_caller]
+    pub fn bench_scaling_with_mode<T, F>(
+        &mut self,
+        name: &str,
+        threads: &[usize],
+        mode: ScalingMode,
+        mut func: F,
+    ) -> &mut Self
+    where
+        F: FnMut(usize, Option<usize>) -> T,
+    {
+        self.run_scaling(
+            ScalingResult::with_mode(name, mode),
+            threads,
+            None,
+            |thread_count| func(thread_count, mode.problem_size(thread_count)),
+        )
+    }
+
+    /// Benchmarks a closure for each of the given thread counts inside a
+    /// dedicated rayon thread pool of that size. The workers report their
+    /// busy time via the given handle, e.g. by wrapping the body of a
+    /// parallel-for in `ThreadTimes::measure`, and the load imbalance
+    /// of each point is reported.
+    #[track_caller]
+    pub fn bench_scaling_with_thread_times<T, F>(
+        &mut self,
+        name: &str,
+        threads: &[usize],
+        mut func: F,
+    ) -> &mut Self
+    where
+        F: FnMut(usize, &ThreadTimes) -> T + Send,
+        T: Send,
+    {
+        let times = ThreadTimes::new();
+        let pools = self.thread_pools(name, threads);
+        let threads = pools.iter().map(|(t, _)| *t).collect::<Vec<usize>>();
+        self.run_scaling(
+            ScalingResult::new(name),
+            &threads,
+            Some(&times),
+            |thread_count| {
+                let (_, pool) = pools.iter().find(|(t, _)| *t == thread_count).unwrap();
+                pool.install(|| func(thread_count, &times))
+            },
+        )
+    }
+
+    /// Returns the thread counts of a sweep without 0, which no thread pool
+    /// can be built with, and warns if it was given
+    fn valid_thread_counts(&mut self, name: &str, threads: &[usize]) -> Vec<usize> {
+        if threads.contains(&0) {
+            self.report(
+                Level::Warning,
+                &format!(
+                    "Skipped 0 threads in the sweep of {}, it needs at least one thread",
+                    name
+                ),
+            );
+        }
+
+        threads.iter().copied().filter(|&t| t > 0).collect()
+    }
+
+    /// Builds a rayon thread pool with the given number of threads for a
+    /// benchmark or warns and returns None if the pool can't be built
+    fn thread_pool(&mut self, name: &str, threads: usize) -> Option<ThreadPool> {
+        match ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => Some(pool),
+            Err(e) => {
+                self.report(
+                    Level::Warning,
+                    &format!(
+                        "Skipped {} with {} threads, the rayon thread pool can't be built: {}",
+                        name, threads, e
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    /// Builds the rayon thread pools of a sweep, skipping the thread counts
+    /// whose pool can't be built
+    fn thread_pools(&mut self, name: &str, threads: &[usize]) -> Vec<(usize, ThreadPool)> {
+        self.valid_thread_counts(name, threads)
+            .into_iter()
+            .filter_map(|thread_count| {
+                self.thread_pool(name, thread_count)
+                    .map(|pool| (thread_count, pool))
+            })
+            .collect()
+    }
+
+    /// Runs a scaling sweep and reports the collected result
+    #[track_caller]
+    fn run_scaling<T, F>(
+        &mut self,
+        mut result: ScalingResult,
+        threads: &[usize],
+        thread_times: Option<&ThreadTimes>,
+        mut func: F,
+    ) -> &mut Self
+    where
+        F: FnMut(usize) -> T,
+    {
+        for &thread_count in threads {
+            self.measure_scaling_point(&mut result, thread_count, thread_times, &mut func);
+        }
+
+        self.report_scaling(result, threads)
+    }
+
+    /// Measures one thread count of a scaling sweep and adds it to the result
+    #[track_caller]
+    fn measure_scaling_point<T, F>(
+        &mut self,
+        result: &mut ScalingResult,
+        thread_count: usize,
+        thread_times: Option<&ThreadTimes>,
+        func: &mut F,
+    ) where
+        F: FnMut(usize) -> T,
+    {
+        let measured = self.measurements.len();
+        self.bench(
+            &format!("{} ({} threads)", result.name, thread_count),
+            || func(thread_count),
+        );
+        let times = thread_times.map(|times| times.take()).unwrap_or_default();
+        if self.measurements.len() > measured {
+            result.push_with_thread_times(
+                thread_count,
+                self.measurements.last().unwrap().clone(),
+                times,
+            );
+        }
+    }
+
+    /// Reports the result of a scaling sweep over the given thread counts and records it
+    fn report_scaling(&mut self, mut result: ScalingResult, threads: &[usize]) -> &mut Self {
+        let name = result.name.clone();
+        let topology = CpuTopology::detect();
+        let regime = match result.mode {
+            ScalingMode::Strong => "Strong scaling".to_string(),
+            ScalingMode::Weak { base_size } => {
+                format!("Weak scaling (base size {})", base_size)
+            }
+        };
+        self.report(Level::Heading, &format!("{} of {}", regime, name));
+        match result.placement {
+            Some(placement) => self.report(
+                Level::Note,
+                &format!("Machine: {}, {} placement", topology, placement),
+            ),
+            None => self.report(Level::Note, &format!("Machine: {}", topology)),
+        }
+        if threads
+            .iter()
+            .any(|&t| topology.occupancy(t) == Occupancy::Oversubscribed)
+        {
+            self.report(
+                Level::Note,
+                &format!(
+                    "Points with more than {} threads are oversubscribed",
+                    topology.logical_cpus()
+                ),
+            );
+        }
+        result.set_topology(topology);
+        if let Some(throughput) = self.throughput {
+            result.set_throughput(throughput);
+        }
+        self.report(
+            Level::Info,
+            result.with_format(self.format).to_string().trim_end(),
+        );
+        if let Some(fit) = result.amdahl_fit() {
+            self.report(Level::Info, &fit.with_format(self.format).to_string());
+        }
+        if let Some(report) = result.throughput_report_with(&self.format) {
+            self.report(Level::Info, &report);
+        }
+        if let Some(writer) = &mut self.scaling_writer {
+            let _ = writer.write_all(result.to_tsv_with(&self.format).as_bytes());
+        }
+        if let Some(dir) = &self.plot_dir {
+            let script = dir.join(format!("{}.gp", result.plot_name()));
+            let output = dir.join(format!("{}.svg", result.plot_name()));
+            if let Err(e) = std::fs::write(
+                &script,
+                result.to_gnuplot_with(&output.to_string_lossy(), &self.format),
+            ) {
+                self.report(
+                    Level::Warning,
+                    &format!(
+                        "Failed to write the plot script {}: {}",
+                        script.display(),
+                        e
+                    ),
+                );
+            }
+        }
+        self.scaling_results.push(result);
+
+        self
+    }
+
+    /// Returns the results of all scaling sweeps
+    pub fn scaling_results(&self) -> &[ScalingResult] {
+        &self.scaling_results
+    }
+
+    /// Returns the result of the last scaling sweep with the given name
+    pub fn scaling_result(&self, name: &str) -> Option<&ScalingResult> {
+        self.scaling_results.iter().rev().find(|r| r.name == name)
+    }
+
+    /// Asserts that the parallel efficiency of the last scaling sweep with the
+    /// given name is at least the given value for the given thread count,
+    /// so graded assignments can check that a solution actually scales.
+    /// Panics if the efficiency is lower or the thread count wasn't measured.
+    pub fn assert_efficiency_at_least(
+        &mut self,
+        name: &str,
+        threads: usize,
+        min: f64,
+    ) -> &mut Self {
+        let efficiency = self
+            .scaling_result(name)
+            .and_then(|result| result.efficiency(threads))
+            .unwrap_or_else(|| {
+                panic!(
+                    "no efficiency of {} with {} threads was measured",
+                    name, threads
+                )
+            });
+        assert!(
+            efficiency >= min,
+            "the efficiency of {} with {} threads is {} but at least {} is required",
+            name,
+            threads,
+            self.format.number(efficiency),
+            self.format.number(min)
+        );
+
+        self
+    }
+
+    /// Asserts that the speedup of the last scaling sweep with the given name
+    /// is at least the given value for the given thread count.
+    /// Panics if the speedup is lower or the thread count wasn't measured.
+    pub fn assert_speedup_at_least(&mut self, name: &str, threads: usize, min: f64) -> &mut Self {
+        let speedup = self
+            .scaling_result(name)
+            .and_then(|result| result.speedup(threads))
+            .unwrap_or_else(|| {
+                panic!(
+                    "no speedup of {} with {} threads was measured",
+                    name, threads
+                )
+            });
+        assert!(
+            speedup >= min,
+            "the speedup of {} with {} threads is {} but at least {} is required",
+            name,
+            threads,
+            self.format.number(speedup),
+            self.format.number(min)
+        );
+
+        self
+    }
+
+    /// Warns that the multi-threaded modes measure the wall time
+    /// instead of the clock set with `set_clock`
+    fn warn_wall_clock(&mut self) {
+        if self.custom_clock {
+            self.report(
+                Level::Warning,
+                "The threads are measured in wall time, the clock set with set_clock is only used by single-threaded benchmarks.",
+            );
+        }
+    }
+
+    /// Benchmarks a closure that is called simultaneously on the given number
+    /// of threads to measure locks, atomics or concurrent queues under contention.
+    /// Every thread calls the closure the configured number of times or the
+    /// maximum number of iterations in auto mode. The threads wait on a barrier
+    /// until all of them are spawned, so the spawn time isn't measured.
+    /// The aggregate throughput and the latency distribution of all calls are
+    /// reported together with the average latency of each thread.
+    /// The threads measure the wall time, a clock set with `set_clock` isn't used.
+    #[track_caller]
+    pub fn bench_concurrent<T, F>(&mut self, name: &str, threads: usize, func: F) -> &mut Self
+    where
+        F: Fn() -> T + Sync,
+    {
+        self.location = Some(Location::caller());
+        if cachegrind::target().is_some()
+            || massif::target().is_some()
+            || isolation::target().is_some()
+        {
+            return self;
+        }
+        let iterations = if self.iterations > 0 {
+            self.iterations
+        } else {
+            self.max_auto_iterations
+        };
+        let threads = threads.max(1);
+        self.start_bench(name);
+        self.warn_wall_clock();
+        let barrier = Barrier::new(threads);
+        let runs = concurrent::run_indexed(self.spawner.as_ref(), threads, |_| {
+            barrier.wait();
+            let start = Instant::now();
+            let durations = (0..iterations)
+                .map(|_| {
+                    let start = Instant::now();
+                    black_box(func());
+                    start.elapsed()
+                })
+                .collect::<Vec<Duration>>();
+            (start, Instant::now(), durations)
+        });
+        let start = runs.iter().map(|(start, _, _)| *start).min().unwrap();
+        let end = runs.iter().map(|(_, end, _)| *end).max().unwrap();
+        let per_thread = runs
+            .into_iter()
+            .map(|(_, _, durations)| BenchVec::from_vec(&durations))
+            .collect::<Vec<BenchVec>>();
+        let result = ConcurrentResult::new(name, per_thread, end.duration_since(start));
+        match self.report_mode {
+            ReportMode::Average => self.report(
+                Level::Info,
+                &format!("Latency: {}", result.latencies.with_format(self.format)),
+            ),
+            ReportMode::TailLatency => self.report(
+                Level::Info,
+                &format!(
+                    "Latency: {}",
+                    result.latencies.tail_latencies_with(&self.format)
+                ),
+            ),
+        }
+        self.report(
+            Level::Info,
+            &format!("Throughput: {}", result.with_format(self.format)),
+        );
+        self.report(
+            Level::Info,
+            &format!("Per thread: {}", result.thread_breakdown_with(&self.format)),
+        );
+        self.record(name, result.latencies.clone(), None);
+        self.concurrent_results.push(result);
+
+        self
+    }
+
+    /// Reports the results of several processes or MPI ranks with the
+    /// statistics of each rank and of all ranks combined.
+    /// The combined results are written to the output files like
+    /// the results of benchmarks that were run by this Bencher.
+    pub fn report_ranks(&mut self, results: &RankResults) -> &mut Self {
+        for name in results.names() {
+            let ranks = results.ranks(name).unwrap();
+            self.report(Level::Heading, &format!("{} ({} ranks)", name, ranks.len()));
+            for (rank, durations) in ranks {
+                self.report(
+                    Level::Info,
+                    &format!("Rank {}: {}", rank, durations.with_format(self.format)),
+                );
+            }
+            let combined = results.combined(name).unwrap();
+            self.report_result(name, &combined);
+            if let Some(rank) = results.slowest_rank(name) {
+                self.report(
+                    Level::Info,
+                    &format!(
+                        "Slowest rank: {} ({} average)",
+                        rank,
+                        self.format.duration(ranks[&rank].average())
+                    ),
+                );
+            }
+            self.record(name, combined, None);
+        }
+
+        self
+    }
+
+    /// Returns the results of all concurrent benchmarks
+    pub fn concurrent_results(&self) -> &[ConcurrentResult] {
+        &self.concurrent_results
+    }
+
+    /// Runs probe benchmarks of mutexes, read-write locks, atomic
+    /// compare-and-swap and a channel ping-pong uncontended and contended
+    /// by the given number of threads, so the cost of synchronization on the
+    /// machine is known before interpreting other results
+    #[track_caller]
+    pub fn bench_sync_probes(&mut self, threads: usize) -> &mut Self {
+        probes::run(self, threads);
+
+        self
+    }
+
+    /// Benchmarks a closure under a fixed load. A load generator issues
+    /// operations at the given rate per second to the given number of worker
+    /// threads, which call the closure once per operation. The response time
+    /// is measured from the intended start of an operation, so operations that
+    /// queue behind slow ones aren't omitted from the latency distribution.
+    /// The configured number of iterations or the maximum number of iterations
+    /// in auto mode is issued. Panics if the rate isn't a positive finite
+    /// number or no operation would be issued. The response times are
+    /// measured in wall time, a clock set with `set_clock` isn't used.
+    #[track_caller]
+    pub fn bench_open_loop<T, F>(
+        &mut self,
+        name: &str,
+        rate: f64,
+        workers: usize,
+        func: F,
+    ) -> &mut Self
+    where
+        F: Fn() -> T + Sync,
+    {
+        self.location = Some(Location::caller());
+        assert!(
+            rate > 0f64 && rate.is_finite(),
+            "the rate of {} must be a positive number of operations per second, but is {}",
+            name,
+            rate
+        );
+        let operations = if self.iterations > 0 {
+            self.iterations
+        } else {
+            self.max_auto_iterations
+        };
+        assert!(
+            operations > 0,
+            "the open loop benchmark {} needs at least one operation",
+            name
+        );
+        if cachegrind::target().is_some()
+            || massif::target().is_some()
+            || isolation::target().is_some()
+        {
+            return self;
+        }
+        let workers = workers.max(1);
+        self.start_bench(name);
+        self.warn_wall_clock();
+        let (sender, receiver) = mpsc::channel::<Instant>();
+        let sender = Mutex::new(Some(sender));
+        let receiver = Mutex::new(receiver);
+        let barrier = Barrier::new(workers + 1);
+        let runs = concurrent::run_indexed(self.spawner.as_ref(), workers + 1, |index| {
+            barrier.wait();
+            let mut samples = Vec::new();
+            if index == workers {
+                let sender = sender.lock().unwrap().take().unwrap();
+                let start = Instant::now();
+                for operation in 0..operations {
+                    let intended = start + Duration::from_secs_f64(operation as f64 / rate);
+                    let now = Instant::now();
+                    if intended > now {
+                        thread::sleep(intended - now);
+                    }
+                    let _ = sender.send(intended);
+                }
+                return (Some(start), samples);
+            }
+            loop {
+                let intended = match receiver.lock().unwrap().recv() {
+                    Ok(intended) => intended,
+                    Err(_) => break,
+                };
+                let start = Instant::now();
+                black_box(func());
+                let end = Instant::now();
+                samples.push((end.duration_since(intended), end - start, end));
+            }
+            (None, samples)
+        });
+        let start = runs.iter().find_map(|(start, _)| *start).unwrap();
+        let samples = runs
+            .into_iter()
+            .flat_map(|(_, samples)| samples)
+            .collect::<Vec<(Duration, Duration, Instant)>>();
+        let end = samples
+            .iter()
+            .map(|(_, _, end)| *end)
+            .max()
+            .unwrap_or(start);
+        let result = OpenLoopResult {
+            name: name.to_string(),
+            rate,
+            response_times: BenchVec::from_vec(
+                &samples
+                    .iter()
+                    .map(|(r, _, _)| *r)
+                    .collect::<Vec<Duration>>(),
+            ),
+            service_times: BenchVec::from_vec(
+                &samples
+                    .iter()
+                    .map(|(_, s, _)| *s)
+                    .collect::<Vec<Duration>>(),
+            ),
+            wall_time: end.duration_since(start),
+        };
+        self.report(
+            Level::Info,
+            &format!(
+                "Response time: {}",
+                result.response_times.tail_latencies_with(&self.format)
+            ),
+        );
+        self.report(
+            Level::Info,
+            &format!(
+                "Service time: {} (queueing {} average)",
+                result.service_times.with_format(self.format),
+                self.format.duration(result.queueing_time())
+            ),
+        );
+        self.report(
+            Level::Info,
+            &format!("Load: {}", result.with_format(self.format)),
+        );
+        if result.achieved_rate() < rate * 0.95 {
+            self.report(
+                Level::Warning,
+                "The targeted rate wasn't sustained. The system is overloaded.",
+            );
+        }
+        self.record(name, result.response_times.clone(), None);
+        self.open_loop_results.push(result);
+
+        self
+    }
+
+    /// Returns the results of all open loop benchmarks
+    pub fn open_loop_results(&self) -> &[OpenLoopResult] {
+        &self.open_loop_results
+    }
+
+    /// Benchmarks a closure once per given input size and fits the durations
+    /// against the complexity models O(n), O(n log n) and O(n²).
+    /// The closure receives the input size it should process.
+    #[track_caller]
+    pub fn bench_sizes<T, F>(&mut self, name: &str, sizes: &[usize], mut func: F) -> &mut Self
+    where
+        F: FnMut(usize) -> T,
+    {
+        let mut result = SizeSweepResult::new(name);
+        for &size in sizes {
+            let measured = self.measurements.len();
+            self.bench(&format!("{} (n = {})", name, size), || func(size));
+            if self.measurements.len() > measured {
+                result.push(size, self.measurements.last().unwrap().clone());
+            }
+        }
+        self.report(Level::Heading, &format!("Size sweep of {}", name));
+        self.report(
+            Level::Info,
+            result.with_format(self.format).to_string().trim_end(),
+        );
+        if let Some(best) = result.best_fit() {
+            for fit in result.fits() {
+                self.report(Level::Info, &fit.with_format(self.format).to_string());
+            }
+            self.report(Level::Info, &format!("Best fit: {}", best.model));
+        }
+        self.size_results.push(result);
+
+        self
+    }
+
+    /// Returns the results of all size sweeps
+    pub fn size_results(&self) -> &[SizeSweepResult] {
+        &self.size_results
+    }
+
+    /// Benchmarks a closure for every combination of the given thread counts and
+    /// problem sizes and reports the efficiency matrix together with the
+    /// iso-efficiency curves, the problem size each thread count needs to keep
+    /// the efficiency constant. A single thread is always measured as baseline.
+    /// The closure receives the thread count and the problem size.
+    #[track_caller]
+    pub fn bench_iso_efficiency<T, F>(
+        &mut self,
+        name: &str,
+        threads: &[usize],
+        sizes: &[usize],
+        mut func: F,
+    ) -> &mut Self
+    where
+        F: FnMut(usize, usize) -> T,
+    {
+        let mut thread_counts = threads.iter().copied().collect::<BTreeSet<usize>>();
+        thread_counts.insert(1);
+        let thread_counts = thread_counts.into_iter().collect::<Vec<usize>>();
+        let mut result = IsoEfficiencyResult::new(name, &thread_counts, sizes);
+        for size in result.sizes.clone() {
+            for thread_count in result.threads.clone() {
+                let measured = self.measurements.len();
+                self.bench(
+                    &format!("{} (n = {}, {} threads)", name, size, thread_count),
+                    || func(thread_count, size),
+                );
+                if self.measurements.len() > measured {
+                    result.push(
+                        thread_count,
+                        size,
+                        self.measurements.last().unwrap().clone(),
+                    );
+                }
+            }
+        }
+        self.report(Level::Heading, &format!("Efficiency of {}", name));
+        self.report(
+            Level::Info,
+            result.with_format(self.format).to_string().trim_end(),
+        );
+        for &efficiency in &[0.9, 0.7, 0.5] {
+            let curve = result
+                .iso_efficiency(efficiency)
+                .into_iter()
+                .map(|(threads, size)| match size {
+                    Some(size) => format!("{}: {}", threads, self.format.number(size)),
+                    None => format!("{}: -", threads),
+                })
+                .collect::<Vec<String>>();
+            self.report(
+                Level::Info,
+                &format!("Iso-efficiency {}: {}", efficiency, curve.join(", ")),
+            );
+        }
+        if let Some(dir) = &self.plot_dir {
+            let matrix = dir.join(format!("{}.tsv", result.plot_name()));
+            if let Err(e) = std::fs::write(&matrix, result.to_matrix_with(&self.format)) {
+                self.report(
+                    Level::Warning,
+                    &format!(
+                        "Failed to write the efficiency matrix {}: {}",
+                        matrix.display(),
+                        e
+                    ),
+                );
+            }
+        }
+        self.iso_efficiency_results.push(result);
+
+        self
+    }
+
+    /// Returns the results of all iso-efficiency sweeps
+    pub fn iso_efficiency_results(&self) -> &[IsoEfficiencyResult] {
+        &self.iso_efficiency_results
+    }
+
+    /// Benchmarks a closure that operates on a fixture.
+    /// The fixture is created once before all iterations and torn down
+    /// afterwards, both outside of the measured region.
+    #[track_caller]
+    pub fn bench_with_fixture<X, T, S, F, D>(
+        &mut self,
+        name: &str,
+        setup: S,
+        mut func: F,
+        teardown: D,
+    ) -> &mut Self
+    where
+        S: FnOnce() -> X,
+        F: FnMut(&mut X) -> T,
+        D: FnOnce(X),
+    {
+        let mut fixture = setup();
+        self.bench(name, || func(&mut fixture));
+        teardown(fixture);
+
+        self
+    }
+
+    /// Compares the last two benchmarks and shows their distributions as box plots
+    /// If the number of benchmarks is below 2 it doesn't do anything
+    pub fn compare(&mut self) -> &mut Self {
+        if self.measurements.len() > 1 {
+            let left = self.measurements.last().unwrap();
+            let right = self.measurements.get(self.measurements.len() - 2).unwrap();
+            let diff = DurationDifference::with_thresholds(left, right, self.compare_thresholds);
+            let name = &self.names[self.names.len() - 1];
+            let previous = &self.names[self.names.len() - 2];
+            let plots = chart::box_plots(&[(previous, right), (name, left)], 60, &self.format);
+            for reporter in &mut self.reporters {
+                reporter.on_compare(name, previous, &diff);
+                reporter.on_message(Level::Info, &plots);
+            }
+        }
+
+        self
+    }
+
+    /// Starts a group the following benchmarks belong to until the group
+    /// is ended or another one is started. Starting a group with the name of
+    /// an existing one continues it.
+    pub fn begin_group(&mut self, name: &str) -> &mut Self {
+        let index = match self.groups.iter().position(|g| g.name == name) {
+            Some(index) => index,
+            None => {
+                self.groups.push(GroupSummary::new(name));
+                self.groups.len() - 1
+            }
+        };
+        self.group = Some(index);
+        self.report(Level::Heading, &format!("Group {}", name));
+
+        self
+    }
+
+    /// Ends the current group, so the following benchmarks don't belong to a group
+    pub fn end_group(&mut self) -> &mut Self {
+        self.group = None;
+
+        self
+    }
+
+    /// Returns the summaries of all groups
+    pub fn group_summaries(&self) -> &Vec<GroupSummary> {
+        &self.groups
+    }
+
+    /// Sets if the summary of `finish` contains a bar chart of the average
+    /// durations of all benchmarks scaled to the slowest one
+    pub fn set_summary_chart(&mut self, enabled: bool) -> &mut Self {
+        self.summary_chart = enabled;
+
+        self
+    }
+
+    /// Attaches an explanatory note like "uses the SIMD path" to a benchmark.
+    /// The notes are shown in the summary and passed to the reporters.
+    /// They're written to the output file if the benchmark is annotated
+    /// before it runs.
+    pub fn annotate(&mut self, name: &str, text: &str) -> &mut Self {
+        self.annotations
+            .entry(name.to_string())
+            .or_default()
+            .push(text.to_string());
+        for reporter in &mut self.reporters {
+            reporter.on_annotate(name, text);
+        }
+
+        self
+    }
+
+    /// Sets a directory that `profile_heap` writes the full DHAT profile of
+    /// each benchmark to, named after the benchmark, for the DHAT viewer.
+    /// The path of each profile is attached to its benchmark as note.
+    /// If set to None the profiles are written to the temporary directory
+    /// and removed after their hot spots are read.
+    #[cfg(feature = "heap-profiling")]
+    pub fn set_heap_profiles(&mut self, dir: Option<PathBuf>) -> &mut Self {
+        self.heap_profile_dir = dir;
+
+        self
+    }
+
+    /// Runs the closure once under the DHAT heap profiler and attaches the
+    /// total allocations and the program points that allocated the most
+    /// bytes to the benchmark as notes. The profiler only sees allocations
+    /// if `dhat::Alloc` is the global allocator of the binary.
+    #[cfg(feature = "heap-profiling")]
+    pub fn profile_heap<T, F: FnOnce() -> T>(&mut self, name: &str, func: F) -> &mut Self {
+        if cachegrind::target().is_some()
+            || massif::target().is_some()
+            || isolation::target().is_some()
+        {
+            return self;
+        }
+        let file_name = file_stem(name);
+        let path = match &self.heap_profile_dir {
+            Some(dir) => {
+                if let Err(e) = std::fs::create_dir_all(dir) {
+                    self.report(
+                        Level::Warning,
+                        &format!("Failed to create {}: {}", dir.display(), e),
+                    );
+                    return self;
+                }
+                dir.join(format!("dhat.{}.json", file_name))
+            }
+            None => std::env::temp_dir().join(format!(
+                "dhat.benchlib.{}.{}.json",
+                std::process::id(),
+                file_name
+            )),
+        };
+        let profiler = dhat::Profiler::builder().file_name(&path).build();
+        black_box(func());
+        let stats = dhat::HeapStats::get();
+        drop(profiler);
+        self.annotate(
+            name,
+            &format!(
+                "allocated {} bytes in {} blocks, at most {} bytes at once",
+                self.format.count(stats.total_bytes as usize),
+                self.format.count(stats.total_blocks as usize),
+                self.format.count(stats.max_bytes)
+            ),
+        );
+        let hot_spots = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|profile| heap::parse_hot_spots(&profile, HEAP_HOT_SPOTS));
+        if self.heap_profile_dir.is_some() {
+            self.annotate(name, &format!("heap profile: {}", path.display()));
+        } else {
+            let _ = std::fs::remove_file(&path);
+        }
+        match hot_spots {
+            Some(hot_spots) => {
+                for hot_spot in hot_spots {
+                    self.annotate(
+                        name,
+                        &format!("hot spot: {}", hot_spot.with_format(self.format)),
+                    );
+                }
+            }
+            None => self.report(
+                Level::Warning,
+                &format!("Failed to read the heap profile {}", path.display()),
+            ),
+        }
+
+        self
+    }
+
+    /// Returns the notes attached to a benchmark
+    pub fn annotations(&self, name: &str) -> &[String] {
+        self.annotations
+            .get(name)
+            .map(|notes| notes.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Writes the BMF output, prints the summary of each group and the
+    /// annotated benchmarks and tells all reporters that the benchmarks are finished, so they can
+    /// print summaries or finish their output
+    pub fn finish(&mut self) -> &mut Self {
+        if let Some(mut writer) = self.bmf_writer.take() {
+            let document = json::bmf_document(&self.results());
+            if let Err(e) = writeln!(writer, "{}", document).and_then(|_| writer.flush()) {
+                self.report(
+                    Level::Warning,
+                    &format!("Failed to write the BMF output: {}", e),
+                );
+            }
+        }
+        let mut summary = self
+            .groups
+            .iter()
+            .map(|g| g.with_format(self.format).to_string())
+            .collect::<Vec<String>>();
+        for (name, durations) in self.names.iter().zip(&self.measurements) {
+            if let Some(notes) = self.annotations.get(name) {
+                summary.push(format!(
+                    "{}: {} - {}",
+                    name,
+                    durations.with_format(self.format),
+                    notes.join("; ")
+                ));
+            }
+        }
+        if self.summary_chart && self.measurements.len() > 1 {
+            let averages = self
+                .names
+                .iter()
+                .zip(&self.measurements)
+                .map(|(name, durations)| (name.as_str(), durations.average()))
+                .collect::<Vec<(&str, Duration)>>();
+            summary.push(chart::bar_chart(&averages, 40, &self.format));
+        }
+        if !summary.is_empty() {
+            self.report(Level::Section, "Summary");
+            for line in summary {
+                self.report(Level::Info, &line);
+            }
+        }
+        if !self.warnings.is_empty() {
+            self.report(Level::Section, "Warnings");
+            for warning in &self.warnings {
+                for reporter in &mut self.reporters {
+                    reporter.on_message(Level::Warning, warning);
+                }
+            }
+        }
+        for reporter in &mut self.reporters {
+            reporter.on_finish();
+        }
+
+        self
+    }
+
+    /// Returns the names and durations of all recorded benchmarks
+    pub fn results(&self) -> Vec<(&str, &BenchVec)> {
+        self.names
+            .iter()
+            .map(|name| name.as_str())
+            .zip(&self.measurements)
+            .collect()
+    }
+
+    /// Returns the names of the benchmarks whose coefficient of variation
+    /// exceeds the given threshold, e.g. 0.05 for 5%
+    pub fn noisy_benchmarks(&self, threshold: f64) -> Vec<String> {
+        let mut noisy = Vec::new();
+        for (name, durations) in self.names.iter().zip(&self.measurements) {
+            if durations.coefficient_of_variation() > threshold && !noisy.contains(name) {
+                noisy.push(name.clone());
+            }
+        }
+
+        noisy
+    }
+
+    /// Re-runs the benchmarks whose coefficient of variation exceeds the
+    /// threshold and merges the new samples into their results. The Bencher
+    /// doesn't keep the benchmarked closures, so the given function is called
+    /// with the name of each noisy benchmark to run it again.
+    pub fn rerun_noisy<F>(&mut self, threshold: f64, mut rerun: F) -> &mut Self
+    where
+        F: FnMut(&mut Self, &str),
+    {
+        self.merging = true;
+        for name in self.noisy_benchmarks(threshold) {
+            self.report(Level::Note, &format!("Re-running {}", name));
+            rerun(self, &name);
+        }
+        self.merging = false;
+
+        self
+    }
+
+    /// Finishes the benchmarks like `finish`, but first offers to re-run the
+    /// noisy benchmarks with `rerun_noisy` if stdin and stderr are terminals.
+    /// The prompt is written to stderr, so it doesn't end up in the results
+    /// on stdout. On CI services or when stdin or stderr are redirected it
+    /// doesn't prompt.
+    pub fn finish_with_rerun_prompt<F>(&mut self, threshold: f64, rerun: F) -> &mut Self
+    where
+        F: FnMut(&mut Self, &str),
+    {
+        let noisy = self.noisy_benchmarks(threshold);
+        if !noisy.is_empty()
+            && io::stderr().is_terminal()
+            && io::stdin().is_terminal()
+            && !ci::is_ci()
+        {
+            self.report(
+                Level::Warning,
+                &format!(
+                    "The coefficient of variation of {} exceeds {}%",
+                    noisy.join(", "),
+                    self.format.number(threshold * 100f64)
+                ),
+            );
+            eprint!("Re-run them and merge the new samples? [y/N] ");
+            let _ = io::stderr().flush();
+            let mut answer = String::new();
+            if io::stdin().read_line(&mut answer).is_ok() && answer.trim().eq_ignore_ascii_case("y")
+            {
+                self.rerun_noisy(threshold, rerun);
+            }
+        }
+
+        self.finish()
+    }
+
+    /// Prints the settings of the Bencher
+    pub fn print_settings(&mut self) -> &mut Self {
+        self.report(Level::Section, "Benchmarking Settings");
+        self.report(
+            Level::Info,
+            &format!(
+                "Benchmarking accuracy delay:\t {} (+{} per call)",
+                self.format.duration(self.overhead.intercept),
+                self.format.duration(self.overhead.per_call)
+            ),
+        );
+        self.report(
+            Level::Info,
+            &format!(
+                "Clock resolution:\t {}",
+                self.format.duration(self.resolution)
+            ),
+        );
+        if let Some(bandwidth) = &self.memory_bandwidth {
+            self.report(
+                Level::Info,
+                &format!("Memory bandwidth:\t {}", bandwidth.with_format(self.format)),
+            );
+        }
+        self.report(
+            Level::Info,
+            &format!(
+                "Calibration probes:\t {}",
+                self.format.count(self.calibration_probes)
+            ),
+        );
+        if let Some(interval) = self.recalibration_interval {
+            self.report(
+                Level::Info,
+                &format!(
+                    "Recalibration interval:\t {}",
+                    self.format.duration(interval)
+                ),
+            );
+        }
+        self.report(
+            Level::Info,
+            &format!(
+                "Number of iterations:\t {}",
+                if self.iterations > 0 {
+                    self.format.count(self.iterations)
+                } else {
+                    "auto".to_string()
+                }
+            ),
+        );
+        if self.iterations == 0 {
+            self.report(
+                Level::Info,
+                &format!(
+                    "Maximum number of iterations: {}",
+                    self.format.count(self.max_auto_iterations)
+                ),
+            )
+        }
+
+        self
+    }
+
+    /// Adds a file to write the output to. The mean and the standard
+    /// deviation are written in nanoseconds, so the columns can be parsed.
+    pub fn write_output_to(&mut self, mut writer: BufWriter<File>) -> &mut Self {
+        writer.write_all(BENCH_FILE_HEAD.as_bytes()).unwrap();
+        self.writer = Some(writer);
+
+        self
+    }
+
+    /// Adds a file to write the raw durations of each benchmark to,
+    /// so results of several processes can be merged with `RankResults`
+    pub fn write_raw_output_to(&mut self, writer: BufWriter<File>) -> &mut Self {
+        self.raw_writer = Some(writer);
+
+        self
+    }
+
+    /// Adds a file that `finish` writes the results of all benchmarks to
+    /// in the Bencher Metric Format (BMF), so they can be uploaded to a
+    /// continuous benchmarking service like bencher.dev
+    pub fn write_bmf_output_to(&mut self, writer: BufWriter<File>) -> &mut Self {
+        self.bmf_writer = Some(writer);
+
+        self
+    }
+
+    /// Sends the results of all benchmarks recorded so far with the metadata
+    /// of the run as JSON to a collector with a POST request, so machines
+    /// without a shared filesystem can report to a central place.
+    /// Only plain `http://` URLs are supported.
+    #[cfg(feature = "http-push")]
+    pub fn push_results(&mut self, url: &str) -> io::Result<&mut Self> {
+        let document = push::results_document(&RunMetadata::collect(), &self.results());
+        push::post(url, &document)?;
+        self.report(Level::Note, &format!("Pushed the results to {}", url));
+
+        Ok(self)
+    }
+
+    /// Sets a directory that a flamegraph SVG is written to for each benchmark,
+    /// named after the benchmark. The process is sampled while the benchmark
+    /// iterates, so the hotspots behind a slow result can be inspected.
+    /// If set to None no profile is recorded.
+    #[cfg(all(feature = "profiling", unix))]
+    pub fn set_flamegraphs(&mut self, dir: Option<PathBuf>) -> &mut Self {
+        self.flamegraph_dir = dir;
+
+        self
+    }
+
+    /// Writes the samples of the profiler as flamegraph of the benchmark
+    #[cfg(all(feature = "profiling", unix))]
+    fn write_flamegraph(
+        &mut self,
+        name: &str,
+        profiler: pprof::Result<pprof::ProfilerGuard<'static>>,
+    ) {
+        let dir = match &self.flamegraph_dir {
+            Some(dir) => dir,
+            None => return,
+        };
+        let file_name = file_stem(name);
+        let path = dir.join(format!("{}.svg", file_name));
+        let result = profiler
+            .and_then(|profiler| profiler.report().build())
+            .map_err(|e| e.to_string())
+            .and_then(|report| {
+                let file = File::create(&path).map_err(|e| e.to_string())?;
+                report.flamegraph(file).map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(()) => self.report(
+                Level::Note,
+                &format!("Flamegraph written to {}", path.display()),
+            ),
+            Err(e) => self.report(
+                Level::Warning,
+                &format!("Failed to write the flamegraph {}: {}", path.display(), e),
+            ),
+        }
+    }
+
+    /// Adds a file to write the tables of scaling sweeps to. Like in the
+    /// output file the durations are written in nanoseconds.
+    pub fn write_scaling_output_to(&mut self, mut writer: BufWriter<File>) -> &mut Self {
+        writer.write_all(SCALING_FILE_HEAD.as_bytes()).unwrap();
+        self.scaling_writer = Some(writer);
+
+        self
+    }
+
+    /// Sets a directory that a gnuplot script is written to for each
+    /// scaling sweep. Running the script plots the speedup against the thread
+    /// count with the ideal linear speedup as reference into an SVG file.
+    /// Iso-efficiency sweeps write their efficiency matrix there as TSV.
+    pub fn set_scaling_plots(&mut self, dir: Option<PathBuf>) -> &mut Self {
+        self.plot_dir = dir;
+
+        self
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(writer) = &mut self.scaling_writer {
+            writer.flush()?;
+        }
+        if let Some(writer) = &mut self.raw_writer {
+            writer.flush()?;
+        }
+        if let Some(writer) = &mut self.writer {
+            writer.flush()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The result of a prepare or cleanup hook
+pub trait HookOutcome {
+    /// Returns the description of the failure if the hook failed
+    fn failure(self) -> Option<String>;
+}
+
+impl HookOutcome for () {
+    fn failure(self) -> Option<String> {
+        None
+    }
+}
+
+impl<E: Display> HookOutcome for Result<(), E> {
+    fn failure(self) -> Option<String> {
+        self.err().map(|e| e.to_string())
+    }
+}
+
+/// Creates a hook that runs the given shell command and waits for it to finish.
+/// The hook fails if the command can't be run or exits with a failure status.
+pub fn shell_hook(command: &str) -> impl FnMut() -> io::Result<()> {
+    let command = command.to_string();
+    move || {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let status = Command::new(shell).arg(flag).arg(&command).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "{} exited with {}",
+                command, status
+            )))
+        }
+    }
+}
+
+/// Returns the name with every character that isn't alphanumeric
+/// replaced by an underscore, so it can be used in file names
+pub(crate) fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
 }
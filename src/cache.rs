@@ -0,0 +1,38 @@
+use std::hint::black_box;
+
+/// The assumed size of a cache line in bytes
+pub const CACHE_LINE_SIZE: usize = 64;
+
+/// The default size of the buffer used to thrash the caches
+pub const DEFAULT_THRASH_SIZE: usize = 64 * 1024 * 1024;
+
+/// Flushes the memory region of the given slice from all cache levels.
+/// On architectures without a flush instruction the caches are thrashed instead.
+#[cfg(target_arch = "x86_64")]
+pub fn flush_region<T>(data: &[T]) {
+    use std::arch::x86_64::{_mm_clflush, _mm_mfence};
+    let start = data.as_ptr() as *const u8;
+    let len = std::mem::size_of_val(data);
+    unsafe {
+        for offset in (0..len).step_by(CACHE_LINE_SIZE) {
+            _mm_clflush(start.add(offset));
+        }
+        _mm_mfence();
+    }
+}
+
+/// Flushes the memory region of the given slice from all cache levels.
+/// On architectures without a flush instruction the caches are thrashed instead.
+#[cfg(not(target_arch = "x86_64"))]
+pub fn flush_region<T>(_data: &[T]) {
+    thrash(&mut vec![0u8; DEFAULT_THRASH_SIZE]);
+}
+
+/// Evicts the caches by writing every cache line of a buffer
+/// that should be larger than the last level cache
+pub fn thrash(buffer: &mut [u8]) {
+    for i in (0..buffer.len()).step_by(CACHE_LINE_SIZE) {
+        buffer[i] = buffer[i].wrapping_add(1);
+    }
+    black_box(buffer);
+}
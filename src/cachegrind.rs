@@ -0,0 +1,139 @@
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::process::{Command, Stdio};
+
+use crate::benching::file_stem;
+
+/// The environment variable that selects the benchmark to run
+/// when the process was started under cachegrind
+pub const CACHEGRIND_ENV: &str = "BENCHLIB_CACHEGRIND";
+
+/// The environment variable that tells the process under cachegrind to
+/// call an empty closure instead of the selected benchmark
+pub const BASELINE_ENV: &str = "BENCHLIB_CACHEGRIND_BASELINE";
+
+/// Instruction and cache access counts reported by cachegrind
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CachegrindStats {
+    pub instructions: u64,
+    pub l1_misses: u64,
+    pub ll_misses: u64,
+}
+
+impl CachegrindStats {
+    /// Estimates the number of cycles from the instruction and cache miss counts
+    pub fn estimated_cycles(&self) -> u64 {
+        self.instructions + 5 * self.l1_misses + 35 * self.ll_misses
+    }
+
+    /// Returns the counts without the counts of the given baseline or None
+    /// if any count of the baseline is higher
+    pub fn since(&self, baseline: &Self) -> Option<Self> {
+        Some(Self {
+            instructions: self.instructions.checked_sub(baseline.instructions)?,
+            l1_misses: self.l1_misses.checked_sub(baseline.l1_misses)?,
+            ll_misses: self.ll_misses.checked_sub(baseline.ll_misses)?,
+        })
+    }
+
+    /// Parses the events and summary lines of a cachegrind output file
+    pub fn parse(contents: &str) -> Option<Self> {
+        let events = contents
+            .lines()
+            .find_map(|l| l.strip_prefix("events:"))?
+            .split_whitespace()
+            .collect::<Vec<&str>>();
+        let summary = contents
+            .lines()
+            .find_map(|l| l.strip_prefix("summary:"))?
+            .split_whitespace()
+            .map(|v| v.parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()
+            .ok()?;
+        let event = |name: &str| {
+            events
+                .iter()
+                .position(|e| *e == name)
+                .and_then(|i| summary.get(i))
+                .copied()
+                .unwrap_or(0)
+        };
+
+        Some(Self {
+            instructions: event("Ir"),
+            l1_misses: event("I1mr") + event("D1mr") + event("D1mw"),
+            ll_misses: event("ILmr") + event("DLmr") + event("DLmw"),
+        })
+    }
+}
+
+impl Display for CachegrindStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Instructions: {}, L1 misses: {}, LL misses: {}, Estimated cycles: {}",
+            self.instructions,
+            self.l1_misses,
+            self.ll_misses,
+            self.estimated_cycles()
+        )
+    }
+}
+
+/// Returns the name of the benchmark to run if the process
+/// was started under cachegrind by the Bencher
+pub fn target() -> Option<String> {
+    env::var(CACHEGRIND_ENV).ok()
+}
+
+/// Returns if the process under cachegrind measures the baseline, which
+/// calls an empty closure in place of the selected benchmark
+pub fn is_baseline() -> bool {
+    env::var_os(BASELINE_ENV).is_some()
+}
+
+/// Runs the current executable with the same arguments under cachegrind
+/// with the given benchmark selected and returns the counted events
+pub fn run(name: &str) -> io::Result<CachegrindStats> {
+    run_child(name, false)
+}
+
+/// Runs the current executable under cachegrind like `run`, but calls an
+/// empty closure in place of the given benchmark. The counts include the
+/// same work before the benchmark, so they are the baseline of its counts.
+pub fn run_baseline(name: &str) -> io::Result<CachegrindStats> {
+    run_child(name, true)
+}
+
+fn run_child(name: &str, baseline: bool) -> io::Result<CachegrindStats> {
+    let output_file = env::temp_dir().join(format!(
+        "cachegrind.benchlib.{}.{}{}",
+        std::process::id(),
+        file_stem(name),
+        if baseline { ".baseline" } else { "" }
+    ));
+    let mut command = Command::new("valgrind");
+    if baseline {
+        command.env(BASELINE_ENV, "1");
+    }
+    let status = command
+        .arg("--tool=cachegrind")
+        .arg("--cache-sim=yes")
+        .arg(format!("--cachegrind-out-file={}", output_file.display()))
+        .arg(env::current_exe()?)
+        .args(env::args_os().skip(1))
+        .env(CACHEGRIND_ENV, name)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("valgrind exited with {}", status)));
+    }
+    let contents = fs::read_to_string(&output_file)?;
+    let _ = fs::remove_file(&output_file);
+
+    CachegrindStats::parse(&contents)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid cachegrind output"))
+}
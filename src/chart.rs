@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use crate::benching::BenchVec;
+use crate::units::Format;
+
+/// The blocks of a sparkline from the lowest to the highest
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A compact chart of the distribution of the samples of a benchmark
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistributionChart {
+    /// A single line of unicode blocks
+    Sparkline,
+    /// A bar per bucket of durations
+    Histogram,
+}
+
+impl DistributionChart {
+    /// Renders the chart of the given durations with durations formatted
+    /// in the given format
+    pub fn render(&self, durations: &[Duration], format: &Format) -> String {
+        match self {
+            DistributionChart::Sparkline => sparkline(durations, 30, format),
+            DistributionChart::Histogram => histogram(durations, 10, 40, format),
+        }
+    }
+}
+
+/// Counts the durations in the given number of equally wide buckets between
+/// the shortest and the longest and returns the shortest, the width of a bucket
+/// and the counts
+pub fn buckets(durations: &[Duration], buckets: usize) -> (Duration, Duration, Vec<usize>) {
+    let buckets = buckets.max(1);
+    let mut counts = vec![0usize; buckets];
+    let (min, max) = match (durations.iter().min(), durations.iter().max()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return (Duration::default(), Duration::default(), counts),
+    };
+    let width = ((max - min).as_nanos() as f64 / buckets as f64).max(1f64);
+    for duration in durations {
+        let bucket = ((*duration - min).as_nanos() as f64 / width) as usize;
+        counts[bucket.min(buckets - 1)] += 1;
+    }
+
+    (min, Duration::from_nanos(width as u64), counts)
+}
+
+/// Renders a histogram of the durations with a line per bucket
+/// whose bar is scaled to the given width for the fullest bucket
+pub fn histogram(
+    durations: &[Duration],
+    bucket_count: usize,
+    width: usize,
+    format: &Format,
+) -> String {
+    let (min, bucket_width, counts) = buckets(durations, bucket_count);
+    let highest = counts.iter().copied().max().unwrap_or(0).max(1);
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            format!(
+                "{:>12} | {:<width$} {}",
+                format.duration(min + bucket_width * i as u32),
+                "#".repeat(count * width / highest),
+                count,
+                width = width
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Renders a single line of blocks whose heights are the counts of the
+/// durations in the given number of buckets, framed by the shortest and
+/// the longest duration. Empty buckets are left blank.
+pub fn sparkline(durations: &[Duration], bucket_count: usize, format: &Format) -> String {
+    let (min, _, counts) = buckets(durations, bucket_count);
+    let max = durations.iter().max().copied().unwrap_or_default();
+    let highest = counts.iter().copied().max().unwrap_or(0).max(1);
+    let line = counts
+        .iter()
+        .map(|&count| {
+            if count == 0 {
+                ' '
+            } else {
+                SPARKS[count * (SPARKS.len() - 1) / highest]
+            }
+        })
+        .collect::<String>();
+
+    format!("{} {} {}", format.duration(min), line, format.duration(max))
+}
+
+/// Renders a box plot per named distribution on a common scale of the given
+/// width, so the overlap of the distributions is visible. The whiskers reach
+/// from the shortest to the longest duration, the box from the first to the
+/// third quartile and the median is marked by a bar.
+pub fn box_plots(distributions: &[(&str, &BenchVec)], width: usize, format: &Format) -> String {
+    let width = width.max(2);
+    let quartiles = distributions
+        .iter()
+        .map(|(_, durations)| {
+            [0f64, 25f64, 50f64, 75f64, 100f64].map(|p| durations.percentile(p).as_nanos() as f64)
+        })
+        .collect::<Vec<[f64; 5]>>();
+    let low = quartiles.iter().map(|q| q[0]).fold(f64::MAX, f64::min);
+    let high = quartiles.iter().map(|q| q[4]).fold(f64::MIN, f64::max);
+    let range = (high - low).max(1f64);
+    let position = |nanos: f64| (((nanos - low) / range) * (width - 1) as f64).round() as usize;
+    let name_width = distributions
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = distributions
+        .iter()
+        .zip(&quartiles)
+        .map(|((name, _), q)| {
+            let [min, q1, median, q3, max] = q.map(position);
+            let mut line = vec![' '; width];
+            line[min..=max].iter_mut().for_each(|c| *c = '-');
+            line[q1..=q3].iter_mut().for_each(|c| *c = '=');
+            line[min] = '|';
+            line[max] = '|';
+            line[q1] = '[';
+            line[q3] = ']';
+            line[median] = '|';
+            format!(
+                "{:<name_width$} {}",
+                name,
+                line.into_iter().collect::<String>().trim_end(),
+                name_width = name_width
+            )
+        })
+        .collect::<Vec<String>>();
+    let low_label = format.nanos(low);
+    let high_label = format.nanos(high);
+    lines.push(format!(
+        "{:<name_width$} {}{:>rest$}",
+        "",
+        low_label,
+        high_label,
+        name_width = name_width,
+        rest = width
+            .saturating_sub(low_label.len())
+            .max(high_label.len() + 1)
+    ));
+
+    lines.join("\n")
+}
+
+/// The blocks of a horizontal bar from an eighth to a full character
+const EIGHTHS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+/// Renders a horizontal bar per named duration, scaled so the longest
+/// duration fills the given width. Fractions of a character are drawn
+/// with eighth blocks, so close durations are still distinguishable.
+pub fn bar_chart(entries: &[(&str, Duration)], width: usize, format: &Format) -> String {
+    let longest = entries
+        .iter()
+        .map(|(_, d)| d.as_nanos())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let name_width = entries
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    entries
+        .iter()
+        .map(|(name, duration)| {
+            let eighths = (duration.as_nanos() * (width * 8) as u128 / longest) as usize;
+            let mut bar = EIGHTHS[7].to_string().repeat(eighths / 8);
+            if let Some(partial) = (eighths % 8).checked_sub(1) {
+                bar.push(EIGHTHS[partial]);
+            }
+            format!(
+                "{:<name_width$} {:<width$} {}",
+                name,
+                bar,
+                format.duration(*duration),
+                name_width = name_width,
+                width = width
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,85 @@
+use std::env;
+
+/// A continuous integration service the benchmarks run on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiEnvironment {
+    GitHubActions,
+    GitLab,
+    Jenkins,
+    Buildkite,
+    CircleCi,
+    Travis,
+    AzurePipelines,
+    TeamCity,
+    /// A service that only sets the CI variable
+    Other,
+}
+
+/// Detects the CI service from the variables it sets for its jobs.
+/// CI set to false or 0 disables the detection of the generic variable.
+pub fn detect() -> Option<CiEnvironment> {
+    let is_set = |name: &str| env::var_os(name).is_some_and(|v| !v.is_empty());
+    if is_set("GITHUB_ACTIONS") {
+        Some(CiEnvironment::GitHubActions)
+    } else if is_set("GITLAB_CI") {
+        Some(CiEnvironment::GitLab)
+    } else if is_set("JENKINS_URL") {
+        Some(CiEnvironment::Jenkins)
+    } else if is_set("BUILDKITE") {
+        Some(CiEnvironment::Buildkite)
+    } else if is_set("CIRCLECI") {
+        Some(CiEnvironment::CircleCi)
+    } else if is_set("TRAVIS") {
+        Some(CiEnvironment::Travis)
+    } else if is_set("TF_BUILD") {
+        Some(CiEnvironment::AzurePipelines)
+    } else if is_set("TEAMCITY_VERSION") {
+        Some(CiEnvironment::TeamCity)
+    } else {
+        env::var("CI")
+            .ok()
+            .filter(|v| !v.is_empty() && v != "false" && v != "0")
+            .map(|_| CiEnvironment::Other)
+    }
+}
+
+/// Returns if the benchmarks run on a CI service
+pub fn is_ci() -> bool {
+    detect().is_some()
+}
+
+/// Escapes the message of a workflow command of GitHub Actions
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a property of a workflow command of GitHub Actions
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Formats a workflow command of GitHub Actions that annotates the given
+/// location with a message, like `::warning file=src/main.rs,line=3,title=..::message`.
+/// The command is an error, a warning or a notice.
+pub fn github_annotation(
+    command: &str,
+    location: Option<(&str, u32)>,
+    title: &str,
+    message: &str,
+) -> String {
+    let mut properties = Vec::new();
+    if let Some((file, line)) = location {
+        properties.push(format!("file={}", escape_property(file)));
+        properties.push(format!("line={}", line));
+    }
+    properties.push(format!("title={}", escape_property(title)));
+
+    format!(
+        "::{} {}::{}",
+        command,
+        properties.join(","),
+        escape_data(message)
+    )
+}
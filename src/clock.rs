@@ -0,0 +1,352 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use crate::wasm::Instant;
+/// The monotonic instant used for the measurements, `std::time::Instant`
+/// or with the wasm feature on wasm32 an instant based on `performance.now()`
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub use std::time::Instant;
+
+/// The wall time after which the default `Clock::resolution` stops waiting
+/// for the clock to advance. It's also the resolution of a clock that never
+/// advances.
+pub const RESOLUTION_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A source of monotonic timestamps used to measure benchmarks
+pub trait Clock: Send {
+    /// Returns the current timestamp relative to a fixed but arbitrary origin
+    fn now(&self) -> Duration;
+
+    /// Returns the time that passed since the given timestamp
+    fn elapsed(&self, start: Duration) -> Duration {
+        self.now().checked_sub(start).unwrap_or_default()
+    }
+
+    /// Measures the effective resolution of the clock as the smallest
+    /// nonzero difference between two consecutive readings. The measurement
+    /// gives up after `RESOLUTION_TIMEOUT` of wall time, so a clock that
+    /// doesn't advance has the resolution `RESOLUTION_TIMEOUT`.
+    fn resolution(&self) -> Duration {
+        let deadline = Instant::now() + RESOLUTION_TIMEOUT;
+        let mut resolution = RESOLUTION_TIMEOUT;
+        for _ in 0..100 {
+            let start = self.now();
+            let mut end = self.now();
+            while end == start {
+                if Instant::now() >= deadline {
+                    return resolution;
+                }
+                end = self.now();
+            }
+            resolution = resolution.min(end.checked_sub(start).unwrap_or(resolution));
+        }
+
+        resolution
+    }
+
+    /// Returns the number of cycles per nanosecond if the clock counts cycles
+    fn cycles_per_nanosecond(&self) -> Option<f64> {
+        None
+    }
+
+    /// Returns if the clock measures the wall time because the time
+    /// it's meant to measure isn't available on this platform
+    fn falls_back_to_wall_time(&self) -> bool {
+        false
+    }
+}
+
+/// The default clock based on the monotonic `std::time::Instant`
+#[derive(Debug, Clone)]
+pub struct InstantClock {
+    origin: Instant,
+}
+
+impl InstantClock {
+    /// Creates a new clock with the current instant as origin
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for InstantClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for InstantClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// A clock reading `performance.now()` of the browser or worker, which is
+/// the default clock on wasm32. Browsers coarsen the timer to protect against
+/// timing attacks, so only cross-origin isolated pages get microsecond
+/// resolution and short closures should be measured in batches.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default)]
+pub struct PerformanceClock;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl PerformanceClock {
+    /// Creates a new performance clock
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl Clock for PerformanceClock {
+    fn now(&self) -> Duration {
+        crate::wasm::now()
+    }
+}
+
+/// A deterministic clock whose time only passes when it is advanced manually
+/// or by a fixed step on every reading. Clones share the same time, so a clone
+/// can be used to advance the time of a clock that was passed to the Bencher.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    nanos: Arc<AtomicU64>,
+    step: u64,
+}
+
+impl MockClock {
+    /// Creates a new mock clock whose time only passes when it is advanced
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new mock clock whose time advances by the given step on every reading.
+    /// The step is also measured during the overhead calibration of the Bencher.
+    pub fn with_step(step: Duration) -> Self {
+        Self {
+            nanos: Arc::new(AtomicU64::new(0)),
+            step: step.as_nanos() as u64,
+        }
+    }
+
+    /// Advances the time of the clock and all its clones
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.fetch_add(self.step, Ordering::SeqCst))
+    }
+
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(self.step.max(1))
+    }
+}
+
+#[cfg(all(
+    feature = "tsc",
+    not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))
+))]
+compile_error!("the tsc feature requires an x86, x86_64 or aarch64 target");
+
+/// A clock reading the CPU cycle counter (rdtscp on x86, cntvct on ARM).
+/// The counter frequency is calibrated against `Instant` on creation
+/// and the time is counted from the creation on.
+#[cfg(feature = "tsc")]
+#[derive(Debug, Clone)]
+pub struct CycleClock {
+    cycles_per_nanosecond: f64,
+    start_cycles: u64,
+}
+
+#[cfg(feature = "tsc")]
+impl CycleClock {
+    /// Creates a new cycle clock and calibrates the counter frequency
+    pub fn new() -> Self {
+        let start = Instant::now();
+        let start_cycles = Self::cycles();
+        while start.elapsed() < Duration::from_millis(10) {}
+        let cycles = Self::cycles() - start_cycles;
+
+        Self {
+            cycles_per_nanosecond: cycles as f64 / start.elapsed().as_nanos() as f64,
+            start_cycles,
+        }
+    }
+
+    /// Reads the current value of the cycle counter
+    #[cfg(target_arch = "x86_64")]
+    pub fn cycles() -> u64 {
+        let mut aux = 0;
+        unsafe { std::arch::x86_64::__rdtscp(&mut aux) }
+    }
+
+    /// Reads the current value of the cycle counter
+    #[cfg(target_arch = "x86")]
+    pub fn cycles() -> u64 {
+        let mut aux = 0;
+        unsafe { std::arch::x86::__rdtscp(&mut aux) }
+    }
+
+    /// Reads the current value of the cycle counter
+    #[cfg(target_arch = "aarch64")]
+    pub fn cycles() -> u64 {
+        let cycles: u64;
+        unsafe { std::arch::asm!("mrs {}, cntvct_el0", out(reg) cycles) };
+
+        cycles
+    }
+}
+
+#[cfg(feature = "tsc")]
+impl Default for CycleClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "tsc")]
+impl Clock for CycleClock {
+    fn now(&self) -> Duration {
+        let cycles = Self::cycles().wrapping_sub(self.start_cycles);
+
+        Duration::from_nanos((cycles as f64 / self.cycles_per_nanosecond) as u64)
+    }
+
+    fn cycles_per_nanosecond(&self) -> Option<f64> {
+        Some(self.cycles_per_nanosecond)
+    }
+}
+
+/// A clock measuring the CPU time consumed by the whole process,
+/// so that time where the process was descheduled isn't counted.
+/// Platforms without CPU time like wasm32 measure the wall time instead.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessCpuClock;
+
+impl ProcessCpuClock {
+    /// Creates a new process CPU time clock
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Clock for ProcessCpuClock {
+    #[cfg(not(any(unix, windows)))]
+    fn now(&self) -> Duration {
+        wall_time()
+    }
+
+    fn falls_back_to_wall_time(&self) -> bool {
+        cfg!(not(any(unix, windows)))
+    }
+
+    #[cfg(unix)]
+    fn now(&self) -> Duration {
+        clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID)
+    }
+
+    #[cfg(windows)]
+    fn now(&self) -> Duration {
+        use winapi::shared::minwindef::FILETIME;
+        use winapi::um::processthreadsapi::{GetCurrentProcess, GetProcessTimes};
+        unsafe {
+            let mut creation: FILETIME = std::mem::zeroed();
+            let mut exit: FILETIME = std::mem::zeroed();
+            let mut kernel: FILETIME = std::mem::zeroed();
+            let mut user: FILETIME = std::mem::zeroed();
+            GetProcessTimes(
+                GetCurrentProcess(),
+                &mut creation,
+                &mut exit,
+                &mut kernel,
+                &mut user,
+            );
+            filetime_to_duration(&kernel) + filetime_to_duration(&user)
+        }
+    }
+}
+
+/// A clock measuring the CPU time consumed by the calling thread,
+/// so that background threads of the process don't distort the measurement.
+/// Platforms without CPU time like wasm32 measure the wall time instead.
+#[derive(Debug, Clone, Default)]
+pub struct ThreadCpuClock;
+
+impl ThreadCpuClock {
+    /// Creates a new thread CPU time clock
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Clock for ThreadCpuClock {
+    #[cfg(not(any(unix, windows)))]
+    fn now(&self) -> Duration {
+        wall_time()
+    }
+
+    fn falls_back_to_wall_time(&self) -> bool {
+        cfg!(not(any(unix, windows)))
+    }
+
+    #[cfg(unix)]
+    fn now(&self) -> Duration {
+        clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID)
+    }
+
+    #[cfg(windows)]
+    fn now(&self) -> Duration {
+        use winapi::shared::minwindef::FILETIME;
+        use winapi::um::processthreadsapi::{GetCurrentThread, GetThreadTimes};
+        unsafe {
+            let mut creation: FILETIME = std::mem::zeroed();
+            let mut exit: FILETIME = std::mem::zeroed();
+            let mut kernel: FILETIME = std::mem::zeroed();
+            let mut user: FILETIME = std::mem::zeroed();
+            GetThreadTimes(
+                GetCurrentThread(),
+                &mut creation,
+                &mut exit,
+                &mut kernel,
+                &mut user,
+            );
+            filetime_to_duration(&kernel) + filetime_to_duration(&user)
+        }
+    }
+}
+
+/// Returns the wall time since the first reading on platforms without
+/// CPU time clocks like wasm32, where the benchmarks run on a single thread
+#[cfg(not(any(unix, windows)))]
+fn wall_time() -> Duration {
+    static ORIGIN: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+
+    ORIGIN.get_or_init(Instant::now).elapsed()
+}
+
+/// Reads the given clock via `clock_gettime`
+#[cfg(unix)]
+fn clock_gettime(clock_id: libc::clockid_t) -> Duration {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(clock_id, &mut time) };
+
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+/// Converts a FILETIME holding 100ns intervals into a duration
+#[cfg(windows)]
+fn filetime_to_duration(time: &winapi::shared::minwindef::FILETIME) -> Duration {
+    let intervals = ((time.dwHighDateTime as u64) << 32) | time.dwLowDateTime as u64;
+
+    Duration::from_nanos(intervals * 100)
+}
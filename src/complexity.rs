@@ -0,0 +1,176 @@
+use std::fmt::{self, Display};
+
+use crate::benching::BenchVec;
+use crate::units::{Format, FormatWith};
+
+/// A candidate model for the growth of the duration with the input size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Complexity {
+    Linear,
+    Linearithmic,
+    Quadratic,
+}
+
+impl Complexity {
+    /// All models that are fitted to a size sweep
+    pub const ALL: [Complexity; 3] = [
+        Complexity::Linear,
+        Complexity::Linearithmic,
+        Complexity::Quadratic,
+    ];
+
+    /// Returns the value of the model function for the given input size
+    pub fn apply(&self, size: usize) -> f64 {
+        let n = size as f64;
+        match self {
+            Complexity::Linear => n,
+            Complexity::Linearithmic => n * n.max(1f64).log2(),
+            Complexity::Quadratic => n * n,
+        }
+    }
+}
+
+impl Display for Complexity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Complexity::Linear => write!(f, "O(n)"),
+            Complexity::Linearithmic => write!(f, "O(n log n)"),
+            Complexity::Quadratic => write!(f, "O(n²)"),
+        }
+    }
+}
+
+/// The result of fitting T(n) = c * f(n) to the measurements of a size sweep
+#[derive(Debug, Clone, Copy)]
+pub struct ComplexityFit {
+    pub model: Complexity,
+    /// The fitted coefficient c in nanoseconds
+    pub coefficient: f64,
+    /// The root mean square of the residuals relative to the mean duration
+    pub residual: f64,
+}
+
+impl Display for ComplexityFit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for ComplexityFit {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} * f(n) (residual {}%)",
+            self.model,
+            format.nanos(self.coefficient),
+            format.number(self.residual * 100f64)
+        )
+    }
+}
+
+/// The measurement of a size sweep for one input size
+#[derive(Debug, Clone)]
+pub struct SizePoint {
+    pub size: usize,
+    pub durations: BenchVec,
+}
+
+/// The measurements of a benchmark for a series of input sizes
+#[derive(Debug, Clone)]
+pub struct SizeSweepResult {
+    pub name: String,
+    pub points: Vec<SizePoint>,
+}
+
+impl SizeSweepResult {
+    /// Creates a new empty size sweep result
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            points: Vec::new(),
+        }
+    }
+
+    /// Adds the measurement for an input size
+    pub fn push(&mut self, size: usize, durations: BenchVec) -> &mut Self {
+        self.points.push(SizePoint { size, durations });
+
+        self
+    }
+
+    /// Fits the given model to the measurements with a least squares
+    /// regression through the origin. Requires at least two measurements.
+    pub fn fit(&self, model: Complexity) -> Option<ComplexityFit> {
+        if self.points.len() < 2 {
+            return None;
+        }
+        let points = self
+            .points
+            .iter()
+            .map(|p| (model.apply(p.size), p.durations.average().as_nanos() as f64))
+            .collect::<Vec<(f64, f64)>>();
+        let denominator = points.iter().map(|(x, _)| x * x).sum::<f64>();
+        if denominator <= 0f64 {
+            return None;
+        }
+        let coefficient = points.iter().map(|(x, t)| x * t).sum::<f64>() / denominator;
+        let mean = points.iter().map(|(_, t)| t).sum::<f64>() / points.len() as f64;
+        let squares = points
+            .iter()
+            .map(|(x, t)| (t - coefficient * x).powi(2))
+            .sum::<f64>();
+        let residual = if mean > 0f64 {
+            (squares / points.len() as f64).sqrt() / mean
+        } else {
+            0f64
+        };
+
+        Some(ComplexityFit {
+            model,
+            coefficient,
+            residual,
+        })
+    }
+
+    /// Returns the fits of all candidate models
+    pub fn fits(&self) -> Vec<ComplexityFit> {
+        Complexity::ALL
+            .iter()
+            .filter_map(|model| self.fit(*model))
+            .collect()
+    }
+
+    /// Returns the fit of the model with the smallest residual
+    pub fn best_fit(&self) -> Option<ComplexityFit> {
+        self.fits()
+            .into_iter()
+            .min_by(|a, b| a.residual.total_cmp(&b.residual))
+    }
+}
+
+impl Display for SizeSweepResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for SizeSweepResult {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>12}  {:>16}  {:>16}",
+            "size", "duration", "std deviation"
+        )?;
+        for point in &self.points {
+            writeln!(
+                f,
+                "{:>12}  {:>16}  {:>16}",
+                point.size,
+                format.duration(point.durations.average()),
+                format.nanos(point.durations.standard_deviation())
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use rayon::ThreadPoolBuilder;
+
+use crate::benching::BenchVec;
+use crate::clock::Instant;
+use crate::units::{Format, FormatWith};
+
+/// The measurement of a closure that was run simultaneously on several threads
+#[derive(Debug, Clone)]
+pub struct ConcurrentResult {
+    pub name: String,
+    pub threads: usize,
+    /// The latencies of all calls on all threads
+    pub latencies: BenchVec,
+    /// The latencies of the calls on each thread
+    pub per_thread: Vec<BenchVec>,
+    /// The wall time from the release of the start barrier until all threads finished
+    pub wall_time: Duration,
+}
+
+impl ConcurrentResult {
+    /// Creates a new result from the latencies of each thread
+    pub fn new(name: &str, per_thread: Vec<BenchVec>, wall_time: Duration) -> Self {
+        let mut latencies = BenchVec::new();
+        for durations in &per_thread {
+            latencies.append(durations.clone());
+        }
+
+        Self {
+            name: name.to_string(),
+            threads: per_thread.len(),
+            latencies,
+            per_thread,
+            wall_time,
+        }
+    }
+
+    /// Returns the number of calls on all threads
+    pub fn operations(&self) -> usize {
+        self.latencies.len()
+    }
+
+    /// Returns the aggregate number of calls per second on all threads
+    pub fn throughput(&self) -> f64 {
+        self.operations() as f64 / self.wall_time.as_secs_f64()
+    }
+
+    /// Returns the average latency of each thread
+    pub fn thread_means(&self) -> Vec<Duration> {
+        self.per_thread
+            .iter()
+            .filter(|durations| !durations.is_empty())
+            .map(|durations| durations.average())
+            .collect()
+    }
+
+    /// Returns the difference between the average latencies
+    /// of the slowest and the fastest thread
+    pub fn thread_spread(&self) -> Duration {
+        let means = self.thread_means();
+        let fastest = means.iter().min().copied().unwrap_or_default();
+        let slowest = means.iter().max().copied().unwrap_or_default();
+
+        slowest - fastest
+    }
+
+    /// Formats the average latency of each thread and the spread between them
+    pub fn thread_breakdown(&self) -> String {
+        self.thread_breakdown_with(&Format::default())
+    }
+
+    /// Formats the average latency of each thread and the spread between them
+    /// with the given format
+    pub fn thread_breakdown_with(&self, format: &Format) -> String {
+        let means = self
+            .per_thread
+            .iter()
+            .enumerate()
+            .filter(|(_, durations)| !durations.is_empty())
+            .map(|(thread, durations)| {
+                format!("#{} {}", thread, format.duration(durations.average()))
+            })
+            .collect::<Vec<String>>();
+        format!(
+            "{} (spread {})",
+            means.join(", "),
+            format.duration(self.thread_spread())
+        )
+    }
+}
+
+impl Display for ConcurrentResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for ConcurrentResult {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(
+            f,
+            "{} ops/s on {} threads ({} calls in {})",
+            format.number(self.throughput()),
+            self.threads,
+            self.operations(),
+            format.duration(self.wall_time)
+        )
+    }
+}
+
+/// The busy time of each worker thread reported by a benchmarked closure,
+/// accumulated over all iterations of a measurement
+#[derive(Debug, Default)]
+pub struct ThreadTimes {
+    times: Mutex<BTreeMap<usize, Duration>>,
+}
+
+impl ThreadTimes {
+    /// Creates a new empty set of thread times
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a duration to the busy time of the given thread
+    pub fn record(&self, thread: usize, duration: Duration) {
+        *self.times.lock().unwrap().entry(thread).or_default() += duration;
+    }
+
+    /// Measures a closure and adds its duration to the busy time of the
+    /// current rayon worker thread or thread 0 outside of a thread pool
+    pub fn measure<T, F: FnOnce() -> T>(&self, func: F) -> T {
+        let start = Instant::now();
+        let value = func();
+        self.record(rayon::current_thread_index().unwrap_or(0), start.elapsed());
+
+        value
+    }
+
+    /// Returns the busy time of each thread that recorded one and resets them
+    pub(crate) fn take(&self) -> Vec<Duration> {
+        std::mem::take(&mut *self.times.lock().unwrap())
+            .into_values()
+            .collect()
+    }
+}
+
+/// A task of a multi-threaded benchmark that may borrow from the benchmark
+pub type Task<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// A threading backend that runs the threads of multi-threaded benchmarks.
+/// All tasks wait for each other before measuring, so they have to run
+/// at the same time on separate threads.
+pub trait Spawner: Send {
+    /// Runs every task on its own thread and returns when all of them finished
+    fn run_all(&self, tasks: Vec<Task<'_>>);
+}
+
+/// Runs the tasks on scoped threads of the standard library
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdSpawner;
+
+impl Spawner for StdSpawner {
+    fn run_all(&self, tasks: Vec<Task<'_>>) {
+        thread::scope(|scope| {
+            let handles = tasks
+                .into_iter()
+                .map(|task| scope.spawn(task))
+                .collect::<Vec<_>>();
+            for handle in handles {
+                handle.join().expect("a benchmark thread panicked");
+            }
+        });
+    }
+}
+
+/// Runs the tasks in a rayon thread pool with one worker per task
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RayonSpawner;
+
+impl Spawner for RayonSpawner {
+    fn run_all(&self, tasks: Vec<Task<'_>>) {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(tasks.len().max(1))
+            .build()
+            .expect("failed to build the rayon thread pool");
+        pool.scope(|scope| {
+            for task in tasks {
+                scope.spawn(move |_| task());
+            }
+        });
+    }
+}
+
+/// Runs the given number of tasks with the spawner and returns their results
+/// ordered by the index the task was called with
+pub(crate) fn run_indexed<R, F>(spawner: &dyn Spawner, count: usize, task: F) -> Vec<R>
+where
+    F: Fn(usize) -> R + Sync,
+    R: Send,
+{
+    let results = Mutex::new((0..count).map(|_| None).collect::<Vec<Option<R>>>());
+    {
+        let task = &task;
+        let results = &results;
+        spawner.run_all(
+            (0..count)
+                .map(|index| {
+                    Box::new(move || {
+                        let result = task(index);
+                        results.lock().unwrap()[index] = Some(result);
+                    }) as Task<'_>
+                })
+                .collect(),
+        );
+    }
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("a benchmark task didn't run"))
+        .collect()
+}
@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use crate::benching::file_stem;
+use crate::clock::Instant;
+
+/// A barrier shared by several separately started processes via a directory.
+/// Every process registers itself with a file for a section and waits until
+/// the given number of processes registered, so their measured sections
+/// begin at the same time. A section that is waited on again, e.g. by a
+/// loop or a re-run, registers in a new generation, so the files of the
+/// previous wait don't release it. The directory should be empty before a run.
+#[derive(Debug, Clone)]
+pub struct ProcessBarrier {
+    dir: PathBuf,
+    participants: usize,
+    timeout: Duration,
+    generations: BTreeMap<String, usize>,
+}
+
+impl ProcessBarrier {
+    /// Creates a barrier for the given number of processes in the given directory
+    pub fn new<P: AsRef<Path>>(dir: P, participants: usize) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            participants,
+            timeout: Duration::from_secs(60),
+            generations: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the time after which waiting for the other processes is given up
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// Returns the directory the processes of the given section register in
+    pub fn section_dir(&self, section: &str) -> PathBuf {
+        self.dir.join(file_stem(section))
+    }
+
+    /// Registers the process for the given section and waits
+    /// until all participating processes registered for it.
+    /// The n-th wait of each process on a section registers in the
+    /// subdirectory of the generation n, counting from 0.
+    pub fn wait(&mut self, section: &str) -> io::Result<()> {
+        let generation = self.generations.get(section).copied().unwrap_or(0);
+        self.generations.insert(section.to_string(), generation + 1);
+        let dir = self.section_dir(section).join(generation.to_string());
+        fs::create_dir_all(&dir)?;
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(dir.join(process::id().to_string()))?;
+        let start = Instant::now();
+        while fs::read_dir(&dir)?.count() < self.participants {
+            if start.elapsed() > self.timeout {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "not all of the {} processes arrived at {}",
+                        self.participants, section
+                    ),
+                ));
+            }
+            thread::sleep(Duration::from_micros(100));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::benching::BenchVec;
+use crate::json::{self, Value};
+
+/// The point estimates criterion stored in the `estimates.json` of a benchmark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimates {
+    pub mean: Duration,
+    pub median: Duration,
+    pub std_dev: Duration,
+}
+
+fn invalid(file: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid criterion data in {}", file.display()),
+    )
+}
+
+fn read_json(path: &Path) -> io::Result<Value> {
+    json::parse(&fs::read_to_string(path)?).ok_or_else(|| invalid(path))
+}
+
+/// Reads the point estimates of the mean, median and standard deviation
+pub fn read_estimates<P: AsRef<Path>>(path: P) -> io::Result<Estimates> {
+    let path = path.as_ref();
+    let estimates = read_json(path)?;
+    let point = |statistic: &str| {
+        estimates
+            .get(statistic)
+            .and_then(|s| s.get("point_estimate"))
+            .and_then(Value::as_f64)
+            .filter(|nanos| nanos.is_finite() && *nanos >= 0f64)
+            .map(|nanos| Duration::from_nanos(nanos.round() as u64))
+            .ok_or_else(|| invalid(path))
+    };
+
+    Ok(Estimates {
+        mean: point("mean")?,
+        median: point("median")?,
+        std_dev: point("std_dev")?,
+    })
+}
+
+/// Reads the samples of a `sample.json`. Criterion measures every sample over
+/// several iterations, so each sample becomes the average of its iterations.
+pub fn read_samples<P: AsRef<Path>>(path: P) -> io::Result<BenchVec> {
+    let path = path.as_ref();
+    let sample = read_json(path)?;
+    let numbers = |key: &str| {
+        sample
+            .get(key)
+            .and_then(Value::as_array)
+            .and_then(|values| {
+                values
+                    .iter()
+                    .map(Value::as_f64)
+                    .collect::<Option<Vec<f64>>>()
+            })
+            .ok_or_else(|| invalid(path))
+    };
+    let iters = numbers("iters")?;
+    let times = numbers("times")?;
+    if iters.len() != times.len() || iters.iter().any(|i| *i <= 0f64) {
+        return Err(invalid(path));
+    }
+    let durations = iters
+        .iter()
+        .zip(&times)
+        .map(|(iters, time)| Duration::from_nanos((time / iters).max(0f64).round() as u64))
+        .collect::<Vec<Duration>>();
+
+    Ok(BenchVec::from_vec(&durations))
+}
+
+/// Reads the samples of all benchmarks saved under the given baseline name
+/// from a criterion output directory, usually `target/criterion`. Criterion
+/// saves the latest run as `new` and the previous one as `base`. The
+/// benchmarks are named by their criterion id, e.g. `group/function/input`.
+pub fn read_baseline<P: AsRef<Path>>(
+    dir: P,
+    baseline: &str,
+) -> io::Result<BTreeMap<String, BenchVec>> {
+    let mut results = BTreeMap::new();
+    collect_baseline(dir.as_ref(), dir.as_ref(), baseline, &mut results)?;
+
+    Ok(results)
+}
+
+fn collect_baseline(
+    root: &Path,
+    dir: &Path,
+    baseline: &str,
+    results: &mut BTreeMap<String, BenchVec>,
+) -> io::Result<()> {
+    let saved = dir.join(baseline);
+    if saved.join("sample.json").is_file() {
+        let name = read_json(&saved.join("benchmark.json"))
+            .ok()
+            .and_then(|b| b.get("full_id").and_then(Value::as_str).map(String::from))
+            .unwrap_or_else(|| {
+                dir.strip_prefix(root)
+                    .unwrap_or(dir)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            });
+        results.insert(name, read_samples(saved.join("sample.json"))?);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let skipped = path
+            .file_name()
+            .is_some_and(|n| n == "report" || n == "new" || n == "base" || n == baseline);
+        if path.is_dir() && !skipped {
+            collect_baseline(root, &path, baseline, results)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::benching::{BenchVec, DurationDifference};
+use crate::chart;
+use crate::clock::Instant;
+use crate::reporter::{Level, Reporter};
+use crate::terminal::{self, Style, Theme};
+use crate::units::{Format, FormatWith};
+
+/// The number of latest samples the rolling statistics and the histogram use
+const WINDOW: usize = 100;
+/// The number of buckets of the histogram
+const BUCKETS: usize = 10;
+/// The number of characters of the longest histogram bar
+const BAR_WIDTH: usize = 40;
+/// The number of warnings that are kept on the dashboard
+const WARNINGS: usize = 5;
+
+/// A full-screen view of the running benchmark with its rolling mean and
+/// standard deviation, a histogram of the latest samples and the table of
+/// completed benchmarks. The dashboard is shown on the alternate screen
+/// while benchmarking and the table is printed when the Bencher finishes,
+/// followed by the details and comparisons that were reported meanwhile.
+pub struct DashboardReporter {
+    current: Option<String>,
+    samples: Vec<Duration>,
+    progress: (usize, usize),
+    completed: Vec<(String, BenchVec)>,
+    details: Vec<String>,
+    warnings: Vec<String>,
+    theme: Theme,
+    format: Format,
+    refresh: Duration,
+    drawn: Option<Instant>,
+    active: bool,
+}
+
+impl DashboardReporter {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            samples: Vec::new(),
+            progress: (0, 0),
+            completed: Vec::new(),
+            details: Vec::new(),
+            warnings: Vec::new(),
+            theme: Theme::default(),
+            format: Format::default(),
+            refresh: Duration::from_millis(100),
+            drawn: None,
+            active: false,
+        }
+    }
+
+    /// Sets the minimal time between two redraws of the dashboard
+    pub fn set_refresh_interval(&mut self, refresh: Duration) -> &mut Self {
+        self.refresh = refresh;
+
+        self
+    }
+
+    /// Renders the current state of the dashboard
+    pub fn render(&self) -> String {
+        let mut frame = format!(
+            "{}benchlib dashboard{}\n\n",
+            self.theme.style(Style::Section),
+            terminal::reset()
+        );
+        if let Some(name) = &self.current {
+            let (done, total) = self.progress;
+            frame.push_str(&format!(
+                "{}Running: {}{} ({}/{} iterations)\n",
+                self.theme.style(Style::Heading),
+                name,
+                terminal::reset(),
+                self.format.count(done),
+                self.format.count(total)
+            ));
+            let window = &self.samples[self.samples.len().saturating_sub(WINDOW)..];
+            if !window.is_empty() {
+                frame.push_str(&format!(
+                    "Rolling: {} over the last {} samples\n\n",
+                    BenchVec::from_vec(window).with_format(self.format),
+                    window.len()
+                ));
+                frame.push_str(&chart::histogram(window, BUCKETS, BAR_WIDTH, &self.format));
+                frame.push('\n');
+            }
+        }
+        frame.push_str(&format!(
+            "\n{}Completed{}\n",
+            self.theme.style(Style::Section),
+            terminal::reset()
+        ));
+        let rows = terminal::size()
+            .map(|(_, rows)| (rows as usize).saturating_sub(30).max(5))
+            .unwrap_or(20);
+        for (name, durations) in self.completed.iter().rev().take(rows).rev() {
+            frame.push_str(&format!(
+                "{:<40} {}\n",
+                name,
+                durations.with_format(self.format)
+            ));
+        }
+        for warning in &self.warnings {
+            frame.push_str(&format!(
+                "{}Warning: {}{}\n",
+                self.theme.style(Style::Warning),
+                warning,
+                terminal::reset()
+            ));
+        }
+
+        frame
+    }
+
+    /// Renders the completed benchmarks followed by the details
+    /// and comparisons, which are printed when the Bencher finishes
+    pub fn summary(&self) -> String {
+        let mut summary = self
+            .completed
+            .iter()
+            .map(|(name, durations)| {
+                format!("{:<40} {}\n", name, durations.with_format(self.format))
+            })
+            .collect::<String>();
+        for detail in &self.details {
+            summary.push_str(detail);
+            summary.push('\n');
+        }
+
+        summary
+    }
+
+    /// Redraws the dashboard if the refresh interval passed
+    fn draw(&mut self, force: bool) {
+        if !force
+            && self
+                .drawn
+                .is_some_and(|drawn| drawn.elapsed() < self.refresh)
+        {
+            return;
+        }
+        if !self.active {
+            print!("{}", terminal::enter_screen());
+            self.active = true;
+        }
+        print!("{}{}", terminal::clear_screen(), self.render());
+        let _ = io::stdout().flush();
+        self.drawn = Some(Instant::now());
+    }
+
+    /// Switches back from the alternate screen
+    fn leave(&mut self) {
+        if self.active {
+            print!("{}", terminal::leave_screen());
+            let _ = io::stdout().flush();
+            self.active = false;
+        }
+    }
+}
+
+impl Default for DashboardReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reporter for DashboardReporter {
+    fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    fn set_format(&mut self, format: Format) {
+        self.format = format;
+    }
+
+    fn on_bench_start(&mut self, name: &str) {
+        self.current = Some(name.to_string());
+        self.samples.clear();
+        self.progress = (0, 0);
+        self.draw(true);
+    }
+
+    fn on_sample(&mut self, _name: &str, duration: Duration) {
+        self.samples.push(duration);
+    }
+
+    fn on_progress(&mut self, _name: &str, done: usize, total: usize, _elapsed: Duration) {
+        self.progress = (done, total);
+        self.draw(false);
+    }
+
+    fn on_bench_complete(&mut self, name: &str, durations: &BenchVec) {
+        self.current = None;
+        self.completed.push((name.to_string(), durations.clone()));
+        self.draw(true);
+    }
+
+    fn on_compare(&mut self, name: &str, previous: &str, difference: &DurationDifference) {
+        self.details.push(format!(
+            "Difference of {} to {}: {}",
+            name,
+            previous,
+            difference.with_format(self.format)
+        ));
+    }
+
+    fn on_message(&mut self, level: Level, message: &str) {
+        match level {
+            Level::Warning => {
+                self.warnings.push(message.to_string());
+                if self.warnings.len() > WARNINGS {
+                    self.warnings.remove(0);
+                }
+            }
+            Level::Heading | Level::Info => self.details.push(message.to_string()),
+            Level::Section | Level::Result | Level::Note => {}
+        }
+    }
+
+    fn on_finish(&mut self) {
+        self.leave();
+        print!("{}", self.summary());
+    }
+}
+
+impl Drop for DashboardReporter {
+    fn drop(&mut self) {
+        self.leave();
+    }
+}
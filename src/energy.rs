@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const POWERCAP_PATH: &str = "/sys/class/powercap";
+
+/// Reads the RAPL energy counters of all CPU packages via the powercap interface
+#[derive(Debug, Clone)]
+pub struct EnergyMeter {
+    domains: Vec<RaplDomain>,
+}
+
+#[derive(Debug, Clone)]
+struct RaplDomain {
+    energy_path: PathBuf,
+    max_energy: u64,
+}
+
+/// A snapshot of the energy counters in microjoules
+#[derive(Debug, Clone)]
+pub struct EnergyReading {
+    counters: Vec<u64>,
+}
+
+impl EnergyMeter {
+    /// Creates a meter for all package domains or returns None
+    /// if RAPL isn't supported or the counters aren't readable
+    pub fn new() -> Option<Self> {
+        let mut domains = fs::read_dir(POWERCAP_PATH)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| Self::is_package_domain(path))
+            .filter_map(|path| {
+                let max_energy = read_counter(&path.join("max_energy_range_uj"))?;
+                let energy_path = path.join("energy_uj");
+                read_counter(&energy_path)?;
+
+                Some(RaplDomain {
+                    energy_path,
+                    max_energy,
+                })
+            })
+            .collect::<Vec<RaplDomain>>();
+        domains.sort_by(|a, b| a.energy_path.cmp(&b.energy_path));
+
+        if domains.is_empty() {
+            None
+        } else {
+            Some(Self { domains })
+        }
+    }
+
+    /// Returns if the path is a top level domain like intel-rapl:0
+    fn is_package_domain(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("intel-rapl:"))
+            .map(|index| index.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false)
+    }
+
+    /// Reads the current values of the energy counters
+    pub fn read(&self) -> EnergyReading {
+        EnergyReading {
+            counters: self
+                .domains
+                .iter()
+                .map(|domain| read_counter(&domain.energy_path).unwrap_or(0))
+                .collect(),
+        }
+    }
+
+    /// Returns the energy in joules consumed between two readings
+    /// taking overflows of the counters into account
+    pub fn joules_between(&self, start: &EnergyReading, end: &EnergyReading) -> f64 {
+        let microjoules = self
+            .domains
+            .iter()
+            .zip(start.counters.iter().zip(end.counters.iter()))
+            .map(|(domain, (start, end))| {
+                if end >= start {
+                    end - start
+                } else {
+                    domain.max_energy - start + end
+                }
+            })
+            .sum::<u64>();
+
+        microjoules as f64 / 1_000_000f64
+    }
+}
+
+fn read_counter(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
@@ -0,0 +1,90 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::aggregate;
+use crate::benching::BenchVec;
+
+/// A file based cache of benchmark results keyed by a fingerprint of the
+/// benchmark binary and the benchmark name
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    path: PathBuf,
+    build_hash: u64,
+    entries: HashMap<u64, BenchVec>,
+}
+
+impl ResultCache {
+    /// Loads the cache from the given file. A missing file results in an empty cache.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = HashMap::new();
+        if path.exists() {
+            for line in fs::read_to_string(&path)?.lines() {
+                if let Some((fingerprint, durations)) = Self::parse_line(line) {
+                    entries.insert(fingerprint, durations);
+                }
+            }
+        }
+
+        Ok(Self {
+            path,
+            build_hash: build_hash()?,
+            entries,
+        })
+    }
+
+    /// Returns the fingerprint of a benchmark in the current build
+    pub fn fingerprint(&self, name: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.build_hash.hash(&mut hasher);
+        name.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Returns the stored result of a benchmark if its code didn't change
+    pub fn get(&self, name: &str) -> Option<&BenchVec> {
+        self.entries.get(&self.fingerprint(name))
+    }
+
+    /// Stores the result of a benchmark and writes the cache to its file
+    pub fn insert(&mut self, name: &str, durations: &BenchVec) -> io::Result<()> {
+        self.entries
+            .insert(self.fingerprint(name), durations.clone());
+
+        self.save()
+    }
+
+    /// Writes the cache to its file with a line per benchmark like the raw
+    /// output, but named by the fingerprint
+    pub fn save(&self) -> io::Result<()> {
+        let contents = self
+            .entries
+            .iter()
+            .map(|(fingerprint, durations)| {
+                aggregate::format_line(&format!("{:016x}", fingerprint), durations)
+            })
+            .collect::<String>();
+
+        fs::write(&self.path, contents)
+    }
+
+    fn parse_line(line: &str) -> Option<(u64, BenchVec)> {
+        let (fingerprint, durations) = aggregate::parse_line(line)?;
+
+        Some((u64::from_str_radix(&fingerprint, 16).ok()?, durations))
+    }
+}
+
+/// Hashes the contents of the current executable
+fn build_hash() -> io::Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    fs::read(env::current_exe()?)?.hash(&mut hasher);
+
+    Ok(hasher.finish())
+}
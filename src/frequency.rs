@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const CPU_PATH: &str = "/sys/devices/system/cpu";
+
+/// The CPU frequency range and thermal throttling observed during a benchmark
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrequencyStats {
+    pub min_khz: u64,
+    pub max_khz: u64,
+    pub throttle_events: u64,
+}
+
+impl FrequencyStats {
+    /// Returns the variation of the frequency relative to the maximum frequency
+    pub fn variation(&self) -> f64 {
+        if self.max_khz == 0 {
+            0f64
+        } else {
+            (self.max_khz - self.min_khz) as f64 / self.max_khz as f64
+        }
+    }
+}
+
+/// Samples the average CPU frequency from sysfs in a background thread
+pub struct FrequencySampler {
+    running: Arc<AtomicBool>,
+    stats: Arc<Mutex<Option<FrequencyStats>>>,
+    throttle_start: u64,
+    handle: JoinHandle<()>,
+}
+
+impl FrequencySampler {
+    /// Starts sampling the CPU frequency with the given interval
+    pub fn start(interval: Duration) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let stats = Arc::new(Mutex::new(None));
+        let handle = {
+            let running = Arc::clone(&running);
+            let stats = Arc::clone(&stats);
+            thread::spawn(move || {
+                let paths = cpu_files("cpufreq/scaling_cur_freq");
+                while running.load(Ordering::Relaxed) {
+                    if let Some(frequency) = average_frequency(&paths) {
+                        let mut stats = stats.lock().unwrap();
+                        let stats = stats.get_or_insert(FrequencyStats {
+                            min_khz: frequency,
+                            max_khz: frequency,
+                            throttle_events: 0,
+                        });
+                        stats.min_khz = stats.min_khz.min(frequency);
+                        stats.max_khz = stats.max_khz.max(frequency);
+                    }
+                    thread::sleep(interval);
+                }
+            })
+        };
+
+        Self {
+            running,
+            stats,
+            throttle_start: throttle_count(),
+            handle,
+        }
+    }
+
+    /// Stops sampling and returns the observed frequency range
+    /// if the frequency could be read
+    pub fn stop(self) -> Option<FrequencyStats> {
+        self.running.store(false, Ordering::Relaxed);
+        let _ = self.handle.join();
+        let mut stats = (*self.stats.lock().unwrap())?;
+        stats.throttle_events = throttle_count().saturating_sub(self.throttle_start);
+
+        Some(stats)
+    }
+}
+
+/// Returns the given file for all CPUs that provide it
+fn cpu_files(file: &str) -> Vec<PathBuf> {
+    fs::read_dir(CPU_PATH)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    name.starts_with("cpu") && name[3..].chars().all(|c| c.is_ascii_digit())
+                })
+                .map(|entry| entry.path().join(file))
+                .filter(|path| path.exists())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_value(path: &PathBuf) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Returns the average frequency of all CPUs in kHz
+fn average_frequency(paths: &[PathBuf]) -> Option<u64> {
+    let frequencies = paths.iter().filter_map(read_value).collect::<Vec<u64>>();
+    if frequencies.is_empty() {
+        None
+    } else {
+        Some(frequencies.iter().sum::<u64>() / frequencies.len() as u64)
+    }
+}
+
+/// Returns the number of thermal throttling events of all CPUs
+fn throttle_count() -> u64 {
+    cpu_files("thermal_throttle/core_throttle_count")
+        .iter()
+        .filter_map(read_value)
+        .sum()
+}
@@ -0,0 +1,78 @@
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use crate::units::{Format, FormatWith};
+
+/// The average durations of the benchmarks of a group
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupSummary {
+    pub name: String,
+    pub members: Vec<(String, Duration)>,
+}
+
+impl GroupSummary {
+    /// Creates a summary of a group without members
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            members: Vec::new(),
+        }
+    }
+
+    /// Adds the average duration of a benchmark to the group
+    pub fn push(&mut self, name: &str, average: Duration) {
+        self.members.push((name.to_string(), average));
+    }
+
+    /// Returns the geometric mean of the durations of the members,
+    /// so members with long durations don't dominate the rollup
+    pub fn geometric_mean(&self) -> Option<Duration> {
+        if self.members.is_empty() {
+            return None;
+        }
+        let log_sum = self
+            .members
+            .iter()
+            .map(|(_, d)| (d.as_nanos() as f64).max(1f64).ln())
+            .sum::<f64>();
+
+        Some(Duration::from_nanos(
+            (log_sum / self.members.len() as f64).exp().round() as u64,
+        ))
+    }
+
+    /// Returns the member with the shortest duration
+    pub fn best(&self) -> Option<&(String, Duration)> {
+        self.members.iter().min_by_key(|(_, d)| *d)
+    }
+
+    /// Returns the member with the longest duration
+    pub fn worst(&self) -> Option<&(String, Duration)> {
+        self.members.iter().max_by_key(|(_, d)| *d)
+    }
+}
+
+impl Display for GroupSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for GroupSummary {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        match (self.geometric_mean(), self.best(), self.worst()) {
+            (Some(mean), Some(best), Some(worst)) => write!(
+                f,
+                "{}: geometric mean {} over {} benchmarks, best {} ({}), worst {} ({})",
+                self.name,
+                format.duration(mean),
+                self.members.len(),
+                best.0,
+                format.duration(best.1),
+                worst.0,
+                format.duration(worst.1)
+            ),
+            _ => write!(f, "{}: no benchmarks", self.name),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::cmp::Reverse;
+use std::fmt::{self, Display};
+
+use crate::json;
+use crate::units::{Format, FormatWith};
+
+/// A program point that allocated memory, read from a DHAT heap profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotSpot {
+    pub bytes: u64,
+    pub blocks: u64,
+    /// The innermost frame of the allocation outside of the allocator,
+    /// like `app::parse (src/parse.rs:10:5)`
+    pub frame: String,
+}
+
+impl Display for HotSpot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for HotSpot {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes in {} allocations at {}",
+            format.count(self.bytes as usize),
+            format.count(self.blocks as usize),
+            self.frame
+        )
+    }
+}
+
+/// Returns if the frame belongs to the allocator or the profiler
+/// instead of the code that requested the memory
+fn is_allocator_frame(frame: &str) -> bool {
+    [
+        "[root]", "dhat::", "alloc::", "core::", "std::", "__rust", "<alloc::", "<dhat::",
+    ]
+    .iter()
+    .any(|prefix| frame.starts_with(prefix))
+}
+
+/// Parses the `dhat-heap.json` profile written by dhat-rs and returns the
+/// program points that allocated the most bytes in total, up to the limit
+pub fn parse_hot_spots(profile: &str, limit: usize) -> Option<Vec<HotSpot>> {
+    let profile = json::parse(profile)?;
+    let frames = profile
+        .get("ftbl")?
+        .as_array()?
+        .iter()
+        .map(|frame| {
+            let frame = frame.as_str()?;
+            // frames are prefixed with their address like `0x5581c2a0: `
+            Some(match frame.split_once(": ") {
+                Some((address, rest)) if address.starts_with("0x") => rest.to_string(),
+                _ => frame.to_string(),
+            })
+        })
+        .collect::<Option<Vec<String>>>()?;
+    let mut hot_spots = profile
+        .get("pps")?
+        .as_array()?
+        .iter()
+        .map(|point| {
+            let frame = point
+                .get("fs")?
+                .as_array()?
+                .iter()
+                .filter_map(|index| frames.get(index.as_f64()? as usize))
+                .find(|frame| !is_allocator_frame(frame))
+                .cloned()
+                .unwrap_or_else(|| "[unknown]".to_string());
+
+            Some(HotSpot {
+                bytes: point.get("tb")?.as_f64()? as u64,
+                blocks: point.get("tbk")?.as_f64()? as u64,
+                frame,
+            })
+        })
+        .collect::<Option<Vec<HotSpot>>>()?;
+    hot_spots.sort_by_key(|hot_spot| Reverse(hot_spot.bytes));
+    hot_spots.truncate(limit);
+
+    Some(hot_spots)
+}
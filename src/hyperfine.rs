@@ -0,0 +1,47 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::benching::BenchVec;
+use crate::json::{self, Value};
+
+/// Reads the results of hyperfine's `--export-json` output. Each command is
+/// named like hyperfine names it, by the command line or the name given with
+/// `--command-name`, and its durations are the wall clock times of its runs.
+pub fn read_results<P: AsRef<Path>>(path: P) -> io::Result<Vec<(String, BenchVec)>> {
+    let path = path.as_ref();
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid hyperfine results in {}", path.display()),
+        )
+    };
+    let document = json::parse(&fs::read_to_string(path)?).ok_or_else(invalid)?;
+    document
+        .get("results")
+        .and_then(Value::as_array)
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|result| {
+            let name = result.get("command").and_then(Value::as_str);
+            let times = result
+                .get("times")
+                .and_then(Value::as_array)
+                .and_then(|times| {
+                    times
+                        .iter()
+                        .map(|t| {
+                            t.as_f64()
+                                .filter(|s| s.is_finite() && *s >= 0f64)
+                                .map(Duration::from_secs_f64)
+                        })
+                        .collect::<Option<Vec<Duration>>>()
+                });
+            match (name, times) {
+                (Some(name), Some(times)) => Ok((name.to_string(), BenchVec::from_vec(&times))),
+                _ => Err(invalid()),
+            }
+        })
+        .collect()
+}
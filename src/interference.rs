@@ -0,0 +1,77 @@
+use std::fmt::{self, Display};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// A load that runs in the background while a benchmark is measured
+/// to simulate the interference on a shared machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundLoad {
+    /// The given number of threads spinning on the CPU
+    Busy { threads: usize },
+    /// The given number of threads repeatedly writing buffers of the given size
+    /// to compete for the caches and the memory bandwidth
+    MemoryThrash { threads: usize, bytes: usize },
+}
+
+impl Display for BackgroundLoad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackgroundLoad::Busy { threads } => write!(f, "{} busy threads", threads),
+            BackgroundLoad::MemoryThrash { threads, bytes } => write!(
+                f,
+                "{} threads thrashing {} bytes of memory each",
+                threads, bytes
+            ),
+        }
+    }
+}
+
+/// Runs a background load in separate threads until it's stopped
+pub struct LoadGenerator {
+    running: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl LoadGenerator {
+    /// Starts the threads of the given background load
+    pub fn start(load: BackgroundLoad) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let (threads, bytes) = match load {
+            BackgroundLoad::Busy { threads } => (threads, 0),
+            BackgroundLoad::MemoryThrash { threads, bytes } => (threads, bytes),
+        };
+        let handles = (0..threads)
+            .map(|_| {
+                let running = Arc::clone(&running);
+                thread::spawn(move || {
+                    let mut buffer = vec![0u8; bytes];
+                    let mut value = 0u8;
+                    while running.load(Ordering::Relaxed) {
+                        if buffer.is_empty() {
+                            black_box(value);
+                        } else {
+                            // one write per cache line is enough to evict it
+                            for byte in buffer.iter_mut().step_by(64) {
+                                *byte = value;
+                            }
+                            black_box(&buffer);
+                        }
+                        value = value.wrapping_add(1);
+                    }
+                })
+            })
+            .collect();
+
+        Self { running, handles }
+    }
+
+    /// Stops the background load and waits for its threads to finish
+    pub fn stop(self) {
+        self.running.store(false, Ordering::Relaxed);
+        for handle in self.handles {
+            let _ = handle.join();
+        }
+    }
+}
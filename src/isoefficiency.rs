@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use crate::benching::{file_stem, BenchVec};
+use crate::units::{Format, FormatWith};
+
+/// The measurements of a benchmark for every combination
+/// of a series of thread counts and problem sizes
+#[derive(Debug, Clone)]
+pub struct IsoEfficiencyResult {
+    pub name: String,
+    pub threads: Vec<usize>,
+    pub sizes: Vec<usize>,
+    /// The durations keyed by thread count and problem size
+    pub durations: BTreeMap<(usize, usize), BenchVec>,
+}
+
+impl IsoEfficiencyResult {
+    /// Creates a new empty result for the given thread counts and problem sizes
+    pub fn new(name: &str, threads: &[usize], sizes: &[usize]) -> Self {
+        let mut threads = threads.to_vec();
+        threads.sort_unstable();
+        threads.dedup();
+        let mut sizes = sizes.to_vec();
+        sizes.sort_unstable();
+        sizes.dedup();
+
+        Self {
+            name: name.to_string(),
+            threads,
+            sizes,
+            durations: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the measurement for a thread count and problem size
+    pub fn push(&mut self, threads: usize, size: usize, durations: BenchVec) -> &mut Self {
+        self.durations.insert((threads, size), durations);
+
+        self
+    }
+
+    /// Returns the parallel efficiency E(p, n) = T(1, n) / (p * T(p, n))
+    /// for the given thread count and problem size
+    pub fn efficiency(&self, threads: usize, size: usize) -> Option<f64> {
+        let baseline = self.durations.get(&(1, size))?.average().as_nanos() as f64;
+        let duration = self.durations.get(&(threads, size))?.average().as_nanos() as f64;
+
+        Some(baseline / (threads as f64 * duration))
+    }
+
+    /// Returns the iso-efficiency curve for the given efficiency, the problem
+    /// size needed for each thread count to reach it. The size is interpolated
+    /// linearly between the measured sizes and is None if no measured size
+    /// reaches the efficiency.
+    pub fn iso_efficiency(&self, efficiency: f64) -> Vec<(usize, Option<f64>)> {
+        self.threads
+            .iter()
+            .map(|&threads| {
+                let points = self
+                    .sizes
+                    .iter()
+                    .filter_map(|&size| Some((size as f64, self.efficiency(threads, size)?)))
+                    .collect::<Vec<(f64, f64)>>();
+                let size = match points.iter().position(|(_, e)| *e >= efficiency) {
+                    Some(0) => Some(points[0].0),
+                    Some(index) => {
+                        let (size, e) = points[index];
+                        let (previous_size, previous_e) = points[index - 1];
+                        if e > previous_e {
+                            Some(
+                                previous_size
+                                    + (size - previous_size) * (efficiency - previous_e)
+                                        / (e - previous_e),
+                            )
+                        } else {
+                            Some(size)
+                        }
+                    }
+                    None => None,
+                };
+                (threads, size)
+            })
+            .collect()
+    }
+
+    /// Returns the name of the sweep usable as file name
+    pub fn plot_name(&self) -> String {
+        file_stem(&self.name)
+    }
+
+    /// Formats the efficiencies as tab separated matrix with a row
+    /// per problem size and a column per thread count for plotting
+    pub fn to_matrix(&self) -> String {
+        self.to_matrix_with(&Format::default())
+    }
+
+    /// Formats the efficiency matrix like `to_matrix` with the precision
+    /// of the given format
+    pub fn to_matrix_with(&self, format: &Format) -> String {
+        let mut matrix = format!(
+            "size\t{}\n",
+            self.threads
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<String>>()
+                .join("\t")
+        );
+        for &size in &self.sizes {
+            let row = self
+                .threads
+                .iter()
+                .map(|&threads| {
+                    self.efficiency(threads, size)
+                        .map(|e| format.exported(e))
+                        .unwrap_or_else(|| "-".to_string())
+                })
+                .collect::<Vec<String>>();
+            matrix.push_str(&format!("{}\t{}\n", size, row.join("\t")));
+        }
+
+        matrix
+    }
+}
+
+impl Display for IsoEfficiencyResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for IsoEfficiencyResult {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(f, "{:>12}", "size \\ threads")?;
+        for threads in &self.threads {
+            write!(f, "  {:>8}", threads)?;
+        }
+        writeln!(f)?;
+        for &size in &self.sizes {
+            write!(f, "{:>14}", size)?;
+            for &threads in &self.threads {
+                let efficiency = self
+                    .efficiency(threads, size)
+                    .map(|e| format.number(e))
+                    .unwrap_or_else(|| "-".to_string());
+                write!(f, "  {:>8}", efficiency)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::aggregate;
+use crate::benching::BenchVec;
+
+/// The environment variable that selects the benchmark to run
+/// when the process was started as an isolated child
+pub const ISOLATION_ENV: &str = "BENCHLIB_ISOLATED";
+
+const RESULT_PREFIX: &str = "benchlib-isolated-result:";
+
+/// Returns the name of the benchmark to run if the process
+/// was started as an isolated child by the Bencher
+pub fn target() -> Option<String> {
+    env::var(ISOLATION_ENV).ok()
+}
+
+/// Formats the durations of a benchmark as a line that can be parsed by the parent
+pub fn format_result(name: &str, durations: &BenchVec) -> String {
+    format!(
+        "{}{}",
+        RESULT_PREFIX,
+        aggregate::format_line(name, durations)
+    )
+}
+
+/// Parses the durations of a benchmark from the output of a child process
+pub fn parse_result(output: &str) -> Option<BenchVec> {
+    let line = output.lines().find_map(|l| l.strip_prefix(RESULT_PREFIX))?;
+
+    aggregate::parse_line(line).map(|(_, durations)| durations)
+}
+
+/// Writes the durations to stdout for the parent process to collect.
+/// The standard output is written directly so it isn't captured by the test harness.
+pub fn report(name: &str, durations: &BenchVec) {
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(format_result(name, durations).as_bytes());
+    let _ = stdout.flush();
+}
+
+/// Runs the current executable with the same arguments in a child process
+/// with the given benchmark selected and collects its durations
+pub fn run(name: &str) -> io::Result<BenchVec> {
+    let output = Command::new(env::current_exe()?)
+        .args(env::args_os().skip(1))
+        .env(ISOLATION_ENV, name)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()?;
+
+    parse_result(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "the child process didn't report a result ({})",
+                output.status
+            ),
+        )
+    })
+}
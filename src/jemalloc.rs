@@ -0,0 +1,71 @@
+use std::fmt::{self, Display};
+
+use tikv_jemalloc_ctl::{epoch, stats};
+
+use crate::units::{Format, FormatWith};
+
+/// A snapshot of the statistics of the jemalloc allocator
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JemallocStats {
+    pub allocated: usize,
+    pub active: usize,
+    pub resident: usize,
+}
+
+impl JemallocStats {
+    /// Reads the current statistics or returns None if jemalloc
+    /// isn't used as allocator or the statistics can't be read
+    pub fn now() -> Option<Self> {
+        epoch::advance().ok()?;
+
+        Some(Self {
+            allocated: stats::allocated::read().ok()?,
+            active: stats::active::read().ok()?,
+            resident: stats::resident::read().ok()?,
+        })
+    }
+
+    /// Returns the fraction of active memory that isn't allocated
+    pub fn fragmentation(&self) -> f64 {
+        if self.active == 0 {
+            0f64
+        } else {
+            1f64 - self.allocated as f64 / self.active as f64
+        }
+    }
+
+    /// Returns the changes since the given snapshot
+    pub fn since(&self, start: &Self) -> JemallocDelta {
+        JemallocDelta {
+            allocated: self.allocated as i64 - start.allocated as i64,
+            resident: self.resident as i64 - start.resident as i64,
+            fragmentation: self.fragmentation(),
+        }
+    }
+}
+
+/// The changes of the jemalloc statistics during a benchmark
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JemallocDelta {
+    pub allocated: i64,
+    pub resident: i64,
+    pub fragmentation: f64,
+}
+
+impl Display for JemallocDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for JemallocDelta {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(
+            f,
+            "jemalloc: {:+} bytes allocated, {:+} bytes resident, {}% fragmentation",
+            self.allocated,
+            self.resident,
+            format.number(self.fragmentation * 100f64)
+        )
+    }
+}
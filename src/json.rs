@@ -0,0 +1,379 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::benching::{BenchVec, DurationDifference, Verdict};
+use crate::reporter::{ColorChoice, ConsoleReporter, Level, Reporter};
+use crate::terminal::Theme;
+use crate::units::Format;
+
+/// Escapes the text as a JSON string including the quotes
+pub fn string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+
+    escaped
+}
+
+/// Formats the number as JSON value. JSON has no infinite values and no NaN,
+/// so they are written as null.
+pub fn number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// A parsed JSON value. Objects keep the order of their members.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns the member of an object with the given key
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a JSON document. Returns None if it isn't valid JSON.
+pub fn parse(text: &str) -> Option<Value> {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+
+    if parser.chars.next().is_none() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> Option<()> {
+        for expected in word.chars() {
+            if self.chars.next()? != expected {
+                return None;
+            }
+        }
+
+        Some(())
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        match *self.chars.peek()? {
+            'n' => self.expect("null").map(|_| Value::Null),
+            't' => self.expect("true").map(|_| Value::Bool(true)),
+            'f' => self.expect("false").map(|_| Value::Bool(false)),
+            '"' => self.string().map(Value::String),
+            '[' => {
+                self.chars.next();
+                let mut values = Vec::new();
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&']') {
+                    self.chars.next();
+                    return Some(Value::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.chars.next()? {
+                        ',' => continue,
+                        ']' => return Some(Value::Array(values)),
+                        _ => return None,
+                    }
+                }
+            }
+            '{' => {
+                self.chars.next();
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&'}') {
+                    self.chars.next();
+                    return Some(Value::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    members.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.chars.next()? {
+                        ',' => continue,
+                        '}' => return Some(Value::Object(members)),
+                        _ => return None,
+                    }
+                }
+            }
+            _ => self.number().map(Value::Number),
+        }
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        let mut text = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                text.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+
+        text.parse().ok()
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect("\"")?;
+        let mut text = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(text),
+                '\\' => match self.chars.next()? {
+                    'n' => text.push('\n'),
+                    'r' => text.push('\r'),
+                    't' => text.push('\t'),
+                    'b' => text.push('\u{8}'),
+                    'f' => text.push('\u{c}'),
+                    'u' => {
+                        let code = self.hex()?;
+                        if (0xd800..0xdc00).contains(&code) {
+                            self.expect("\\u")?;
+                            let low = self.hex()?;
+                            let combined =
+                                0x10000 + ((code - 0xd800) << 10) + (low.checked_sub(0xdc00)?);
+                            text.push(char::from_u32(combined)?);
+                        } else {
+                            text.push(char::from_u32(code)?);
+                        }
+                    }
+                    c => text.push(c),
+                },
+                c => text.push(c),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let digits = (0..4)
+            .map(|_| self.chars.next())
+            .collect::<Option<String>>()?;
+
+        u32::from_str_radix(&digits, 16).ok()
+    }
+}
+
+/// Formats the result of a benchmark as JSON object with the durations in nanoseconds
+pub fn result_record(name: &str, durations: &BenchVec) -> String {
+    format!(
+        "{{\"type\":\"result\",\"name\":{},\"iterations\":{},\"mean_ns\":{},\"stddev_ns\":{},\"min_ns\":{},\"median_ns\":{},\"p90_ns\":{},\"p99_ns\":{},\"max_ns\":{}}}",
+        string(name),
+        durations.len(),
+        durations.average().as_nanos(),
+        number(durations.standard_deviation()),
+        durations.min().as_nanos(),
+        durations.percentile(50f64).as_nanos(),
+        durations.percentile(90f64).as_nanos(),
+        durations.percentile(99f64).as_nanos(),
+        durations.max().as_nanos()
+    )
+}
+
+/// Formats the comparison of two benchmarks as JSON object
+pub fn comparison_record(name: &str, previous: &str, difference: &DurationDifference) -> String {
+    let verdict = match difference.verdict {
+        Verdict::Improvement => "improvement",
+        Verdict::Minor => "minor",
+        Verdict::Regression => "regression",
+        Verdict::Insignificant => "insignificant",
+    };
+    format!(
+        "{{\"type\":\"comparison\",\"name\":{},\"previous\":{},\"difference_ns\":{}{},\"relative\":{},\"significant\":{},\"verdict\":\"{}\"}}",
+        string(name),
+        string(previous),
+        if difference.positive || difference.inner.is_zero() {
+            ""
+        } else {
+            "-"
+        },
+        difference.inner.as_nanos(),
+        number(difference.relative),
+        difference.significant,
+        verdict
+    )
+}
+
+/// Formats a note attached to a benchmark as JSON object
+pub fn annotation_record(name: &str, text: &str) -> String {
+    format!(
+        "{{\"type\":\"annotation\",\"name\":{},\"text\":{}}}",
+        string(name),
+        string(text)
+    )
+}
+
+/// Formats the results as a document of the Bencher Metric Format (BMF) that
+/// continuous benchmarking services like bencher.dev track. Each benchmark
+/// has a latency measure with the mean in nanoseconds and the 5th and
+/// 95th percentile as bounds. Of benchmarks with the same name only the last is written.
+pub fn bmf_document(results: &[(&str, &BenchVec)]) -> String {
+    let benchmarks = results
+        .iter()
+        .enumerate()
+        .filter(|(i, (name, _))| !results[i + 1..].iter().any(|(other, _)| other == name))
+        .map(|(_, (name, durations))| {
+            format!(
+                "{}:{{\"latency\":{{\"value\":{},\"lower_value\":{},\"upper_value\":{}}}}}",
+                string(name),
+                durations.average().as_nanos(),
+                durations.percentile(5f64).as_nanos(),
+                durations.percentile(95f64).as_nanos()
+            )
+        })
+        .collect::<Vec<String>>();
+
+    format!("{{{}}}", benchmarks.join(","))
+}
+
+/// Writes a JSON object per line to stdout for every result, comparison and annotation,
+/// so wrapper scripts can parse the results without scraping the text.
+/// Everything meant for humans, like the messages and the progress bar,
+/// is printed to stderr instead.
+#[derive(Debug, Clone)]
+pub struct JsonLinesReporter {
+    console: ConsoleReporter,
+}
+
+impl JsonLinesReporter {
+    pub fn new() -> Self {
+        Self {
+            console: ConsoleReporter::stderr(),
+        }
+    }
+
+    fn write_record(&self, record: &str) {
+        println!("{}", record);
+        let _ = io::stdout().flush();
+    }
+}
+
+impl Default for JsonLinesReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reporter for JsonLinesReporter {
+    fn set_color(&mut self, choice: ColorChoice) {
+        self.console.set_color(choice);
+    }
+
+    fn set_theme(&mut self, theme: Theme) {
+        self.console.set_theme(theme);
+    }
+
+    fn set_compact(&mut self, enabled: bool) {
+        self.console.set_compact(enabled);
+    }
+
+    fn set_ci(&mut self, enabled: bool) {
+        self.console.set_ci(enabled);
+    }
+
+    fn set_format(&mut self, format: Format) {
+        self.console.set_format(format);
+    }
+
+    fn on_bench_start(&mut self, name: &str) {
+        self.console.on_bench_start(name);
+    }
+
+    fn on_progress(&mut self, name: &str, done: usize, total: usize, elapsed: Duration) {
+        self.console.on_progress(name, done, total, elapsed);
+    }
+
+    fn on_statistics(&mut self, name: &str, durations: &BenchVec, elapsed: Duration) {
+        self.console.on_statistics(name, durations, elapsed);
+    }
+
+    fn on_bench_complete(&mut self, name: &str, durations: &BenchVec) {
+        self.write_record(&result_record(name, durations));
+    }
+
+    fn on_annotate(&mut self, name: &str, text: &str) {
+        self.write_record(&annotation_record(name, text));
+    }
+
+    fn on_compare(&mut self, name: &str, previous: &str, difference: &DurationDifference) {
+        self.console.on_compare(name, previous, difference);
+        self.write_record(&comparison_record(name, previous, difference));
+    }
+
+    fn on_message(&mut self, level: Level, message: &str) {
+        self.console.on_message(level, message);
+    }
+
+    fn on_ci_command(&mut self, command: &str) {
+        self.console.on_ci_command(command);
+    }
+
+    fn on_finish(&mut self) {
+        self.console.on_finish();
+    }
+}
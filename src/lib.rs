@@ -1,11 +1,154 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "std")]
+pub mod affinity;
+#[cfg(feature = "std")]
+pub mod aggregate;
+#[cfg(feature = "alloc-counter")]
+pub mod alloc;
+#[cfg(feature = "std")]
+pub mod bandwidth;
+#[cfg(feature = "std")]
 pub mod benching;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod cachegrind;
+#[cfg(feature = "std")]
+pub mod chart;
+#[cfg(feature = "std")]
+pub mod ci;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod complexity;
+#[cfg(feature = "std")]
+pub mod concurrent;
+#[cfg(feature = "std")]
+pub mod coordination;
+#[cfg(feature = "std")]
+pub mod criterion;
+#[cfg(feature = "std")]
+pub mod dashboard;
+#[cfg(feature = "std")]
+pub mod energy;
+#[cfg(feature = "std")]
+pub mod fingerprint;
+#[cfg(feature = "std")]
+pub mod frequency;
+#[cfg(feature = "std")]
+pub mod group;
+#[cfg(feature = "std")]
+pub mod heap;
+#[cfg(feature = "std")]
+pub mod hyperfine;
+#[cfg(feature = "std")]
+pub mod interference;
+#[cfg(feature = "std")]
+pub mod isoefficiency;
+#[cfg(feature = "std")]
+pub mod isolation;
+#[cfg(feature = "jemalloc")]
+pub mod jemalloc;
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "std")]
+pub mod load;
+#[cfg(feature = "std")]
+pub mod massif;
+#[cfg(feature = "std")]
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(all(feature = "numa", target_os = "linux"))]
+pub mod numa;
+#[cfg(all(feature = "perf", target_os = "linux"))]
+pub mod perf;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod perf_record;
+#[cfg(feature = "std")]
+pub mod priority;
+#[cfg(feature = "std")]
+pub mod probes;
+#[cfg(feature = "http-push")]
+pub mod push;
+#[cfg(feature = "std")]
+pub mod reporter;
+#[cfg(all(feature = "std", unix))]
+pub mod rusage;
+pub mod sampling;
+#[cfg(feature = "std")]
+pub mod scaling;
+#[cfg(feature = "rayon-stats")]
+pub mod scheduler;
+#[cfg(feature = "std")]
+pub mod statistics;
+#[cfg(feature = "std")]
+pub mod template;
+#[cfg(feature = "std")]
+pub mod terminal;
+#[cfg(feature = "std")]
+pub mod throughput;
+#[cfg(feature = "std")]
+pub mod topology;
+#[cfg(feature = "std")]
+pub mod units;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
-    use super::benching::Bencher;
-    use crate::benching::BENCH_FILE_HEAD;
+    use super::benching::{
+        shell_hook, BenchVec, Bencher, CompareThresholds, DurationDifference, OverheadModel,
+        ReportMode, Verdict,
+    };
+    use crate::aggregate::{self, RankResults};
+    use crate::benching::{BENCH_FILE_HEAD, SCALING_FILE_HEAD};
+    use crate::cachegrind::CachegrindStats;
+    use crate::chart::{self, DistributionChart};
+    use crate::ci;
+    use crate::clock::{
+        Clock, InstantClock, MockClock, ProcessCpuClock, ThreadCpuClock, RESOLUTION_TIMEOUT,
+    };
+    use crate::complexity::{Complexity, SizeSweepResult};
+    use crate::concurrent::{ConcurrentResult, RayonSpawner, Spawner, StdSpawner, Task};
+    use crate::coordination::ProcessBarrier;
+    use crate::criterion;
+    use crate::dashboard::DashboardReporter;
+    use crate::frequency::FrequencyStats;
+    use crate::group::GroupSummary;
+    use crate::heap::{self, HotSpot};
+    use crate::hyperfine;
+    use crate::interference::BackgroundLoad;
+    use crate::isoefficiency::IsoEfficiencyResult;
+    use crate::isolation;
+    use crate::json;
+    use crate::massif::MassifSnapshot;
+    use crate::memory::RssSampler;
+    use crate::metrics::Metrics;
+    use crate::priority::Priority;
+    use crate::reporter::{ColorChoice, ConsoleReporter, Level, Reporter};
+    use crate::sampling::{self, Samples, Summary, Timer};
+    use crate::scaling::{ScalingMode, ScalingResult};
+    use crate::statistics;
+    use crate::template::{ResultTemplate, TemplateError};
+    use crate::terminal::{self, Color, Style, Theme, ThemeStyle};
+    use crate::throughput::Throughput;
+    use crate::topology::{Cpu, CpuTopology, Occupancy, Placement};
+    use crate::units::{self, Format, NumberFormat, Precision, TimeUnit};
+    use rayon::prelude::*;
     use std::fs::{read_to_string, remove_file, File};
-    use std::io::BufWriter;
+    use std::io::{BufWriter, Write};
+    use std::process::Command;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn it_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Bencher>();
+    }
 
     #[test]
     fn it_works() {
@@ -33,6 +176,31 @@ mod tests {
         assert!(count > 1);
     }
 
+    #[test]
+    fn it_updates_statistics_while_iterating_automatically() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut bencher = Bencher::new();
+        bencher
+            .set_reporter(RecordingReporter {
+                events: Arc::clone(&events),
+            })
+            .set_iterations(0)
+            .set_max_iterations(5)
+            .bench("auto", || 3 * 4)
+            .set_iterations(3)
+            .bench("fixed", || 3 * 4);
+        let statistics = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.starts_with("statistics"))
+            .cloned()
+            .collect::<Vec<String>>();
+        assert!(!statistics.is_empty());
+        assert!(statistics.iter().all(|e| e.starts_with("statistics auto")));
+        assert_eq!(statistics[0], "statistics auto 1");
+    }
+
     #[test]
     fn it_reports_differences() {
         let mut bencher = Bencher::new();
@@ -41,6 +209,1040 @@ mod tests {
         bencher.compare();
     }
 
+    struct RecordingReporter {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Reporter for RecordingReporter {
+        fn on_bench_start(&mut self, name: &str) {
+            self.events.lock().unwrap().push(format!("start {}", name));
+        }
+
+        fn on_sample(&mut self, name: &str, _duration: Duration) {
+            self.events.lock().unwrap().push(format!("sample {}", name));
+        }
+
+        fn on_statistics(&mut self, name: &str, durations: &BenchVec, _elapsed: Duration) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("statistics {} {}", name, durations.len()));
+        }
+
+        fn on_bench_complete(&mut self, name: &str, durations: &BenchVec) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("complete {} {}", name, durations.len()));
+        }
+
+        fn on_compare(&mut self, name: &str, previous: &str, _difference: &DurationDifference) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("compare {} {}", name, previous));
+        }
+
+        fn on_message(&mut self, level: Level, message: &str) {
+            if level == Level::Warning {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("warning {}", message));
+            }
+        }
+
+        fn on_finish(&mut self) {
+            self.events.lock().unwrap().push("finish".to_string());
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_writes_the_output_to_a_writer() {
+        let buffer = SharedBuffer::default();
+        let mut bencher = Bencher::new();
+        bencher
+            .set_output(buffer.clone())
+            .set_iterations(10)
+            .bench("captured", || 3 * 4);
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(output.starts_with("\ncaptured\n"));
+        assert!(output.contains("(±"));
+        assert!(!output.contains('\u{1b}'));
+
+        let colored = SharedBuffer::default();
+        let mut reporter = ConsoleReporter::with_writer(colored.clone());
+        reporter.set_color(ColorChoice::Always);
+        reporter.on_message(Level::Warning, "styled");
+        let output = String::from_utf8(colored.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("Warning: styled"));
+        assert_eq!(output.contains('\u{1b}'), !terminal::reset().is_empty());
+    }
+
+    #[test]
+    fn it_condenses_the_output_on_ci() {
+        let buffer = SharedBuffer::default();
+        let mut reporter = ConsoleReporter::with_writer(buffer.clone());
+        reporter.set_ci(true);
+        reporter.set_color(ColorChoice::Auto);
+        reporter.on_bench_start("sort");
+        reporter.on_message(Level::Result, "Result: 1.00µs");
+        reporter.on_message(Level::Info, "Peak RSS: 10 bytes");
+        reporter.set_ci(false);
+        reporter.on_bench_start("search");
+        reporter.on_message(Level::Result, "Result: 2.00µs");
+        reporter.on_ci_command("::warning title=t::m");
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            output,
+            "sort: Result: 1.00µs\nPeak RSS: 10 bytes\n\nsearch\nResult: 2.00µs\n::warning title=t::m\n"
+        );
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_ci_mode(true)
+            .set_iterations(10)
+            .bench("condensed", || 3 * 4)
+            .set_ci_mode(ci::is_ci());
+    }
+
+    #[test]
+    fn it_compares_to_a_baseline() {
+        let fast = BenchVec::from_vec(&[Duration::from_micros(10); 20]);
+        std::fs::write(
+            "test-baseline.tsv",
+            aggregate::format_line("sleep", &fast) + &aggregate::format_line("other", &fast),
+        )
+        .unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut bencher = Bencher::new();
+        bencher
+            .set_baseline("test-baseline.tsv")
+            .unwrap()
+            .set_regressions_as_errors(true)
+            .set_reporter(RecordingReporter {
+                events: Arc::clone(&events),
+            })
+            .set_iterations(3)
+            .bench("sleep", || std::thread::sleep(Duration::from_millis(1)))
+            .bench("new", || 3 * 4);
+        remove_file("test-baseline.tsv").unwrap();
+        assert!(events
+            .lock()
+            .unwrap()
+            .contains(&"compare sleep sleep (baseline)".to_string()));
+        assert!(!events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| e.starts_with("compare new")));
+
+        assert_eq!(
+            ci::github_annotation(
+                "warning",
+                Some(("src/main.rs", 12)),
+                "Benchmark regression",
+                "sort, 50% slower\nagain"
+            ),
+            "::warning file=src/main.rs,line=12,title=Benchmark regression::sort, 50%25 slower%0Aagain"
+        );
+        assert_eq!(
+            ci::github_annotation("error", None, "a:b", "c"),
+            "::error title=a%3Ab::c"
+        );
+    }
+
+    #[test]
+    fn it_summarizes_groups() {
+        let mut summary = GroupSummary::new("sorting");
+        assert_eq!(summary.to_string(), "sorting: no benchmarks");
+        summary.push("quick", Duration::from_micros(1));
+        summary.push("merge", Duration::from_micros(4));
+        summary.push("bubble", Duration::from_micros(16));
+        assert_eq!(summary.geometric_mean(), Some(Duration::from_micros(4)));
+        assert_eq!(summary.best().unwrap().0, "quick");
+        assert_eq!(summary.worst().unwrap().0, "bubble");
+        assert_eq!(
+            summary.to_string(),
+            "sorting: geometric mean 4.00µs over 3 benchmarks, best quick (1.00µs), worst bubble (16.00µs)"
+        );
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(5)
+            .begin_group("arithmetic")
+            .bench("add", || 3 + 4)
+            .bench("mul", || 3 * 4)
+            .end_group()
+            .bench("ungrouped", || 3 - 4)
+            .begin_group("arithmetic")
+            .bench("sub", || 3 - 4)
+            .finish();
+        let groups = bencher.group_summaries();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0]
+                .members
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["add", "mul", "sub"]
+        );
+    }
+
+    #[test]
+    fn it_reruns_noisy_benchmarks() {
+        let mut calls = 0;
+        let mut bencher = Bencher::new();
+        let file = File::create("test-rerun.tsv").unwrap();
+        bencher
+            .write_output_to(BufWriter::new(file))
+            .set_iterations(10)
+            .bench("noisy", || {
+                calls += 1;
+                std::thread::sleep(Duration::from_micros(if calls % 2 == 0 {
+                    2000
+                } else {
+                    10
+                }))
+            })
+            .bench("steady", || std::thread::sleep(Duration::from_millis(1)));
+        assert_eq!(bencher.noisy_benchmarks(0.5), vec!["noisy".to_string()]);
+        let mut rerun = Vec::new();
+        bencher.rerun_noisy(0.5, |bencher, name| {
+            rerun.push(name.to_string());
+            bencher.bench(name, || std::thread::sleep(Duration::from_millis(1)));
+        });
+        assert_eq!(rerun, vec!["noisy".to_string()]);
+        assert_eq!(bencher.results().len(), 2);
+        assert_eq!(bencher.results()[0].1.len(), 20);
+        bencher.flush().unwrap();
+        let contents = read_to_string("test-rerun.tsv").unwrap();
+        let noisy = contents
+            .lines()
+            .filter(|line| line.starts_with("noisy\t"))
+            .collect::<Vec<&str>>();
+        assert_eq!(noisy.len(), 2);
+        assert!(noisy[1].starts_with(&format!(
+            "noisy\t{}ns\t",
+            bencher.results()[0].1.average().as_nanos()
+        )));
+        remove_file("test-rerun.tsv").unwrap();
+        bencher.bench("noisy", || 3 * 4);
+        assert_eq!(bencher.results().len(), 3);
+    }
+
+    #[test]
+    fn it_styles_with_themes() {
+        let backend = cfg!(any(feature = "crossterm", all(feature = "termion", unix)));
+        assert_eq!(ThemeStyle::plain().escape(), "");
+        assert_eq!(
+            !Theme::monochrome().style(Style::Heading).is_empty(),
+            backend
+        );
+        assert_eq!(Theme::monochrome().get(Style::Result), ThemeStyle::plain());
+        assert_eq!(
+            Theme::light().get(Style::Regression),
+            ThemeStyle::color(Color::Red).bold()
+        );
+        assert_eq!(
+            terminal::style(Style::Warning),
+            Theme::default().style(Style::Warning)
+        );
+
+        let buffer = SharedBuffer::default();
+        let mut reporter = ConsoleReporter::with_writer(buffer.clone());
+        let theme = Theme {
+            result: ThemeStyle::color(Color::Cyan).underline(),
+            ..Theme::default()
+        };
+        reporter.set_color(ColorChoice::Always);
+        reporter.set_theme(theme);
+        reporter.on_message(Level::Result, "Result: 1.00µs");
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            output,
+            format!(
+                "{}Result: 1.00µs{}\n",
+                theme.style(Style::Result),
+                terminal::reset()
+            )
+        );
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_theme(Theme::light())
+            .set_iterations(2)
+            .bench("light", || 3 * 4);
+    }
+
+    #[test]
+    fn it_prints_compact_lines() {
+        let buffer = SharedBuffer::default();
+        let mut bencher = Bencher::new();
+        bencher
+            .set_output(buffer.clone())
+            .set_compact(true)
+            .set_iterations(10)
+            .print_settings()
+            .bench("first", || 3 * 4)
+            .bench("second", || 3 * 4)
+            .compare()
+            .finish();
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines = output
+            .lines()
+            .filter(|l| !l.starts_with("Warning: "))
+            .collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(&format!("{:<40} ", "first")));
+        assert!(lines[1].starts_with(&format!("{:<40} ", "second")));
+        assert!(lines.iter().all(|l| l.ends_with("10 iterations")));
+        assert_eq!(lines[0].find('±'), lines[1].find('±'));
+    }
+
+    #[test]
+    fn it_collects_warnings_until_the_end() {
+        let buffer = SharedBuffer::default();
+        let mut bencher = Bencher::new();
+        bencher
+            .set_output(buffer.clone())
+            .set_clock(MockClock::new())
+            .set_iterations(3)
+            .set_summary_chart(false)
+            .bench("tiny", || 3 * 4)
+            .bench("other", || 3 * 4)
+            .finish();
+        assert!(bencher
+            .warnings()
+            .iter()
+            .any(|w| w.starts_with("tiny: The iteration time")));
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let section = output.find("Warnings").unwrap();
+        assert!(!output[..section].contains("Warning: "));
+        assert!(output[section..].contains("Warning: tiny: The iteration time"));
+        assert!(output[section..].contains("Warning: other: The iteration time"));
+
+        let buffer = SharedBuffer::default();
+        Bencher::new()
+            .set_output(buffer.clone())
+            .set_clock(MockClock::new())
+            .set_defer_warnings(false)
+            .set_iterations(3)
+            .bench("tiny", || 3 * 4);
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("Warning: The iteration time"));
+    }
+
+    #[test]
+    fn it_imports_criterion_results() {
+        assert_eq!(
+            json::parse(r#"{"a": [1, -2.5e1, null], "b": "x\"é"}"#),
+            Some(json::Value::Object(vec![
+                (
+                    "a".to_string(),
+                    json::Value::Array(vec![
+                        json::Value::Number(1f64),
+                        json::Value::Number(-25f64),
+                        json::Value::Null
+                    ])
+                ),
+                ("b".to_string(), json::Value::String("x\"é".to_string()))
+            ]))
+        );
+        assert_eq!(json::parse("[1,]"), None);
+
+        let dir = std::path::Path::new("test-criterion");
+        let saved = dir.join("fib").join("20").join("base");
+        std::fs::create_dir_all(&saved).unwrap();
+        std::fs::create_dir_all(dir.join("report")).unwrap();
+        std::fs::write(
+            saved.join("sample.json"),
+            r#"{"sampling_mode":"Linear","iters":[10.0,20.0,40.0],"times":[1000.0,2400.0,4000.0]}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            saved.join("benchmark.json"),
+            r#"{"group_id":"fib","function_id":"20","value_str":null,"throughput":null,"full_id":"fib/20","directory_name":"fib/20","title":"fib/20"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            saved.join("estimates.json"),
+            r#"{"mean":{"confidence_interval":{"confidence_level":0.95,"lower_bound":99.0,"upper_bound":121.0},"point_estimate":106.7,"standard_error":1.0},"median":{"point_estimate":100.0},"std_dev":{"point_estimate":11.5}}"#,
+        )
+        .unwrap();
+
+        let estimates = criterion::read_estimates(saved.join("estimates.json")).unwrap();
+        assert_eq!(estimates.mean, Duration::from_nanos(107));
+        assert_eq!(estimates.median, Duration::from_nanos(100));
+        assert_eq!(estimates.std_dev, Duration::from_nanos(12));
+        let baseline = criterion::read_baseline(dir, "base").unwrap();
+        assert_eq!(
+            baseline["fib/20"].inner,
+            vec![
+                Duration::from_nanos(100),
+                Duration::from_nanos(120),
+                Duration::from_nanos(100)
+            ]
+        );
+        assert!(criterion::read_baseline(dir, "new").unwrap().is_empty());
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        Bencher::new()
+            .set_criterion_baseline(dir, "base")
+            .unwrap()
+            .set_reporter(RecordingReporter {
+                events: Arc::clone(&events),
+            })
+            .set_iterations(3)
+            .bench("fib/20", || 3 * 4);
+        std::fs::remove_dir_all(dir).unwrap();
+        assert!(events
+            .lock()
+            .unwrap()
+            .contains(&"compare fib/20 fib/20 (baseline)".to_string()));
+    }
+
+    #[test]
+    fn it_writes_bmf_documents() {
+        let durations = BenchVec::from_vec(
+            &(1..=20)
+                .map(Duration::from_nanos)
+                .collect::<Vec<Duration>>(),
+        );
+        let old = BenchVec::from_vec(&[Duration::from_secs(1)]);
+        assert_eq!(
+            json::bmf_document(&[("sort", &old), ("sort", &durations), ("a\"b", &old)]),
+            r#"{"sort":{"latency":{"value":10,"lower_value":1,"upper_value":19}},"a\"b":{"latency":{"value":1000000000,"lower_value":1000000000,"upper_value":1000000000}}}"#
+        );
+
+        let file = File::create("test-bmf.json").unwrap();
+        Bencher::new()
+            .write_bmf_output_to(BufWriter::new(file))
+            .set_iterations(3)
+            .bench("first", || 3 * 4)
+            .bench("second", || 3 * 4)
+            .finish();
+        let contents = read_to_string("test-bmf.json").unwrap();
+        remove_file("test-bmf.json").unwrap();
+        let document = json::parse(&contents).unwrap();
+        for name in &["first", "second"] {
+            assert!(document
+                .get(name)
+                .and_then(|b| b.get("latency"))
+                .and_then(|l| l.get("value"))
+                .and_then(json::Value::as_f64)
+                .is_some());
+        }
+    }
+
+    #[test]
+    fn it_imports_hyperfine_results() {
+        std::fs::write(
+            "test-hyperfine.json",
+            r#"{"results":[{"command":"sort -n data.txt","mean":0.0015,"stddev":0.0005,"median":0.0015,"user":0.001,"system":0.0005,"min":0.001,"max":0.002,"times":[0.001,0.002],"exit_codes":[0,0]}]}"#,
+        )
+        .unwrap();
+        let results = hyperfine::read_results("test-hyperfine.json").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "sort -n data.txt");
+        assert_eq!(
+            results[0].1.inner,
+            vec![Duration::from_millis(1), Duration::from_millis(2)]
+        );
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(3)
+            .bench("sort_in_process", || 3 * 4)
+            .import_hyperfine("test-hyperfine.json")
+            .unwrap()
+            .compare();
+        remove_file("test-hyperfine.json").unwrap();
+        let results = bencher.results();
+        assert_eq!(results[1].0, "sort -n data.txt");
+        assert_eq!(results[1].1.average(), Duration::from_micros(1500));
+
+        std::fs::write("test-hyperfine-invalid.json", r#"{"results":[{}]}"#).unwrap();
+        assert!(hyperfine::read_results("test-hyperfine-invalid.json").is_err());
+        remove_file("test-hyperfine-invalid.json").unwrap();
+    }
+
+    #[test]
+    fn it_finds_allocation_hot_spots() {
+        let profile = r#"{"dhatFileVersion":2,"mode":"rust-heap","pps":[
+            {"tb":1024,"tbk":1,"mb":1024,"mbk":1,"gb":1024,"gbk":1,"eb":0,"ebk":0,"fs":[1,2,3]},
+            {"tb":4096,"tbk":64,"mb":512,"mbk":8,"gb":512,"gbk":8,"eb":0,"ebk":0,"fs":[1,4]}
+        ],"ftbl":["[root]",
+            "0x55d1c8a3b2c1: alloc::alloc::alloc (alloc/src/alloc.rs:95:14)",
+            "0x55d1c8a3b2c2: <alloc::vec::Vec<T>>::with_capacity (alloc/src/vec/mod.rs:480:9)",
+            "0x55d1c8a3b2c3: app::load (src/load.rs:12:5)",
+            "0x55d1c8a3b2c4: app::parse (src/parse.rs:40:17)"]}"#;
+        let hot_spots = heap::parse_hot_spots(profile, 3).unwrap();
+        assert_eq!(
+            hot_spots,
+            vec![
+                HotSpot {
+                    bytes: 4096,
+                    blocks: 64,
+                    frame: "app::parse (src/parse.rs:40:17)".to_string()
+                },
+                HotSpot {
+                    bytes: 1024,
+                    blocks: 1,
+                    frame: "app::load (src/load.rs:12:5)".to_string()
+                }
+            ]
+        );
+        assert_eq!(
+            hot_spots[0].to_string(),
+            "4096 bytes in 64 allocations at app::parse (src/parse.rs:40:17)"
+        );
+        assert_eq!(heap::parse_hot_spots(profile, 1).unwrap().len(), 1);
+        assert_eq!(heap::parse_hot_spots("{}", 3), None);
+    }
+
+    #[cfg(feature = "heap-profiling")]
+    #[test]
+    fn it_writes_heap_profiles_to_the_directory() {
+        let dir = std::path::PathBuf::from("test-heap-profiles");
+        let mut bencher = Bencher::new();
+        bencher
+            .set_heap_profiles(Some(dir.clone()))
+            .profile_heap("heap profiled", || vec![0u8; 1024]);
+        let path = dir.join("dhat.heap_profiled.json");
+        assert!(read_to_string(&path)
+            .unwrap()
+            .contains("\"dhatFileVersion\""));
+        assert!(bencher
+            .annotations("heap profiled")
+            .contains(&format!("heap profile: {}", path.display())));
+        assert!(!std::path::Path::new("dhat-heap.json").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_annotates_benchmarks() {
+        let buffer = SharedBuffer::default();
+        let file = File::create("test-notes.tsv").unwrap();
+        let mut bencher = Bencher::new();
+        bencher
+            .set_output(buffer.clone())
+            .write_output_to(BufWriter::new(file))
+            .set_iterations(2)
+            .annotate("simd", "uses the SIMD path")
+            .annotate("simd", "N=2^20")
+            .set_summary_chart(false)
+            .bench("simd", || 3 * 4)
+            .bench("scalar", || 3 * 4)
+            .finish();
+        assert_eq!(
+            bencher.annotations("simd"),
+            ["uses the SIMD path", "N=2^20"]
+        );
+        assert!(bencher.annotations("scalar").is_empty());
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("Note on simd: uses the SIMD path\n"));
+        let summary = &output[output.find("Summary").unwrap()..];
+        let summary = &summary[..summary.find("Warnings").unwrap_or(summary.len())];
+        assert!(summary.contains("simd: "));
+        assert!(summary.contains(" - uses the SIMD path; N=2^20"));
+        assert!(!summary.contains("scalar"));
+        drop(bencher);
+        let contents = read_to_string("test-notes.tsv").unwrap();
+        remove_file("test-notes.tsv").unwrap();
+        assert!(contents
+            .lines()
+            .any(|l| l.starts_with("simd\t") && l.ends_with("\tuses the SIMD path; N=2^20")));
+        assert!(contents
+            .lines()
+            .any(|l| l.starts_with("scalar\t") && l.ends_with('\t')));
+        assert_eq!(
+            json::annotation_record("simd", "N=2^20"),
+            "{\"type\":\"annotation\",\"name\":\"simd\",\"text\":\"N=2^20\"}"
+        );
+    }
+
+    #[test]
+    fn it_reports_to_custom_reporters() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut bencher = Bencher::new();
+        bencher
+            .set_reporter(RecordingReporter {
+                events: Arc::clone(&events),
+            })
+            .add_reporter(ConsoleReporter::new())
+            .set_iterations(2)
+            .bench("first", || 3 * 4)
+            .bench("second", || 35 * 4)
+            .compare()
+            .finish();
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| !e.starts_with("warning"))
+                .cloned()
+                .collect::<Vec<String>>(),
+            vec![
+                "start first",
+                "sample first",
+                "sample first",
+                "complete first 2",
+                "start second",
+                "sample second",
+                "sample second",
+                "complete second 2",
+                "compare second first",
+                "finish",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_the_progress() {
+        struct ProgressReporter {
+            progress: Arc<Mutex<Vec<(usize, usize)>>>,
+        }
+
+        impl Reporter for ProgressReporter {
+            fn on_progress(&mut self, _name: &str, done: usize, total: usize, _: Duration) {
+                self.progress.lock().unwrap().push((done, total));
+            }
+        }
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let mut bencher = Bencher::new();
+        bencher
+            .set_reporter(ProgressReporter {
+                progress: Arc::clone(&progress),
+            })
+            .set_iterations(3)
+            .bench("progress", || 3 * 4)
+            .set_progress(false)
+            .bench("silent", || 3 * 4);
+        assert_eq!(*progress.lock().unwrap(), vec![(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn it_renders_a_dashboard() {
+        let mut dashboard = DashboardReporter::new();
+        dashboard.on_bench_start("first");
+        dashboard.on_bench_complete("first", &BenchVec::from_vec(&[Duration::from_millis(2)]));
+        dashboard.on_bench_start("second");
+        for micros in 1..=20 {
+            dashboard.on_sample("second", Duration::from_micros(micros));
+        }
+        dashboard.on_progress("second", 20, 50, Duration::from_secs(1));
+        dashboard.on_message(Level::Warning, "noisy");
+        let frame = dashboard.render();
+        assert!(frame.contains("second"));
+        assert!(frame.contains("(20/50 iterations)"));
+        assert!(frame.contains("over the last 20 samples"));
+        assert_eq!(frame.matches(" | ").count(), 10);
+        assert!(frame.contains("Warning: noisy"));
+        let first = BenchVec::from_vec(&[Duration::from_millis(2)]);
+        dashboard.on_message(Level::Info, "box plot");
+        dashboard.on_compare("second", "first", &first.compare(first.clone()));
+        let summary = dashboard.summary();
+        assert!(summary.starts_with("first"));
+        assert!(summary.contains("box plot\nDifference of second to first: "));
+        assert!(!dashboard.render().contains("box plot"));
+        dashboard.on_finish();
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_reporter(DashboardReporter::new())
+            .set_iterations(5)
+            .bench("dashboard", || 3 * 4)
+            .finish();
+    }
+
+    #[test]
+    fn it_charts_the_distribution() {
+        let durations = (0..100u64)
+            .map(|i| Duration::from_micros(10 + i % 10))
+            .chain(std::iter::once(Duration::from_micros(30)))
+            .collect::<Vec<Duration>>();
+        let (min, width, counts) = chart::buckets(&durations, 10);
+        assert_eq!(min, Duration::from_micros(10));
+        assert_eq!(width, Duration::from_micros(2));
+        assert_eq!(counts, vec![20, 20, 20, 20, 20, 0, 0, 0, 0, 1]);
+        assert_eq!(
+            chart::sparkline(&durations, 10, &Format::default()),
+            "10.00µs █████    ▁ 30.00µs"
+        );
+        let histogram = chart::histogram(&durations, 10, 20, &Format::default());
+        assert_eq!(histogram.lines().count(), 10);
+        assert!(histogram
+            .lines()
+            .next()
+            .unwrap()
+            .ends_with(&format!("{} 20", "#".repeat(20))));
+
+        let fast = BenchVec::from_vec(
+            &(10..=20)
+                .map(Duration::from_nanos)
+                .collect::<Vec<Duration>>(),
+        );
+        let slow = BenchVec::from_vec(
+            &(30..=50)
+                .map(Duration::from_nanos)
+                .collect::<Vec<Duration>>(),
+        );
+        let plots = chart::box_plots(&[("fast", &fast), ("slow", &slow)], 41, &Format::default());
+        let lines = plots.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[0], "fast |-[==|==]-|");
+        assert_eq!(
+            lines[1],
+            format!("slow {}|----[====|====]----|", " ".repeat(20))
+        );
+        assert_eq!(lines[2], format!("     10.00ns{}50.00ns", " ".repeat(27)));
+
+        let bars = chart::bar_chart(
+            &[
+                ("fast", Duration::from_nanos(100)),
+                ("medium", Duration::from_nanos(275)),
+                ("slow", Duration::from_nanos(400)),
+            ],
+            4,
+            &Format::default(),
+        );
+        assert_eq!(
+            bars,
+            "fast   █    100.00ns\nmedium ██▊  275.00ns\nslow   ████ 400.00ns"
+        );
+
+        let buffer = SharedBuffer::default();
+        let mut bencher = Bencher::new();
+        bencher
+            .set_output(buffer.clone())
+            .set_iterations(2)
+            .bench("first", || 3 * 4)
+            .bench("second", || 3 * 4)
+            .finish();
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let summary = &output[output.find("Summary").unwrap()..];
+        assert!(summary.lines().any(|l| l.starts_with("first  ")));
+        assert!(summary.lines().any(|l| l.starts_with("second ")));
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_distribution_chart(Some(DistributionChart::Histogram))
+            .set_iterations(10)
+            .bench("charted", || 3 * 4)
+            .set_distribution_chart(Some(DistributionChart::Sparkline))
+            .bench("sparkline", || 3 * 4);
+    }
+
+    #[test]
+    fn it_scales_time_units() {
+        assert_eq!(TimeUnit::for_nanos(999f64), TimeUnit::Nanoseconds);
+        assert_eq!(TimeUnit::for_nanos(1500f64), TimeUnit::Microseconds);
+        assert_eq!(units::format_nanos_in(1500f64, TimeUnit::Auto), "1.50µs");
+        assert_eq!(units::format_nanos_in(2e9, TimeUnit::Auto), "2.00s");
+        assert_eq!(
+            units::format_nanos_in(1500f64, TimeUnit::Nanoseconds),
+            "1500.00ns"
+        );
+        assert_eq!(
+            units::format_nanos_in(2e9, TimeUnit::Milliseconds),
+            "2000.00ms"
+        );
+
+        let mut bencher = Bencher::new();
+        bencher.set_time_unit(TimeUnit::Milliseconds);
+        assert_eq!(bencher.format().time_unit, TimeUnit::Milliseconds);
+        assert_eq!(
+            bencher.format().duration(Duration::from_micros(1500)),
+            "1.50ms"
+        );
+        assert_eq!(Bencher::new().format().time_unit, TimeUnit::Auto);
+    }
+
+    #[test]
+    fn it_formats_with_the_precision() {
+        assert_eq!(Precision::Decimals(4).format(1.23456), "1.2346");
+        assert_eq!(Precision::Decimals(0).format(1.5e3), "1500");
+        assert_eq!(Precision::Significant(3).format(0.012345), "0.0123");
+        assert_eq!(Precision::Significant(3).format(12.345), "12.3");
+        assert_eq!(Precision::Significant(3).format(12345.6), "12346");
+        assert_eq!(Precision::Significant(2).format(0f64), "0.0");
+
+        let mut bencher = Bencher::new();
+        bencher.set_precision(Precision::Significant(4));
+        assert_eq!(bencher.format().precision, Precision::Significant(4));
+        assert_eq!(bencher.format().nanos(1234.5), "1.234µs");
+        assert_eq!(units::format_nanos_in(1234.5, TimeUnit::Auto), "1.23µs");
+    }
+
+    #[test]
+    fn it_assesses_differences() {
+        let samples = |base: u64| {
+            BenchVec::from_vec(
+                &(0..50u64)
+                    .map(|i| Duration::from_nanos(base + i % 5))
+                    .collect::<Vec<Duration>>(),
+            )
+        };
+        let regression = DurationDifference::new(&samples(1200), &samples(1000));
+        assert_eq!(regression.verdict, Verdict::Regression);
+        assert!(regression.positive);
+        assert!((regression.relative - 0.2).abs() < 0.01);
+        assert_eq!(
+            DurationDifference::new(&samples(1000), &samples(1200)).verdict,
+            Verdict::Improvement
+        );
+        assert_eq!(
+            DurationDifference::new(&samples(1020), &samples(1000)).verdict,
+            Verdict::Minor
+        );
+        let thresholds = CompareThresholds {
+            improvement: 0.5,
+            regression: 0.01,
+        };
+        assert_eq!(
+            DurationDifference::with_thresholds(&samples(1020), &samples(1000), thresholds).verdict,
+            Verdict::Regression
+        );
+        let insignificant = DurationDifference::new(&samples(1000), &samples(1000));
+        assert_eq!(insignificant.verdict, Verdict::Insignificant);
+        assert!(insignificant.to_string().ends_with("not significant)"));
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(10)
+            .set_compare_thresholds(0.1, 0.1)
+            .bench("first", || 3 * 4)
+            .bench("second", || 3 * 4)
+            .compare();
+    }
+
+    #[test]
+    fn it_formats_json_records() {
+        assert_eq!(json::string("a \"b\"\n\\"), "\"a \\\"b\\\"\\n\\\\\"");
+        assert_eq!(json::string("\u{1}"), "\"\\u0001\"");
+        assert_eq!(json::number(f64::NAN), "null");
+        let durations = BenchVec::from_vec(&[
+            Duration::from_nanos(100),
+            Duration::from_nanos(200),
+            Duration::from_nanos(300),
+        ]);
+        let record = json::result_record("sort", &durations);
+        assert!(record.starts_with(
+            "{\"type\":\"result\",\"name\":\"sort\",\"iterations\":3,\"mean_ns\":200,\"stddev_ns\":"
+        ));
+        assert!(record.ends_with("\"max_ns\":300}"));
+        let difference = DurationDifference::new(&durations, &durations);
+        assert_eq!(
+            json::comparison_record("b", "a", &difference),
+            "{\"type\":\"comparison\",\"name\":\"b\",\"previous\":\"a\",\"difference_ns\":0,\"relative\":0,\"significant\":false,\"verdict\":\"insignificant\"}"
+        );
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_machine_readable(true)
+            .set_iterations(10)
+            .bench("json", || 3 * 4)
+            .bench("json again", || 3 * 4)
+            .compare()
+            .set_machine_readable(false);
+    }
+
+    #[test]
+    fn it_formats_numbers_for_locales() {
+        let english = NumberFormat::new(',', '.');
+        assert_eq!(english.apply("123456789"), "123,456,789");
+        assert_eq!(english.apply("-1234.50"), "-1,234.50");
+        assert_eq!(english.apply("999.99"), "999.99");
+        assert_eq!(NumberFormat::plain().apply("1234567.5"), "1234567.5");
+        assert_eq!(NumberFormat::for_locale("en_US.UTF-8"), english);
+        assert_eq!(
+            NumberFormat::for_locale("de_DE.UTF-8").apply("1234567.89"),
+            "1.234.567,89"
+        );
+        assert_eq!(
+            NumberFormat::for_locale("fr_FR").apply("1234567.89"),
+            "1 234 567,89"
+        );
+        assert_eq!(
+            NumberFormat::for_locale("de_CH").apply("1234567.89"),
+            "1'234'567.89"
+        );
+        assert_eq!(NumberFormat::for_locale("C"), NumberFormat::plain());
+        assert_eq!(units::format_exported(1234.5), "1234.50");
+
+        let mut bencher = Bencher::new();
+        bencher.set_number_format(NumberFormat::new('.', ','));
+        assert_eq!(bencher.format().number_format, NumberFormat::new('.', ','));
+        assert_eq!(bencher.format().count(123456), "123.456");
+        assert_eq!(bencher.format().exported(1234.5), "1234.50");
+        assert_eq!(units::format_count(123456), "123456");
+    }
+
+    #[test]
+    fn it_keeps_the_format_per_bencher() {
+        let seconds = SharedBuffer::default();
+        let nanos = SharedBuffer::default();
+        let mut first = Bencher::new();
+        first
+            .set_time_unit(TimeUnit::Seconds)
+            .set_output(seconds.clone())
+            .set_iterations(10);
+        let mut second = Bencher::new();
+        second
+            .set_output(nanos.clone())
+            .set_time_unit(TimeUnit::Nanoseconds)
+            .set_iterations(10);
+        first.bench("first", || 3 * 4);
+        second.bench("second", || 3 * 4);
+        first.bench("third", || 3 * 4);
+        let seconds = String::from_utf8(seconds.0.lock().unwrap().clone()).unwrap();
+        let nanos = String::from_utf8(nanos.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(seconds.matches("Result: ").count(), 2);
+        for line in seconds.lines().filter(|l| l.starts_with("Result: ")) {
+            assert!(line.contains("s (±") && !line.contains("ns (±"));
+        }
+        let result = nanos.lines().find(|l| l.starts_with("Result: ")).unwrap();
+        assert!(result.contains("ns (±"));
+    }
+
+    #[test]
+    fn it_renders_result_templates() {
+        let durations = BenchVec::from_vec(&[
+            Duration::from_micros(1),
+            Duration::from_micros(2),
+            Duration::from_micros(3),
+        ]);
+        let template =
+            ResultTemplate::parse("{name:<8}|{mean:>6}|{iters:^5}| {{max {max}}}").unwrap();
+        assert_eq!(
+            template.render("sort", &durations, &Format::default()),
+            "sort    |2.00µs|  3  | {max 3.00µs}"
+        );
+        let format = Format {
+            time_unit: TimeUnit::Nanoseconds,
+            precision: Precision::Decimals(0),
+            number_format: NumberFormat::new(',', '.'),
+        };
+        assert_eq!(
+            template.render("sort", &durations, &format),
+            "sort    |2,000ns|  3  | {max 3,000ns}"
+        );
+        assert_eq!(
+            ResultTemplate::parse("{mean} {average}"),
+            Err(TemplateError::UnknownField("average".to_string()))
+        );
+        assert_eq!(
+            ResultTemplate::parse("{mean:>x}"),
+            Err(TemplateError::InvalidFormat(">x".to_string()))
+        );
+        assert_eq!(
+            ResultTemplate::parse("{mean"),
+            Err(TemplateError::UnmatchedBrace)
+        );
+        assert_eq!(
+            ResultTemplate::parse("mean}"),
+            Err(TemplateError::UnmatchedBrace)
+        );
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        struct LineReporter {
+            lines: Arc<Mutex<Vec<String>>>,
+        }
+        impl Reporter for LineReporter {
+            fn on_message(&mut self, level: Level, message: &str) {
+                if level == Level::Result {
+                    self.lines.lock().unwrap().push(message.to_string());
+                }
+            }
+        }
+        let mut bencher = Bencher::new();
+        bencher
+            .set_reporter(LineReporter {
+                lines: Arc::clone(&lines),
+            })
+            .set_result_template(Some(ResultTemplate::parse("{name} took {mean}").unwrap()))
+            .set_iterations(2)
+            .bench("templated", || 3 * 4);
+        assert!(lines.lock().unwrap()[0].starts_with("templated took "));
+    }
+
+    #[test]
+    fn it_chooses_colors() {
+        assert!(ColorChoice::Always.use_color());
+        assert!(!ColorChoice::Never.use_color());
+        let backend = cfg!(any(feature = "crossterm", all(feature = "termion", unix)));
+        assert_eq!(!terminal::style(Style::Heading).is_empty(), backend);
+        assert_eq!(!terminal::reset().is_empty(), backend);
+        let mut bencher = Bencher::new();
+        bencher
+            .set_color(ColorChoice::Never)
+            .set_iterations(2)
+            .bench("colorless", || 3 * 4)
+            .print_settings();
+    }
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn it_logs_the_output() {
+        struct TestLogger {
+            records: Mutex<Vec<(log::Level, String)>>,
+        }
+
+        impl log::Log for TestLogger {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata.target() == "benchlib"
+            }
+
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    self.records
+                        .lock()
+                        .unwrap()
+                        .push((record.level(), record.args().to_string()));
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: TestLogger = TestLogger {
+            records: Mutex::new(Vec::new()),
+        };
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        let mut bencher = Bencher::new();
+        bencher.set_iterations(3).bench("logged", || 3 * 4);
+        let records = LOGGER.records.lock().unwrap();
+        assert!(records.contains(&(log::Level::Info, "Benchmarking logged".to_string())));
+        assert_eq!(
+            records
+                .iter()
+                .filter(|(level, message)| *level == log::Level::Trace
+                    && message.starts_with("Sample of logged"))
+                .count(),
+            3
+        );
+        assert!(records
+            .iter()
+            .any(|(level, message)| *level == log::Level::Info && message.starts_with("Result: ")));
+    }
+
     #[test]
     fn it_prints_settings() {
         let mut bencher = Bencher::new();
@@ -63,4 +1265,1522 @@ mod tests {
         assert!(contents.len() > BENCH_FILE_HEAD.len());
         remove_file("test.tsv").unwrap();
     }
+
+    #[test]
+    fn it_benches_with_fixtures() {
+        let mut bencher = Bencher::new();
+        let mut torn_down = false;
+        bencher.set_iterations(10).bench_with_fixture(
+            "fixture",
+            || vec![0u32; 16],
+            |data| data[0] += 1,
+            |data| torn_down = data[0] == 10,
+        );
+        assert!(torn_down);
+    }
+
+    #[test]
+    fn it_uses_custom_clocks() {
+        let mut bencher = Bencher::new();
+        let mut count = 0;
+        bencher
+            .set_clock(InstantClock::new())
+            .set_iterations(5)
+            .bench("clock", || count += 1);
+        assert_eq!(count, 5);
+    }
+
+    #[cfg(feature = "tsc")]
+    #[test]
+    fn it_counts_cycles() {
+        use crate::clock::CycleClock;
+        let clock = CycleClock::new();
+        let start = clock.now();
+        assert!(start < std::time::Duration::from_secs(1));
+        assert!(clock.elapsed(start) < std::time::Duration::from_secs(1));
+        let mut bencher = Bencher::new();
+        bencher.set_clock(clock).bench("cycles", || 3 * 4);
+    }
+
+    #[test]
+    fn it_measures_process_cpu_time() {
+        let mut bencher = Bencher::new();
+        let mut count = 0;
+        bencher
+            .set_clock(ProcessCpuClock::new())
+            .set_iterations(5)
+            .bench("cpu time", || count += 1);
+        assert_eq!(count, 5);
+        assert_eq!(
+            ProcessCpuClock::new().falls_back_to_wall_time(),
+            cfg!(not(any(unix, windows)))
+        );
+        assert!(!InstantClock::new().falls_back_to_wall_time());
+        assert_eq!(
+            bencher.warnings().iter().any(|w| w.contains("wall time")),
+            ProcessCpuClock::new().falls_back_to_wall_time()
+        );
+    }
+
+    #[test]
+    fn it_measures_thread_cpu_time() {
+        let mut bencher = Bencher::new();
+        let mut count = 0;
+        bencher
+            .set_clock(ThreadCpuClock::new())
+            .set_iterations(5)
+            .bench("thread cpu time", || count += 1);
+        assert_eq!(count, 5);
+    }
+
+    #[test]
+    fn it_recalibrates() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_calibration_probes(10)
+            .set_recalibration_interval(Some(Duration::from_secs(0)))
+            .recalibrate()
+            .bench("recalibrated", || 3 * 4)
+            .print_settings();
+    }
+
+    #[test]
+    fn it_fits_the_overhead_model() {
+        let samples = (1..=8)
+            .map(|calls| (calls, Duration::from_nanos(20 + 5 * calls as u64)))
+            .collect::<Vec<(usize, Duration)>>();
+        let model = OverheadModel::fit(&samples);
+        assert_eq!(model.intercept, Duration::from_nanos(20));
+        assert_eq!(model.per_call, Duration::from_nanos(5));
+
+        let mut durations = BenchVec::from_vec(&[Duration::from_nanos(30); 4]);
+        durations.set_overhead(model.per_sample());
+        assert_eq!(durations.average(), Duration::from_nanos(5));
+        assert_eq!(durations.raw_average(), Duration::from_nanos(30));
+    }
+
+    #[test]
+    fn it_detects_the_clock_resolution() {
+        let resolution = InstantClock::new().resolution();
+        assert!(resolution > Duration::from_secs(0));
+        assert!(resolution < Duration::from_secs(1));
+
+        struct StoppedClock;
+
+        impl Clock for StoppedClock {
+            fn now(&self) -> Duration {
+                Duration::from_secs(1)
+            }
+        }
+
+        assert_eq!(StoppedClock.resolution(), RESOLUTION_TIMEOUT);
+    }
+
+    #[test]
+    fn it_reports_cpu_time() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_cpu_time_reporting(true)
+            .set_iterations(10)
+            .bench("cpu and wall time", || (0..1000).sum::<u64>());
+    }
+
+    #[cfg(feature = "alloc-counter")]
+    #[global_allocator]
+    static ALLOCATOR: crate::alloc::CountingAllocator<std::alloc::System> =
+        crate::alloc::CountingAllocator::new(std::alloc::System);
+
+    #[cfg(feature = "alloc-counter")]
+    #[test]
+    fn it_counts_allocations() {
+        use crate::alloc::AllocationStats;
+        let start = AllocationStats::now();
+        let data = vec![0u8; 1024];
+        let allocations = AllocationStats::now().since(&start);
+        drop(data);
+        assert!(allocations.allocations >= 1);
+        assert!(allocations.allocated_bytes >= 1024);
+
+        let mut bencher = Bencher::new();
+        bencher.bench("allocating", || vec![1u64; 64]);
+    }
+
+    #[cfg(feature = "rayon-stats")]
+    #[test]
+    fn it_counts_scheduler_events() {
+        use crate::scheduler::{self, SchedulerStats};
+        fn sum(values: &[u64]) -> u64 {
+            if values.len() < 16 {
+                return values.iter().sum();
+            }
+            let (left, right) = values.split_at(values.len() / 2);
+            let (a, b) = scheduler::join(|| sum(left), || sum(right));
+            a + b
+        }
+        let values = (0..1024).collect::<Vec<u64>>();
+        let start = SchedulerStats::now();
+        assert_eq!(sum(&values), 1023 * 512);
+        let stats = SchedulerStats::now().since(&start);
+        assert!(stats.jobs >= 63);
+        assert!(stats.steals <= stats.jobs);
+
+        let mut bencher = Bencher::new();
+        bencher.bench("scheduled", || sum(&values));
+    }
+
+    #[test]
+    fn it_measures_memory_bandwidth() {
+        let mut bencher = Bencher::new();
+        bencher
+            .measure_memory_bandwidth(1 << 16)
+            .set_iterations(5)
+            .set_throughput(Some(Throughput::Bytes(1 << 16)))
+            .bench("copy", || vec![1u8; 1 << 16])
+            .print_settings();
+        let bandwidth = bencher.memory_bandwidth().unwrap();
+        assert!(bandwidth.triad > 0f64);
+        assert!(bandwidth.best() >= bandwidth.copy);
+        assert!((bandwidth.fraction(bandwidth.best()) - 1f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_benches_under_background_load() {
+        let mut bencher = Bencher::new();
+        let mut count = 0;
+        bencher
+            .set_iterations(5)
+            .set_background_load(Some(BackgroundLoad::Busy { threads: 1 }))
+            .bench("busy", || count += 1)
+            .set_background_load(Some(BackgroundLoad::MemoryThrash {
+                threads: 1,
+                bytes: 1 << 16,
+            }))
+            .bench("thrashed", || count += 1);
+        assert_eq!(count, 10);
+    }
+
+    #[test]
+    fn it_coordinates_processes() {
+        let dir = std::env::temp_dir().join(format!("benchlib-barrier-{}", std::process::id()));
+        let mut barrier = ProcessBarrier::new(&dir, 2);
+        barrier.set_timeout(Duration::from_millis(20));
+        assert!(barrier.wait("alone").is_err());
+
+        // another process that already arrived at the first wait
+        let section = barrier.section_dir("together").join("0");
+        std::fs::create_dir_all(&section).unwrap();
+        File::create(section.join("0")).unwrap();
+        let mut bencher = Bencher::new();
+        let mut count = 0;
+        bencher
+            .set_iterations(3)
+            .set_process_barrier(Some(barrier))
+            .bench("together", || count += 1);
+        assert_eq!(count, 3);
+        assert_eq!(std::fs::read_dir(&section).unwrap().count(), 2);
+        let waits_failed = |bencher: &Bencher| {
+            bencher
+                .warnings()
+                .iter()
+                .any(|w| w.contains("Failed to wait for the other processes"))
+        };
+        assert!(!waits_failed(&bencher));
+
+        // the files of the first wait don't release the second one
+        bencher.bench("together", || count += 1);
+        assert_eq!(count, 6);
+        assert!(waits_failed(&bencher));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_tracks_peak_rss() {
+        let sampler = RssSampler::start(Duration::from_millis(1));
+        let data = vec![1u8; 1 << 20];
+        let peak = sampler.stop();
+        drop(data);
+        if cfg!(target_os = "linux") {
+            assert!(peak.unwrap() >= 1 << 20);
+        }
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_rss_tracking(true)
+            .bench("rss", || vec![1u8; 1024]);
+    }
+
+    #[test]
+    fn it_reports_resource_usage() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_rusage_reporting(true)
+            .bench("rusage", || vec![1u8; 4096]);
+    }
+
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    #[test]
+    fn it_counts_hardware_events() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_perf_counters(true)
+            .set_iterations(10)
+            .bench("perf", || (0..1000).sum::<u64>());
+    }
+
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    #[test]
+    fn it_counts_hardware_events_per_worker_thread() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(5)
+            .bench_scaling_with_perf_counters("perf sweep", &[1, 2], |_| {
+                rayon::broadcast(|_| (0..1000).sum::<u64>())
+            });
+        let result = &bencher.scaling_results()[0];
+        assert!(result.thread_counters[&2].len() <= 2);
+    }
+
+    #[test]
+    fn it_parses_cachegrind_output() {
+        let contents = "desc: I1 cache: 32768 B, 64 B, 8-way associative\n\
+            events: Ir I1mr ILmr Dr D1mr DLmr Dw D1mw DLmw\n\
+            summary: 1000 10 5 400 20 2 300 30 3\n";
+        let stats = CachegrindStats::parse(contents).unwrap();
+        assert_eq!(stats.instructions, 1000);
+        assert_eq!(stats.l1_misses, 60);
+        assert_eq!(stats.ll_misses, 10);
+        assert_eq!(stats.estimated_cycles(), 1000 + 5 * 60 + 35 * 10);
+        let baseline = CachegrindStats {
+            instructions: 400,
+            l1_misses: 10,
+            ll_misses: 10,
+        };
+        let counts = stats.since(&baseline).unwrap();
+        assert_eq!(counts.instructions, 600);
+        assert_eq!(counts.l1_misses, 50);
+        assert_eq!(counts.ll_misses, 0);
+        assert!(baseline.since(&stats).is_none());
+    }
+
+    #[test]
+    fn it_parses_massif_output() {
+        let contents = "desc: (none)\n\
+            cmd: ./bench\n\
+            time_unit: i\n\
+            #-----------\n\
+            snapshot=0\n\
+            #-----------\n\
+            time=0\n\
+            mem_heap_B=0\n\
+            mem_heap_extra_B=0\n\
+            mem_stacks_B=0\n\
+            heap_tree=empty\n\
+            #-----------\n\
+            snapshot=7\n\
+            #-----------\n\
+            time=1200\n\
+            mem_heap_B=4096\n\
+            mem_heap_extra_B=24\n\
+            mem_stacks_B=0\n\
+            heap_tree=peak\n\
+            n1: 4096 (heap allocation functions) malloc/new/new[], --alloc-fns, etc.\n";
+        assert_eq!(MassifSnapshot::parse(contents), Some((7, 4120)));
+        assert_eq!(MassifSnapshot::parse("snapshot=0\nheap_tree=empty\n"), None);
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_massif(Some(std::env::temp_dir()), &["other"])
+            .set_iterations(3)
+            .bench("first", || 3 * 4);
+        assert!(bencher.massif_snapshot("first").is_none());
+        assert!(bencher.annotations("first").is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn it_records_perf_profiles_if_available() {
+        let dir = std::env::temp_dir().join("benchlib-perf-records");
+        let mut bencher = Bencher::new();
+        bencher
+            .set_perf_record(Some(dir.clone()))
+            .set_iterations(10)
+            .bench("perf recorded", || (0..1000).sum::<u64>());
+        assert_eq!(bencher.results().len(), 1);
+        if let Some(path) = bencher.perf_record("perf recorded") {
+            assert_eq!(path, dir.join("perf.perf_recorded.data"));
+            assert!(path.exists());
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn it_measures_energy_if_available() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_energy_measurement(true)
+            .bench("energy", || (0..1000).sum::<u64>());
+    }
+
+    #[test]
+    fn it_pins_to_cores() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_pinned_core(Some(0))
+            .unwrap()
+            .bench("pinned", || 3 * 4);
+        if cfg!(target_os = "linux") {
+            let pool = crate::affinity::pinned_thread_pool(&[0]).unwrap();
+            let cores = pool.install(|| crate::affinity::current_affinity().unwrap());
+            assert_eq!(cores, vec![0]);
+
+            match bencher.set_pinned_core(Some(1 << 20)) {
+                Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput),
+                Ok(_) => panic!("a core beyond the affinity mask was accepted"),
+            }
+            let error = crate::affinity::pin_current_thread(1 << 20).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            let error = crate::affinity::pinned_thread_pool(&[]).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            let error = crate::affinity::pinned_thread_pool(&[0, 1 << 20]).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn it_elevates_the_priority_if_permitted() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_priority(Some(Priority::Nice(-5)))
+            .bench("prioritized", || 3 * 4);
+    }
+
+    #[test]
+    fn it_monitors_the_cpu_frequency() {
+        let stats = FrequencyStats {
+            min_khz: 1_800_000,
+            max_khz: 2_000_000,
+            throttle_events: 0,
+        };
+        assert!((stats.variation() - 0.1).abs() < 1e-9);
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_frequency_monitoring(Some(0.1))
+            .bench("frequency", || 3 * 4);
+    }
+
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    #[test]
+    fn it_binds_to_numa_nodes() {
+        let mut bencher = Bencher::new();
+        bencher.set_numa_node(Some(0)).bench("numa", || 3 * 4);
+        assert!(crate::numa::node_count().map(|n| n >= 1).unwrap_or(true));
+    }
+
+    #[test]
+    fn it_transfers_isolated_results() {
+        let mut durations =
+            BenchVec::from_vec(&[Duration::from_nanos(120), Duration::from_nanos(80)]);
+        durations.set_overhead(Duration::from_nanos(20));
+        let output = format!(
+            "noise\n{}more noise\n",
+            isolation::format_result("isolated", &durations)
+        );
+        let parsed = isolation::parse_result(&output).unwrap();
+        assert_eq!(parsed.inner, durations.inner);
+        assert_eq!(parsed.average(), Duration::from_nanos(80));
+        let empty = isolation::format_result("empty", &BenchVec::new());
+        assert!(isolation::parse_result(&empty).unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_benches_commands() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_command_warmup(1)
+            .set_iterations(3)
+            .bench_command("true", Command::new("true"))
+            .bench_command("missing", Command::new("benchlib-missing-command"));
+    }
+
+    #[test]
+    fn it_runs_hooks_around_iterations() {
+        let mut bencher = Bencher::new();
+        let prepared = std::cell::Cell::new(0);
+        let cleaned = std::cell::Cell::new(0);
+        bencher.set_iterations(7).bench_with_hooks(
+            "hooks",
+            || prepared.set(prepared.get() + 1),
+            || assert_eq!(prepared.get(), cleaned.get() + 1),
+            || cleaned.set(cleaned.get() + 1),
+        );
+        assert_eq!(prepared.get(), 7);
+        assert_eq!(cleaned.get(), 7);
+        #[cfg(unix)]
+        bencher.set_iterations(2).bench_with_hooks(
+            "shell hooks",
+            shell_hook("true"),
+            || 3 * 4,
+            shell_hook("true"),
+        );
+        #[cfg(unix)]
+        {
+            assert!(!bencher.warnings().iter().any(|w| w.contains("hook failed")));
+            bencher.set_iterations(1).bench_with_hooks(
+                "failing hooks",
+                shell_hook("exit 3"),
+                || 3 * 4,
+                || Err("no cleanup"),
+            );
+            assert!(bencher.warnings().iter().any(
+                |w| w.starts_with("failing hooks: The prepare hook failed: exit 3 exited with")
+            ));
+            assert!(bencher
+                .warnings()
+                .iter()
+                .any(|w| w == "failing hooks: The cleanup hook failed: no cleanup"));
+        }
+    }
+
+    #[test]
+    fn it_measures_with_cold_caches() {
+        let data = vec![1u64; 4096];
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(5)
+            .set_cold_cache(Some(1 << 20))
+            .bench("thrashed", || data.iter().sum::<u64>())
+            .set_cold_cache(None)
+            .bench_with_hooks(
+                "flushed",
+                || crate::cache::flush_region(&data),
+                || data.iter().sum::<u64>(),
+                || {},
+            );
+    }
+
+    #[test]
+    fn it_records_custom_metrics() {
+        let mut metrics = Metrics::new();
+        metrics.record("elements", 2.0).record("elements", 3.0);
+        metrics.finish_iteration();
+        metrics.record("elements", 1.0);
+        metrics.finish_iteration();
+        assert_eq!(metrics.total("elements"), Some(6.0));
+        assert_eq!(metrics.mean("elements"), Some(3.0));
+        assert_eq!(metrics.to_column(), "elements=3.00");
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(4)
+            .bench_with_metrics("metrics", |m| {
+                m.record("retries", 1.0);
+            });
+    }
+
+    #[test]
+    fn it_reports_throughput() {
+        let throughput = Throughput::Bytes(2_000_000_000);
+        assert_eq!(throughput.format_rate(Duration::from_secs(1)), "2.00 GB/s");
+        let format = Format {
+            time_unit: TimeUnit::Auto,
+            precision: Precision::Decimals(3),
+            number_format: NumberFormat::plain(),
+        };
+        assert_eq!(
+            throughput.format_rate_with(Duration::from_secs(1), &format),
+            "2.000 GB/s"
+        );
+        let throughput = Throughput::Elements(500_000);
+        assert_eq!(
+            throughput.format_rate(Duration::from_millis(500)),
+            "1.00 Melem/s"
+        );
+
+        let data = vec![1u8; 1024];
+        let mut bencher = Bencher::new();
+        bencher
+            .set_throughput(Some(Throughput::Bytes(data.len() as u64)))
+            .bench("sum", || data.iter().map(|b| *b as u64).sum::<u64>());
+    }
+
+    #[test]
+    fn it_reports_tail_latencies() {
+        let durations =
+            BenchVec::from_vec(&(1..=1000).map(Duration::from_micros).collect::<Vec<_>>());
+        assert_eq!(durations.percentile(50f64), Duration::from_micros(500));
+        assert_eq!(durations.percentile(99f64), Duration::from_micros(990));
+        assert_eq!(durations.percentile(99.9), Duration::from_micros(999));
+        assert_eq!(durations.max(), Duration::from_micros(1000));
+        assert_eq!(durations.min(), Duration::from_micros(1));
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_report_mode(ReportMode::TailLatency)
+            .bench("tail", || 3 * 4);
+    }
+
+    #[cfg(feature = "http-push")]
+    #[test]
+    fn it_pushes_results_to_a_collector() {
+        use std::io::{BufRead, BufReader, Read};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let collector = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in &["204 No Content", "500 Internal Server Error"] {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.strip_prefix("Content-Length: ") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0u8; length];
+                reader.read_exact(&mut body).unwrap();
+                write!(reader.get_mut(), "HTTP/1.1 {}\r\n\r\n", status).unwrap();
+                requests.push((head, String::from_utf8(body).unwrap()));
+            }
+            requests
+        });
+
+        let url = format!("http://127.0.0.1:{}/runs", port);
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(3)
+            .bench("first", || 3 * 4)
+            .push_results(&url)
+            .unwrap();
+        assert!(bencher.push_results(&url).is_err());
+        assert!(bencher.push_results("https://example.com").is_err());
+        let requests = collector.join().unwrap();
+        assert!(requests[0].0.starts_with("POST /runs HTTP/1.1\r\n"));
+        let document = json::parse(&requests[0].1).unwrap();
+        assert!(document
+            .get("metadata")
+            .and_then(|m| m.get("timestamp"))
+            .and_then(json::Value::as_f64)
+            .is_some());
+        let results = document
+            .get("results")
+            .and_then(json::Value::as_array)
+            .unwrap();
+        assert_eq!(
+            results[0].get("name").and_then(json::Value::as_str),
+            Some("first")
+        );
+    }
+
+    #[cfg(all(feature = "profiling", unix))]
+    #[test]
+    fn it_writes_flamegraphs() {
+        let dir = std::env::temp_dir().join("benchlib-flamegraphs");
+        std::fs::create_dir_all(&dir).unwrap();
+        Bencher::new()
+            .set_flamegraphs(Some(dir.clone()))
+            .set_iterations(200)
+            .bench("sum of squares", || {
+                (0..10_000u64).map(|i| i * i).sum::<u64>()
+            });
+        let svg = dir.join("sum_of_squares.svg");
+        assert!(svg.exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "itt")]
+    #[test]
+    fn it_runs_without_a_vtune_collector() {
+        let mut bencher = Bencher::new();
+        bencher.set_iterations(10).bench("itt", || 3 * 4);
+        assert_eq!(bencher.results()[0].1.len(), 10);
+    }
+
+    #[cfg(feature = "tracy")]
+    #[test]
+    fn it_runs_with_a_tracy_client() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(10)
+            .bench("tracy", || 3 * 4)
+            .bench_with_hooks("tracy hooks", || (), || 3 * 4, || ());
+        assert_eq!(bencher.results().len(), 2);
+    }
+
+    #[cfg(feature = "tracing-spans")]
+    #[test]
+    fn it_emits_tracing_spans() {
+        use crate::reporter::TracingReporter;
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        #[derive(Default)]
+        struct Fields(Vec<String>);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.push(format!("{}={:?}", field.name(), value));
+            }
+        }
+
+        #[derive(Clone, Default)]
+        struct RecordingSubscriber(Arc<Mutex<Vec<String>>>);
+
+        impl tracing::Subscriber for RecordingSubscriber {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut fields = Fields::default();
+                span.record(&mut fields);
+                let mut spans = self.0.lock().unwrap();
+                spans.push(format!("{} {}", span.metadata().name(), fields.0.join(" ")));
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, _: &Id, values: &Record<'_>) {
+                let mut fields = Fields::default();
+                values.record(&mut fields);
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("record {}", fields.0.join(" ")));
+            }
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, _: &Event<'_>) {}
+
+            fn enter(&self, id: &Id) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("enter {}", id.into_u64()));
+            }
+
+            fn exit(&self, id: &Id) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("exit {}", id.into_u64()));
+            }
+        }
+
+        let subscriber = RecordingSubscriber::default();
+        let events = subscriber.0.clone();
+        tracing::subscriber::with_default(subscriber.clone(), || {
+            Bencher::new()
+                .set_reporter(TracingReporter::with_samples())
+                .set_iterations(2)
+                .bench("traced", || {
+                    events.lock().unwrap().push("closure".to_string())
+                });
+        });
+        let spans = subscriber.0.lock().unwrap().clone();
+        assert_eq!(spans[0], "benchmark name=\"traced\"");
+        assert_eq!(spans[1], "enter 1");
+        let samples = spans
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.starts_with("sample name=\"traced\""))
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        assert_eq!(samples.len(), 2);
+        for i in samples {
+            // the sample span is entered while the closure runs
+            // and records the measured duration
+            let id = i + 1;
+            assert_eq!(spans[i + 1], format!("enter {}", id));
+            assert_eq!(spans[i + 2], "closure");
+            assert!(spans[i + 3].starts_with("record duration_ns="));
+            assert_eq!(spans[i + 4], format!("exit {}", id));
+        }
+        assert!(spans.contains(&"record iterations=2".to_string()));
+        assert!(spans.iter().any(|s| s.starts_with("record mean_ns=")));
+        assert_eq!(spans.last().unwrap(), "exit 1");
+
+        let subscriber = RecordingSubscriber::default();
+        tracing::subscriber::with_default(subscriber.clone(), || {
+            let mut reporter = TracingReporter::new();
+            reporter.on_bench_start("isolated");
+            reporter.on_bench_end("isolated");
+        });
+        assert_eq!(subscriber.0.lock().unwrap().last().unwrap(), "exit 1");
+    }
+
+    #[cfg(feature = "jemalloc")]
+    #[test]
+    fn it_reports_jemalloc_deltas() {
+        use crate::jemalloc::JemallocStats;
+        let start = JemallocStats {
+            allocated: 100,
+            active: 200,
+            resident: 400,
+        };
+        let end = JemallocStats {
+            allocated: 150,
+            active: 200,
+            resident: 300,
+        };
+        let delta = end.since(&start);
+        assert_eq!(delta.allocated, 50);
+        assert_eq!(delta.resident, -100);
+        assert!((delta.fragmentation - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_skips_unchanged_benchmarks() {
+        let mut bencher = Bencher::new();
+        let mut count = 0;
+        bencher
+            .set_incremental("test-cache.tsv")
+            .unwrap()
+            .set_iterations(3)
+            .bench("cached", || count += 1);
+        let mut bencher = Bencher::new();
+        bencher
+            .set_incremental("test-cache.tsv")
+            .unwrap()
+            .bench("cached", || count += 1);
+        assert_eq!(count, 3);
+        remove_file("test-cache.tsv").unwrap();
+    }
+
+    #[test]
+    fn it_estimates_required_sample_sizes() {
+        assert!((statistics::normal_quantile(0.975) - 1.959964).abs() < 1e-5);
+        assert!((statistics::normal_quantile(0.8) - 0.841621).abs() < 1e-5);
+        assert!((statistics::normal_quantile(0.01) + 2.326348).abs() < 1e-5);
+        // 2 * (1.96 + 0.84)^2 * 100^2 / 50^2 = 62.8
+        assert_eq!(
+            statistics::required_sample_size(100f64 * 100f64, 50f64, 0.05, 0.8),
+            63
+        );
+
+        let mut bencher = Bencher::new();
+        let mut count = 0;
+        bencher.set_max_iterations(500).bench_with_pilot(
+            "pilot",
+            10,
+            Duration::from_nanos(1),
+            || count += 1,
+        );
+        assert!(count > 10 && count <= 510);
+        let mut count = 0;
+        bencher.set_max_iterations(0).bench_with_pilot(
+            "limited",
+            10,
+            Duration::from_nanos(1),
+            || count += 1,
+        );
+        assert_eq!(count, 11);
+    }
+
+    #[test]
+    fn it_detects_drift() {
+        let increasing = (0..100)
+            .map(|i| Duration::from_nanos(1000 + i * 10))
+            .collect::<Vec<Duration>>();
+        let drift = statistics::detect_drift(&BenchVec::from_vec(&increasing)).unwrap();
+        assert!(drift.z > 0f64);
+        assert!(drift.relative_change > 0.5);
+
+        let alternating = (0..100)
+            .map(|i| Duration::from_nanos(1000 + (i % 2) * 10))
+            .collect::<Vec<Duration>>();
+        assert!(statistics::detect_drift(&BenchVec::from_vec(&alternating)).is_none());
+
+        let binned = statistics::bin_means(&[1f64, 3f64, 5f64, 7f64, 9f64], 2);
+        assert_eq!(binned, vec![2f64, 7f64]);
+        // the pairwise test would compare 5 * 10^11 pairs without the bins
+        let long = (0..1_000_000)
+            .map(|i| Duration::from_nanos(1000 + i / 1000))
+            .collect::<Vec<Duration>>();
+        assert!(statistics::detect_drift(&BenchVec::from_vec(&long)).is_some());
+    }
+
+    #[test]
+    fn it_measures_with_mock_clocks() {
+        let clock = MockClock::new();
+        let start = clock.now();
+        clock.advance(Duration::from_millis(3));
+        assert_eq!(clock.elapsed(start), Duration::from_millis(3));
+        let stepping = MockClock::with_step(Duration::from_micros(2));
+        let start = stepping.now();
+        assert_eq!(stepping.elapsed(start), Duration::from_micros(2));
+
+        let mut bencher = Bencher::new();
+        let handle = clock.clone();
+        let file = File::create("test-mock.tsv").unwrap();
+        bencher
+            .set_clock(clock)
+            .set_iterations(10)
+            .write_output_to(BufWriter::new(file))
+            .bench("mocked", || handle.advance(Duration::from_millis(5)))
+            .flush()
+            .unwrap();
+        let contents = read_to_string("test-mock.tsv").unwrap();
+        assert!(contents.contains("mocked\t5000000ns\t"));
+        remove_file("test-mock.tsv").unwrap();
+    }
+
+    #[test]
+    fn it_records_external_timings() {
+        let mut bencher = Bencher::new();
+        let file = File::create("test-external.tsv").unwrap();
+        let mut device_time = 0;
+        bencher
+            .set_iterations(4)
+            .write_output_to(BufWriter::new(file))
+            .record_external("gpu kernel", &[Duration::from_micros(50); 3])
+            .bench_external("gpu events", || {
+                device_time += 10;
+                Duration::from_micros(device_time)
+            })
+            .bench("cpu", || 3 * 4)
+            .compare()
+            .flush()
+            .unwrap();
+        let contents = read_to_string("test-external.tsv").unwrap();
+        assert!(contents.contains("gpu kernel\t50000ns\t"));
+        assert!(contents.contains("gpu events\t25000ns\t"));
+        remove_file("test-external.tsv").unwrap();
+    }
+
+    #[test]
+    fn it_sweeps_thread_counts() {
+        let mut bencher = Bencher::new();
+        let mut counts = Vec::new();
+        bencher
+            .set_iterations(3)
+            .bench_scaling("sweep", &[1, 2, 4], |threads| counts.push(threads));
+        assert_eq!(counts, vec![1, 1, 1, 2, 2, 2, 4, 4, 4]);
+        let result = &bencher.scaling_results()[0];
+        assert_eq!(result.name, "sweep");
+        assert_eq!(result.points.len(), 3);
+        assert_eq!(result.point(4).unwrap().durations.len(), 3);
+    }
+
+    #[test]
+    fn it_computes_speedup_and_efficiency() {
+        let mut result = ScalingResult::new("scaling");
+        result
+            .push(1, BenchVec::from_vec(&[Duration::from_millis(80)]))
+            .push(4, BenchVec::from_vec(&[Duration::from_millis(25)]));
+        assert_eq!(result.speedup(4), Some(3.2));
+        assert_eq!(result.efficiency(4), Some(0.8));
+        assert!(result.to_tsv().contains("scaling\t4\t25000000ns\t"));
+
+        let mut bencher = Bencher::new();
+        let file = File::create("test-scaling.tsv").unwrap();
+        bencher
+            .set_iterations(2)
+            .write_scaling_output_to(BufWriter::new(file))
+            .bench_scaling("sweep", &[1, 2], |_| 3 * 4)
+            .flush()
+            .unwrap();
+        let contents = read_to_string("test-scaling.tsv").unwrap();
+        assert!(contents.starts_with(SCALING_FILE_HEAD));
+        assert_eq!(contents.lines().count(), 3);
+        remove_file("test-scaling.tsv").unwrap();
+    }
+
+    #[test]
+    fn it_asserts_parallel_efficiency() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(3)
+            .bench_scaling("sleepy", &[1, 2], |threads| {
+                std::thread::sleep(Duration::from_millis(4 / threads as u64))
+            })
+            .assert_speedup_at_least("sleepy", 2, 1.2)
+            .assert_efficiency_at_least("sleepy", 2, 0.6);
+        assert_eq!(bencher.scaling_result("sleepy").unwrap().points.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "but at least 2.00 is required")]
+    fn it_fails_efficiency_assertions() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(2)
+            .bench_scaling("flat", &[1, 2], |_| {
+                std::thread::sleep(Duration::from_millis(2))
+            })
+            .assert_efficiency_at_least("flat", 2, 2f64);
+    }
+
+    #[test]
+    fn it_fits_amdahls_law() {
+        let mut result = ScalingResult::new("amdahl");
+        for threads in [1usize, 2, 4, 8] {
+            // 20% serial part of 100ms
+            let millis = 20f64 + 80f64 / threads as f64;
+            result.push(
+                threads,
+                BenchVec::from_vec(&[Duration::from_secs_f64(millis / 1000f64)]),
+            );
+        }
+        let fit = result.amdahl_fit().unwrap();
+        assert!((fit.serial_fraction - 0.2).abs() < 1e-6);
+        assert!(fit.r_squared > 0.999);
+        assert!((fit.max_speedup() - 5f64).abs() < 1e-4);
+    }
+
+    #[test]
+    fn it_reports_scaled_speedup_for_weak_scaling() {
+        let mut result =
+            ScalingResult::with_mode("gustafson", ScalingMode::Weak { base_size: 1000 });
+        result.push(1, BenchVec::from_vec(&[Duration::from_millis(100)]));
+        result.push(4, BenchVec::from_vec(&[Duration::from_millis(125)]));
+        assert_eq!(result.problem_size(4), Some(4000));
+        assert!((result.speedup(4).unwrap() - 3.2).abs() < 1e-9);
+        assert!((result.efficiency(4).unwrap() - 0.8).abs() < 1e-9);
+        assert!(result.amdahl_fit().is_none());
+
+        let mut bencher = Bencher::new();
+        bencher.set_iterations(2);
+        let mut sizes = Vec::new();
+        bencher.bench_weak_scaling("weak", &[1, 2], 10, |threads, size| {
+            sizes.push((threads, size))
+        });
+        assert_eq!(
+            bencher.scaling_results()[0].mode,
+            ScalingMode::Weak { base_size: 10 }
+        );
+        assert!(sizes.contains(&(2, 20)));
+    }
+
+    #[test]
+    fn it_runs_one_definition_in_both_scaling_modes() {
+        let mut bencher = Bencher::new();
+        bencher.set_iterations(1);
+        let mut sizes = Vec::new();
+        for mode in [ScalingMode::Strong, ScalingMode::Weak { base_size: 8 }] {
+            bencher.bench_scaling_with_mode("modes", &[1, 2], mode, |threads, size| {
+                sizes.push((threads, size.unwrap_or(8)))
+            });
+        }
+        let results = bencher.scaling_results();
+        assert_eq!(results[0].mode, ScalingMode::Strong);
+        assert!(results[0].to_tsv().contains("\tstrong\t"));
+        assert!(results[1].to_tsv().contains("\tweak\t"));
+        assert!(sizes.contains(&(2, 8)) && sizes.contains(&(2, 16)));
+    }
+
+    #[test]
+    fn it_computes_the_karp_flatt_metric() {
+        let mut result = ScalingResult::new("karp-flatt");
+        result
+            .push(1, BenchVec::from_vec(&[Duration::from_millis(100)]))
+            .push(2, BenchVec::from_vec(&[Duration::from_millis(60)]))
+            .push(4, BenchVec::from_vec(&[Duration::from_millis(40)]));
+        assert!((result.karp_flatt(2).unwrap() - 0.2).abs() < 1e-9);
+        assert!((result.karp_flatt(4).unwrap() - 0.2).abs() < 1e-9);
+        assert!(result.karp_flatt(1).is_none());
+        assert!(result.to_tsv().ends_with("\t0.20\t-\t-\t-\n"));
+    }
+
+    #[test]
+    fn it_computes_the_load_imbalance() {
+        let mut result = ScalingResult::new("imbalance");
+        result
+            .push(1, BenchVec::from_vec(&[Duration::from_millis(100)]))
+            .push_with_thread_times(
+                4,
+                BenchVec::from_vec(&[Duration::from_millis(40)]),
+                vec![Duration::from_millis(40), Duration::from_millis(20)],
+            );
+        // two of four workers stayed idle
+        assert!((result.load_imbalance(4).unwrap() - 2.6667).abs() < 1e-3);
+        assert!(result.load_imbalance(1).is_none());
+        assert!(result.to_tsv().ends_with("\t2.67\t-\t-\n"));
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(2)
+            .bench_scaling_with_thread_times("timed", &[1, 2], |_, times| {
+                (0..64usize)
+                    .into_par_iter()
+                    .map(|i| times.measure(|| i * 4))
+                    .sum::<usize>()
+            });
+        let result = &bencher.scaling_results()[0];
+        assert!(result.load_imbalance(2).unwrap() >= 1f64);
+        assert!(result.point(2).unwrap().thread_times.len() <= 2);
+    }
+
+    #[test]
+    fn it_writes_speedup_plots() {
+        let mut result = ScalingResult::new("plot me");
+        result
+            .push(1, BenchVec::from_vec(&[Duration::from_millis(80)]))
+            .push(4, BenchVec::from_vec(&[Duration::from_millis(25)]));
+        let script = result.to_gnuplot("plot.svg");
+        assert!(script.contains("set output \"plot.svg\""));
+        assert!(script.contains("1 1.00\n4 3.20\nEOD"));
+        let format = Format {
+            time_unit: TimeUnit::Auto,
+            precision: Precision::Decimals(4),
+            number_format: NumberFormat::new(',', '.'),
+        };
+        let script = result.to_gnuplot_with("plot.svg", &format);
+        assert!(script.contains("1 1.0000\n4 3.2000\nEOD"));
+        assert!(script.contains("x with lines dashtype 2 title \"ideal\""));
+        assert_eq!(result.plot_name(), "plot_me");
+
+        let dir = std::env::temp_dir().join("benchlib-plots");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(2)
+            .set_scaling_plots(Some(dir.clone()))
+            .bench_scaling("plotted", &[1, 2], |_| 3 * 4);
+        assert!(read_to_string(dir.join("plotted.gp"))
+            .unwrap()
+            .contains("plotted.svg"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_marks_oversubscribed_points() {
+        let topology = CpuTopology::from_cpus(
+            (0..4)
+                .map(|id| Cpu {
+                    id,
+                    core: id % 2,
+                    socket: 0,
+                })
+                .collect(),
+        );
+        assert_eq!(topology.physical_cores(), 2);
+        assert!(topology.has_smt());
+        assert_eq!(topology.occupancy(2), Occupancy::Physical);
+        assert_eq!(topology.occupancy(3), Occupancy::Smt);
+        assert_eq!(topology.occupancy(5), Occupancy::Oversubscribed);
+        assert_eq!(topology.oversubscribed_sweep(2), vec![1, 2, 4, 8]);
+
+        let mut bencher = Bencher::new();
+        let oversubscribed = CpuTopology::detect().logical_cpus() + 1;
+        bencher.set_iterations(1).bench_scaling_in_pools(
+            "oversubscribed",
+            &[1, oversubscribed],
+            |_| 3 * 4,
+        );
+        let result = &bencher.scaling_results()[0];
+        assert_eq!(
+            result.occupancy(oversubscribed),
+            Some(Occupancy::Oversubscribed)
+        );
+        assert!(result.to_tsv().ends_with("\toversubscribed\t-\n"));
+    }
+
+    #[test]
+    fn it_places_threads_on_sockets() {
+        let topology = CpuTopology::from_cpus(
+            (0..8)
+                .map(|id| Cpu {
+                    id,
+                    core: id % 4 / 2,
+                    socket: id / 4,
+                })
+                .collect(),
+        );
+        assert_eq!(topology.sockets(), 2);
+        assert_eq!(topology.physical_cores(), 4);
+        assert_eq!(topology.placement(Placement::Compact, 4), vec![0, 2, 4, 6]);
+        assert_eq!(topology.placement(Placement::Spread, 3), vec![0, 4, 2]);
+        assert_eq!(topology.placement(Placement::Compact, 2), vec![0, 2]);
+        assert_eq!(topology.placement(Placement::Spread, 10).len(), 10);
+        let restricted = topology.restricted_to(&[4, 5, 6, 7]);
+        assert_eq!(restricted.placement(Placement::Spread, 2), vec![4, 6]);
+
+        let mut bencher = Bencher::new();
+        bencher.set_iterations(1).bench_scaling_with_placement(
+            "spread",
+            &[1, 2],
+            Placement::Spread,
+            |_| 3 * 4,
+        );
+        let result = &bencher.scaling_results()[0];
+        assert_eq!(result.placement, Some(Placement::Spread));
+        assert!(result.to_tsv().ends_with("\tspread\n"));
+
+        let mut used = Vec::new();
+        bencher.bench_scaling_placements("placements", &[2], &Placement::ALL, |_, placement| {
+            used.push(placement)
+        });
+        assert!(used.contains(&Placement::Compact) && used.contains(&Placement::Spread));
+        let results = bencher.scaling_results();
+        assert_eq!(results[1].placement, Some(Placement::Compact));
+        assert_eq!(results[2].placement, Some(Placement::Spread));
+
+        bencher.bench_scaling_with_placement("zero", &[0, 1], Placement::Compact, |_| 3 * 4);
+        let result = bencher.scaling_result("zero").unwrap();
+        assert_eq!(result.points.len(), 1);
+        assert!(bencher
+            .warnings()
+            .iter()
+            .any(|w| w.contains("Skipped 0 threads in the sweep of zero")));
+    }
+
+    #[test]
+    fn it_aggregates_results_of_ranks() {
+        let clock = MockClock::new();
+        let handle = clock.clone();
+        let mut bencher = Bencher::new();
+        let file = File::create("test-rank-1.tsv").unwrap();
+        bencher
+            .set_clock(clock)
+            .set_iterations(3)
+            .write_raw_output_to(BufWriter::new(file))
+            .bench("ranked", || handle.advance(Duration::from_millis(4)))
+            .flush()
+            .unwrap();
+
+        let mut results = RankResults::new();
+        results
+            .add(
+                0,
+                "ranked",
+                &BenchVec::from_vec(&[Duration::from_millis(2); 3]),
+            )
+            .load_file(1, "test-rank-1.tsv")
+            .unwrap();
+        remove_file("test-rank-1.tsv").unwrap();
+        assert_eq!(results.names(), vec!["ranked"]);
+        assert_eq!(results.ranks("ranked").unwrap().len(), 2);
+        assert_eq!(results.combined("ranked").unwrap().len(), 6);
+        assert_eq!(results.slowest_rank("ranked"), Some(1));
+        assert!(results.to_string().contains("all ranks"));
+
+        let with_overhead = |nanos: u64, overhead: u64| {
+            let mut durations = BenchVec::from_vec(&[Duration::from_nanos(nanos); 2]);
+            durations.set_overhead(Duration::from_nanos(overhead));
+            durations
+        };
+        let mut overheads = RankResults::new();
+        overheads
+            .add(0, "overheads", &with_overhead(150, 50))
+            .add(1, "overheads", &with_overhead(120, 20))
+            .add(1, "overheads", &with_overhead(130, 30));
+        let rank = &overheads.ranks("overheads").unwrap()[&1];
+        assert_eq!(rank.average(), Duration::from_nanos(100));
+        let combined = overheads.combined("overheads").unwrap();
+        assert_eq!(combined.len(), 6);
+        assert_eq!(combined.min(), Duration::from_nanos(100));
+        assert_eq!(combined.max(), Duration::from_nanos(100));
+
+        let file = File::create("test-ranks.tsv").unwrap();
+        let mut bencher = Bencher::new();
+        bencher
+            .write_output_to(BufWriter::new(file))
+            .report_ranks(&results)
+            .flush()
+            .unwrap();
+        let contents = read_to_string("test-ranks.tsv").unwrap();
+        assert!(contents.contains("\nranked\t"));
+        remove_file("test-ranks.tsv").unwrap();
+    }
+
+    #[test]
+    fn it_fits_complexity_models() {
+        let mut result = SizeSweepResult::new("quadratic");
+        for size in [100usize, 200, 400, 800] {
+            let nanos = (size * size) as u64 * 3;
+            result.push(size, BenchVec::from_vec(&[Duration::from_nanos(nanos)]));
+        }
+        let best = result.best_fit().unwrap();
+        assert_eq!(best.model, Complexity::Quadratic);
+        assert!((best.coefficient - 3f64).abs() < 1e-6);
+        assert!(best.residual < 1e-9);
+        assert_eq!(result.fits().len(), 3);
+        assert!(result.fit(Complexity::Linear).unwrap().residual > 0.1);
+
+        let mut bencher = Bencher::new();
+        let mut sizes = Vec::new();
+        bencher
+            .set_iterations(1)
+            .bench_sizes("sizes", &[10, 20], |size| sizes.push(size));
+        assert_eq!(sizes, vec![10, 20]);
+        assert_eq!(bencher.size_results()[0].points.len(), 2);
+    }
+
+    #[test]
+    fn it_computes_iso_efficiency_curves() {
+        let mut result = IsoEfficiencyResult::new("iso", &[4, 1, 2], &[8000, 1000, 2000, 4000]);
+        for &threads in &[1usize, 2, 4] {
+            for &size in &[1000usize, 2000, 4000, 8000] {
+                let nanos = (size / threads + 100 * threads * threads) as u64;
+                result.push(
+                    threads,
+                    size,
+                    BenchVec::from_vec(&[Duration::from_nanos(nanos)]),
+                );
+            }
+        }
+        assert_eq!(result.threads, vec![1, 2, 4]);
+        assert!((result.efficiency(2, 1000).unwrap() - 1100f64 / 1800f64).abs() < 1e-9);
+        let curve = result.iso_efficiency(0.9);
+        assert_eq!(curve[0], (1, Some(1000f64)));
+        let size = curve[1].1.unwrap();
+        assert!(size > 4000f64 && size < 8000f64);
+        assert_eq!(curve[2], (4, None));
+        let matrix = result.to_matrix();
+        assert!(matrix.starts_with("size\t1\t2\t4\n1000\t1.00\t0.61\t"));
+        let format = Format {
+            time_unit: TimeUnit::Auto,
+            precision: Precision::Decimals(4),
+            number_format: NumberFormat::new(',', '.'),
+        };
+        assert!(result
+            .to_matrix_with(&format)
+            .starts_with("size\t1\t2\t4\n1000\t1.0000\t0.6111\t"));
+        assert_eq!(matrix.lines().count(), 5);
+
+        let dir = std::env::temp_dir().join(format!("iso-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut bencher = Bencher::new();
+        let mut runs = Vec::new();
+        bencher
+            .set_iterations(1)
+            .set_scaling_plots(Some(dir.clone()))
+            .bench_iso_efficiency("iso sweep", &[2, 1, 2], &[10, 20], |threads, size| {
+                runs.push((threads, size))
+            });
+        assert_eq!(runs, vec![(1, 10), (2, 10), (1, 20), (2, 20)]);
+        assert_eq!(bencher.iso_efficiency_results()[0].durations.len(), 4);
+        assert!(dir.join("iso_sweep.tsv").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn it_detects_diminishing_returns_of_throughput() {
+        let mut result = ScalingResult::new("knee");
+        result.set_throughput(Throughput::Bytes(1_000_000));
+        for (threads, millis) in [(1, 100), (2, 50), (4, 25), (8, 22), (16, 21)] {
+            result.push(
+                threads,
+                BenchVec::from_vec(&[Duration::from_millis(millis)]),
+            );
+        }
+        assert!((result.rate(4).unwrap() - 40e6).abs() < 1f64);
+        assert_eq!(result.knee(), Some(4));
+        let report = result.throughput_report().unwrap();
+        assert!(report.contains("2 threads 0.02 GB/s"));
+        assert!(report.contains("beyond 4 threads"));
+        assert!(ScalingResult::new("none").throughput_report().is_none());
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(1)
+            .set_throughput(Some(Throughput::Elements(64)))
+            .bench_scaling("rates", &[1, 2], |_| 3 * 4);
+        assert!(bencher.scaling_results()[0].rate(2).is_some());
+    }
+
+    #[test]
+    fn it_runs_benchmarks_in_dedicated_thread_pools() {
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(2)
+            .bench_with_threads("pool", 3, || assert_eq!(rayon::current_num_threads(), 3));
+        let mut sizes = Vec::new();
+        bencher.bench_scaling_in_pools("pools", &[1, 2], |threads| {
+            sizes.push((threads, rayon::current_num_threads()))
+        });
+        assert!(sizes.iter().all(|(threads, size)| threads == size));
+        assert_eq!(bencher.scaling_results()[0].points.len(), 2);
+    }
+
+    #[test]
+    fn it_benches_under_contention() {
+        let counter = AtomicUsize::new(0);
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(50)
+            .bench_concurrent("contended", 4, || counter.fetch_add(1, Ordering::SeqCst));
+        assert_eq!(counter.load(Ordering::SeqCst), 200);
+        let result = &bencher.concurrent_results()[0];
+        assert_eq!(result.threads, 4);
+        assert_eq!(result.operations(), 200);
+        assert!(result.throughput() > 0f64);
+        assert_eq!(result.per_thread.len(), 4);
+        assert!(result
+            .per_thread
+            .iter()
+            .all(|durations| durations.len() == 50));
+    }
+
+    #[test]
+    fn it_runs_threads_on_different_backends() {
+        struct CountingSpawner(std::sync::Arc<AtomicUsize>);
+
+        impl Spawner for CountingSpawner {
+            fn run_all(&self, tasks: Vec<Task<'_>>) {
+                self.0.fetch_add(tasks.len(), Ordering::SeqCst);
+                StdSpawner.run_all(tasks)
+            }
+        }
+
+        let counter = AtomicUsize::new(0);
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(10)
+            .set_spawner(RayonSpawner)
+            .bench_concurrent("rayon", 3, || counter.fetch_add(1, Ordering::SeqCst))
+            .bench_open_loop("rayon open loop", 10_000f64, 2, || 3 * 4);
+        assert_eq!(counter.load(Ordering::SeqCst), 30);
+        assert_eq!(bencher.open_loop_results()[0].response_times.len(), 10);
+
+        let spawned = std::sync::Arc::new(AtomicUsize::new(0));
+        bencher
+            .set_spawner(CountingSpawner(spawned.clone()))
+            .bench_concurrent("custom", 2, || 3 * 4);
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(2)
+            .set_clock(MockClock::new())
+            .bench_concurrent("mocked", 2, || 3 * 4);
+        assert!(bencher
+            .warnings()
+            .iter()
+            .any(|w| w.starts_with("mocked: The threads are measured in wall time")));
+    }
+
+    #[test]
+    fn it_runs_sync_probes() {
+        let mut bencher = Bencher::new();
+        bencher.set_iterations(10).bench_sync_probes(2);
+        assert_eq!(bencher.concurrent_results().len(), 4);
+        assert!(bencher
+            .concurrent_results()
+            .iter()
+            .all(|result| result.name.starts_with("probe: ")));
+    }
+
+    #[test]
+    fn it_benches_under_a_fixed_load() {
+        let counter = AtomicUsize::new(0);
+        let mut bencher = Bencher::new();
+        bencher
+            .set_iterations(100)
+            .bench_open_loop("open loop", 20_000f64, 2, || {
+                counter.fetch_add(1, Ordering::SeqCst)
+            });
+        assert_eq!(counter.load(Ordering::SeqCst), 100);
+        let result = &bencher.open_loop_results()[0];
+        assert_eq!(result.response_times.len(), 100);
+        assert!(result.response_times.sum() >= result.service_times.sum());
+        assert!(result.wall_time >= Duration::from_micros(99 * 50));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be a positive number of operations per second")]
+    fn it_rejects_an_invalid_rate() {
+        Bencher::new()
+            .set_iterations(10)
+            .bench_open_loop("no load", 0f64, 2, || 3 * 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "needs at least one operation")]
+    fn it_rejects_an_open_loop_without_operations() {
+        Bencher::new()
+            .set_iterations(0)
+            .set_max_iterations(0)
+            .bench_open_loop("empty", 1000f64, 2, || 3 * 4);
+    }
+
+    #[test]
+    fn it_reports_the_spread_between_threads() {
+        let result = ConcurrentResult::new(
+            "stragglers",
+            vec![
+                BenchVec::from_vec(&[Duration::from_millis(2), Duration::from_millis(4)]),
+                BenchVec::from_vec(&[Duration::from_millis(10)]),
+            ],
+            Duration::from_millis(10),
+        );
+        assert_eq!(result.threads, 2);
+        assert_eq!(result.operations(), 3);
+        assert_eq!(
+            result.thread_means(),
+            vec![Duration::from_millis(3), Duration::from_millis(10)]
+        );
+        assert_eq!(result.thread_spread(), Duration::from_millis(7));
+        assert!(result
+            .thread_breakdown()
+            .starts_with("#0 3.00ms, #1 10.00ms"));
+        let result = ConcurrentResult::new(
+            "idle",
+            vec![
+                BenchVec::new(),
+                BenchVec::from_vec(&[Duration::from_millis(2)]),
+            ],
+            Duration::from_millis(2),
+        );
+        assert!(result.thread_breakdown().starts_with("#1 2.00ms (spread"));
+    }
+
+    #[test]
+    fn it_samples_with_a_user_supplied_timer() {
+        struct CycleCounter(u64);
+        impl Timer for CycleCounter {
+            fn now(&mut self) -> u64 {
+                self.0 += 3;
+                self.0 * self.0
+            }
+
+            fn ticks_per_second(&self) -> u64 {
+                1_000_000
+            }
+        }
+
+        let mut samples = Samples::<4>::new();
+        samples.collect(&mut CycleCounter(0), || ());
+        assert!(!samples.push(Duration::from_secs(1)));
+        let summary = samples.summary().unwrap();
+        // the durations are 27µs, 63µs, 99µs and 135µs
+        assert_eq!(summary.samples, 4);
+        assert_eq!(summary.min, Duration::from_micros(27));
+        assert_eq!(summary.median, Duration::from_micros(63));
+        assert_eq!(summary.max, Duration::from_micros(135));
+        assert_eq!(summary.mean, Duration::from_micros(81));
+        let bench_vec = BenchVec::from_vec(samples.as_slice());
+        assert_eq!(bench_vec.average(), summary.mean);
+        assert_eq!(bench_vec.percentile(50f64), summary.median);
+        // the deviations are ±18µs and ±54µs, so the variance is 2160µs²
+        let std_dev = 2160e6f64.sqrt();
+        assert!((bench_vec.standard_deviation() - std_dev).abs() < 1e-6);
+        assert_eq!(summary.std_dev, Duration::from_nanos(std_dev as u64));
+        assert_eq!(
+            Duration::from_nanos(bench_vec.standard_deviation() as u64),
+            summary.std_dev
+        );
+        assert!((bench_vec.coefficient_of_variation() - std_dev / 81e3).abs() < 1e-9);
+        assert_eq!(Summary::of(&mut []), None);
+        assert_eq!(sampling::nearest_rank(&[], 50f64), None);
+        assert_eq!(BenchVec::new().percentile(50f64), Duration::from_secs(0));
+        assert_eq!(BenchVec::new().average(), Duration::from_secs(0));
+        for value in [0f64, 1e-3, 2f64, 2160e6, 1e300] {
+            let root = sampling::newton_sqrt(value);
+            assert!((root - value.sqrt()).abs() <= value.sqrt() * 1e-15);
+        }
+        assert!(sampling::newton_sqrt(-1f64).is_nan());
+    }
 }
@@ -0,0 +1,52 @@
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use crate::benching::BenchVec;
+use crate::units::{Format, FormatWith};
+
+/// The measurement of a closure that was called at a fixed arrival rate
+#[derive(Debug, Clone)]
+pub struct OpenLoopResult {
+    pub name: String,
+    /// The targeted number of operations per second
+    pub rate: f64,
+    /// The time from the intended start of each operation until it completed,
+    /// including the time it waited for a free worker
+    pub response_times: BenchVec,
+    /// The time each operation took to execute
+    pub service_times: BenchVec,
+    /// The time from the first intended start until the last operation completed
+    pub wall_time: Duration,
+}
+
+impl OpenLoopResult {
+    /// Returns the number of operations that completed per second
+    pub fn achieved_rate(&self) -> f64 {
+        self.response_times.len() as f64 / self.wall_time.as_secs_f64()
+    }
+
+    /// Returns the average time the operations waited for a free worker
+    pub fn queueing_time(&self) -> Duration {
+        self.response_times
+            .average()
+            .checked_sub(self.service_times.average())
+            .unwrap_or_default()
+    }
+}
+
+impl Display for OpenLoopResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for OpenLoopResult {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(
+            f,
+            "{} ops/s targeted, {} ops/s achieved",
+            format.number(self.rate),
+            format.number(self.achieved_rate())
+        )
+    }
+}
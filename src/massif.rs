@@ -0,0 +1,92 @@
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::benching::file_stem;
+
+/// The environment variable that selects the benchmark to run
+/// when the process was started under massif
+pub const MASSIF_ENV: &str = "BENCHLIB_MASSIF";
+
+/// The peak heap snapshot of a benchmark run under massif
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MassifSnapshot {
+    /// The massif output file, which can be opened with ms_print or massif-visualizer
+    pub path: PathBuf,
+    /// The number of the peak snapshot in the output file
+    pub snapshot: usize,
+    /// The heap size at the peak including the allocator overhead
+    pub peak_bytes: u64,
+}
+
+impl MassifSnapshot {
+    /// Parses a massif output file and returns the number of the peak
+    /// snapshot and its heap size
+    pub fn parse(contents: &str) -> Option<(usize, u64)> {
+        let mut snapshot = None;
+        let mut heap = 0;
+        let mut extra = 0;
+        for line in contents.lines() {
+            if let Some(value) = line.strip_prefix("snapshot=") {
+                snapshot = value.trim().parse::<usize>().ok();
+            } else if let Some(value) = line.strip_prefix("mem_heap_B=") {
+                heap = value.trim().parse::<u64>().ok()?;
+            } else if let Some(value) = line.strip_prefix("mem_heap_extra_B=") {
+                extra = value.trim().parse::<u64>().ok()?;
+            } else if line.trim() == "heap_tree=peak" {
+                return Some((snapshot?, heap + extra));
+            }
+        }
+
+        None
+    }
+}
+
+impl Display for MassifSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "peak heap of {} bytes in snapshot {} of {}",
+            self.peak_bytes,
+            self.snapshot,
+            self.path.display()
+        )
+    }
+}
+
+/// Returns the name of the benchmark to run if the process
+/// was started under massif by the Bencher
+pub fn target() -> Option<String> {
+    env::var(MASSIF_ENV).ok()
+}
+
+/// Runs the current executable with the same arguments under massif with the
+/// given benchmark selected. The output file is kept in the given directory,
+/// so the heap growth can be analyzed later.
+pub fn run(name: &str, dir: &Path) -> io::Result<MassifSnapshot> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("massif.{}.out", file_stem(name)));
+    let status = Command::new("valgrind")
+        .arg("--tool=massif")
+        .arg(format!("--massif-out-file={}", path.display()))
+        .arg(env::current_exe()?)
+        .args(env::args_os().skip(1))
+        .env(MASSIF_ENV, name)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("valgrind exited with {}", status)));
+    }
+    let (snapshot, peak_bytes) = MassifSnapshot::parse(&fs::read_to_string(&path)?)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid massif output"))?;
+
+    Ok(MassifSnapshot {
+        path,
+        snapshot,
+        peak_bytes,
+    })
+}
@@ -0,0 +1,65 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Returns the current resident set size of the process in bytes
+/// if it can be determined on this platform
+#[cfg(target_os = "linux")]
+pub fn resident_set_size() -> Option<usize> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages = statm.split_whitespace().nth(1)?.parse::<usize>().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+
+    Some(pages * page_size as usize)
+}
+
+/// Returns the current resident set size of the process in bytes
+/// if it can be determined on this platform
+#[cfg(not(target_os = "linux"))]
+pub fn resident_set_size() -> Option<usize> {
+    None
+}
+
+/// Samples the resident set size in a background thread and keeps track of its peak
+pub struct RssSampler {
+    running: Arc<AtomicBool>,
+    peak: Arc<AtomicUsize>,
+    handle: JoinHandle<()>,
+}
+
+impl RssSampler {
+    /// Starts sampling the resident set size with the given interval
+    pub fn start(interval: Duration) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let peak = Arc::new(AtomicUsize::new(resident_set_size().unwrap_or(0)));
+        let handle = {
+            let running = Arc::clone(&running);
+            let peak = Arc::clone(&peak);
+            thread::spawn(move || {
+                while running.load(Ordering::Relaxed) {
+                    if let Some(rss) = resident_set_size() {
+                        peak.fetch_max(rss, Ordering::Relaxed);
+                    }
+                    thread::sleep(interval);
+                }
+            })
+        };
+
+        Self {
+            running,
+            peak,
+            handle,
+        }
+    }
+
+    /// Stops sampling and returns the peak resident set size in bytes
+    /// if it could be determined
+    pub fn stop(self) -> Option<usize> {
+        self.running.store(false, Ordering::Relaxed);
+        let _ = self.handle.join();
+        let rss = resident_set_size()?;
+
+        Some(self.peak.load(Ordering::Relaxed).max(rss))
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use crate::units::{Format, FormatWith};
+
+/// Auxiliary metrics reported by a benchmarked closure for each iteration
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    current: BTreeMap<String, f64>,
+    samples: BTreeMap<String, Vec<f64>>,
+}
+
+impl Metrics {
+    /// Creates a new empty set of metrics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a value for the current iteration.
+    /// Values recorded multiple times in one iteration are summed up.
+    pub fn record(&mut self, name: &str, value: f64) -> &mut Self {
+        *self.current.entry(name.to_string()).or_insert(0f64) += value;
+
+        self
+    }
+
+    /// Stores the values of the current iteration as samples
+    pub(crate) fn finish_iteration(&mut self) {
+        for (name, value) in std::mem::take(&mut self.current) {
+            self.samples.entry(name).or_default().push(value);
+        }
+    }
+
+    /// Returns the names of all recorded metrics
+    pub fn names(&self) -> Vec<&str> {
+        self.samples.keys().map(|k| k.as_str()).collect()
+    }
+
+    /// Returns the values of a metric for each iteration it was recorded in
+    pub fn samples(&self, name: &str) -> Option<&Vec<f64>> {
+        self.samples.get(name)
+    }
+
+    /// Returns the sum of all values of a metric
+    pub fn total(&self, name: &str) -> Option<f64> {
+        self.samples.get(name).map(|values| values.iter().sum())
+    }
+
+    /// Returns the average value of a metric per iteration
+    pub fn mean(&self, name: &str) -> Option<f64> {
+        self.samples
+            .get(name)
+            .map(|values| values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    /// Returns if no metrics were recorded
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Formats the average values of all metrics as comma separated key value pairs
+    pub fn to_column(&self) -> String {
+        self.to_column_with(&Format::default())
+    }
+
+    /// Formats the average values of all metrics as comma separated key value
+    /// pairs with the precision of the given format
+    pub fn to_column_with(&self, format: &Format) -> String {
+        self.names()
+            .iter()
+            .map(|name| {
+                format!(
+                    "{}={}",
+                    name,
+                    format.exported(self.mean(name).unwrap_or(0f64))
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+impl Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for Metrics {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        let metrics = self
+            .names()
+            .iter()
+            .map(|name| {
+                format!(
+                    "{}: {} per iteration (total {})",
+                    name,
+                    format.number(self.mean(name).unwrap_or(0f64)),
+                    format.number(self.total(name).unwrap_or(0f64))
+                )
+            })
+            .collect::<Vec<String>>();
+        write!(f, "{}", metrics.join(", "))
+    }
+}
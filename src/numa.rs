@@ -0,0 +1,69 @@
+use std::io;
+use std::os::raw::{c_int, c_uint};
+
+#[repr(C)]
+struct Bitmask {
+    _private: [u8; 0],
+}
+
+#[link(name = "numa")]
+extern "C" {
+    fn numa_available() -> c_int;
+    fn numa_max_node() -> c_int;
+    fn numa_allocate_nodemask() -> *mut Bitmask;
+    fn numa_bitmask_setbit(bitmask: *mut Bitmask, n: c_uint) -> *mut Bitmask;
+    fn numa_bitmask_free(bitmask: *mut Bitmask);
+    fn numa_bind(nodemask: *mut Bitmask);
+    fn numa_run_on_node(node: c_int) -> c_int;
+    fn numa_set_localalloc();
+}
+
+/// Restores the default NUMA policy when dropped
+pub struct NumaGuard {
+    _private: (),
+}
+
+/// Binds execution and memory allocation of the calling thread to the given
+/// NUMA node until the returned guard is dropped
+pub fn bind(node: usize) -> io::Result<NumaGuard> {
+    unsafe {
+        if numa_available() < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "NUMA is not available on this system",
+            ));
+        }
+        if node > numa_max_node() as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("NUMA node {} does not exist", node),
+            ));
+        }
+        let nodemask = numa_allocate_nodemask();
+        numa_bitmask_setbit(nodemask, node as c_uint);
+        numa_bind(nodemask);
+        numa_bitmask_free(nodemask);
+    }
+
+    Ok(NumaGuard { _private: () })
+}
+
+/// Returns the number of configured NUMA nodes or None if NUMA isn't available
+pub fn node_count() -> Option<usize> {
+    unsafe {
+        if numa_available() < 0 {
+            None
+        } else {
+            Some(numa_max_node() as usize + 1)
+        }
+    }
+}
+
+impl Drop for NumaGuard {
+    fn drop(&mut self) {
+        unsafe {
+            numa_run_on_node(-1);
+            numa_set_localalloc();
+        }
+    }
+}
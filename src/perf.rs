@@ -0,0 +1,95 @@
+use std::io;
+use std::ops::AddAssign;
+
+use perf_event::events::Hardware;
+use perf_event::{Builder, Counter, Group};
+
+/// A group of hardware performance counters opened via perf_event
+pub struct PerfCounters {
+    group: Group,
+    instructions: Counter,
+    cycles: Counter,
+    cache_misses: Counter,
+    branch_misses: Counter,
+}
+
+impl PerfCounters {
+    /// Opens the performance counters for the calling process
+    pub fn new() -> io::Result<Self> {
+        let mut group = Group::new()?;
+        let instructions = Builder::new()
+            .group(&mut group)
+            .kind(Hardware::INSTRUCTIONS)
+            .build()?;
+        let cycles = Builder::new()
+            .group(&mut group)
+            .kind(Hardware::CPU_CYCLES)
+            .build()?;
+        let cache_misses = Builder::new()
+            .group(&mut group)
+            .kind(Hardware::CACHE_MISSES)
+            .build()?;
+        let branch_misses = Builder::new()
+            .group(&mut group)
+            .kind(Hardware::BRANCH_MISSES)
+            .build()?;
+
+        Ok(Self {
+            group,
+            instructions,
+            cycles,
+            cache_misses,
+            branch_misses,
+        })
+    }
+
+    /// Resets and starts the counters
+    pub fn start(&mut self) -> io::Result<()> {
+        self.group.reset()?;
+        self.group.enable()
+    }
+
+    /// Stops the counters and returns the counted events
+    pub fn stop(&mut self) -> io::Result<PerfCounts> {
+        self.group.disable()?;
+        let counts = self.group.read()?;
+
+        Ok(PerfCounts {
+            instructions: counts[&self.instructions],
+            cycles: counts[&self.cycles],
+            cache_misses: counts[&self.cache_misses],
+            branch_misses: counts[&self.branch_misses],
+        })
+    }
+}
+
+/// Hardware events counted by the performance counters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PerfCounts {
+    pub instructions: u64,
+    pub cycles: u64,
+    pub cache_misses: u64,
+    pub branch_misses: u64,
+}
+
+impl PerfCounts {
+    /// Returns the average counts for the given number of iterations
+    pub fn per_iteration(&self, iterations: usize) -> [f64; 4] {
+        let iterations = iterations.max(1) as f64;
+        [
+            self.instructions as f64 / iterations,
+            self.cycles as f64 / iterations,
+            self.cache_misses as f64 / iterations,
+            self.branch_misses as f64 / iterations,
+        ]
+    }
+}
+
+impl AddAssign for PerfCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.instructions += other.instructions;
+        self.cycles += other.cycles;
+        self.cache_misses += other.cache_misses;
+        self.branch_misses += other.branch_misses;
+    }
+}
@@ -0,0 +1,152 @@
+use std::ffi::CString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+
+/// The time to wait for perf to acknowledge a control command in milliseconds
+const ACK_TIMEOUT: i32 = 5000;
+
+/// A `perf record` process attached to the current process that starts
+/// disabled and is enabled through its control FIFO only while the
+/// iterations of a benchmark run, so the profile doesn't contain setup code
+pub struct PerfRecorder {
+    child: Child,
+    control: File,
+    ack: File,
+    fifo_dir: PathBuf,
+    path: PathBuf,
+}
+
+/// Creates a named pipe at the path
+fn make_fifo(path: &Path) -> io::Result<()> {
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if unsafe { libc::mkfifo(path.as_ptr(), 0o600) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Opens a FIFO for reading and writing, which doesn't block until
+/// the other end is opened by perf
+fn open_fifo(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_CLOEXEC)
+        .open(path)
+}
+
+impl PerfRecorder {
+    /// Starts `perf record` with call graphs for the current process
+    /// that writes the profile to the given path when it's finished
+    pub fn start(path: &Path) -> io::Result<Self> {
+        let fifo_dir = std::env::temp_dir().join(format!(
+            "benchlib-perf-{}-{}",
+            std::process::id(),
+            path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        ));
+        fs::create_dir_all(&fifo_dir)?;
+        let control_path = fifo_dir.join("control");
+        let ack_path = fifo_dir.join("ack");
+        make_fifo(&control_path)?;
+        make_fifo(&ack_path)?;
+        let control = open_fifo(&control_path)?;
+        let ack = open_fifo(&ack_path)?;
+        let child = Command::new("perf")
+            .arg("record")
+            .arg("--call-graph=dwarf")
+            .arg("--delay=-1")
+            .arg(format!(
+                "--control=fifo:{},{}",
+                control_path.display(),
+                ack_path.display()
+            ))
+            .arg("--output")
+            .arg(path)
+            .arg("--pid")
+            .arg(std::process::id().to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .inspect_err(|_| {
+                let _ = fs::remove_dir_all(&fifo_dir);
+            })?;
+
+        Ok(Self {
+            child,
+            control,
+            ack,
+            fifo_dir,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Sends a command to perf and waits until it's acknowledged
+    fn command(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.control, "{}", command)?;
+        let mut poll = libc::pollfd {
+            fd: self.ack.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut poll, 1, ACK_TIMEOUT) } <= 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "perf didn't acknowledge the command, it might not be allowed to profile",
+            ));
+        }
+        let mut buffer = [0u8; 16];
+        if self.ack.read(&mut buffer)? == 0 {
+            return Err(io::Error::other("perf closed its control FIFO"));
+        }
+
+        Ok(())
+    }
+
+    /// Starts recording samples
+    pub fn enable(&mut self) -> io::Result<()> {
+        self.command("enable")
+    }
+
+    /// Stops recording samples
+    pub fn disable(&mut self) -> io::Result<()> {
+        self.command("disable")
+    }
+
+    /// Interrupts perf if it's still running and waits until it exits
+    fn stop(&mut self) -> io::Result<ExitStatus> {
+        if let Some(status) = self.child.try_wait()? {
+            return Ok(status);
+        }
+        unsafe { libc::kill(self.child.id() as libc::pid_t, libc::SIGINT) };
+
+        self.child.wait()
+    }
+
+    /// Stops perf, so it writes the profile, and returns the path of the profile.
+    /// The exit status after the interrupt varies, so only the profile is checked.
+    pub fn finish(mut self) -> io::Result<PathBuf> {
+        let status = self.stop()?;
+        if !self.path.exists() {
+            return Err(io::Error::other(format!("perf exited with {}", status)));
+        }
+
+        Ok(self.path.clone())
+    }
+}
+
+impl Drop for PerfRecorder {
+    fn drop(&mut self) {
+        let _ = self.stop();
+        let _ = fs::remove_dir_all(&self.fifo_dir);
+    }
+}
@@ -0,0 +1,106 @@
+use std::io;
+
+/// A scheduling priority the process can be elevated to while measuring
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Priority {
+    /// A nice value for the process, lower values mean a higher priority
+    Nice(i32),
+    /// The SCHED_FIFO real-time policy for the calling thread with the given priority.
+    /// Only supported on Linux.
+    Fifo(i32),
+}
+
+/// Restores the previous priority when dropped
+pub struct PriorityGuard {
+    #[cfg(unix)]
+    restore: Restore,
+}
+
+#[cfg(unix)]
+enum Restore {
+    Nice(i32),
+    #[cfg(target_os = "linux")]
+    Scheduler(libc::c_int, libc::sched_param),
+}
+
+/// Elevates the scheduling priority until the returned guard is dropped.
+/// Raising the priority usually requires elevated permissions.
+#[cfg(unix)]
+pub fn elevate(priority: Priority) -> io::Result<PriorityGuard> {
+    match priority {
+        Priority::Nice(nice) => unsafe {
+            *errno() = 0;
+            let previous = libc::getpriority(libc::PRIO_PROCESS, 0);
+            if previous == -1 && *errno() != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(PriorityGuard {
+                restore: Restore::Nice(previous),
+            })
+        },
+        #[cfg(target_os = "linux")]
+        Priority::Fifo(fifo_priority) => unsafe {
+            let policy = libc::sched_getscheduler(0);
+            let mut previous: libc::sched_param = std::mem::zeroed();
+            if policy == -1 || libc::sched_getparam(0, &mut previous) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let param = libc::sched_param {
+                sched_priority: fifo_priority,
+            };
+            if libc::sched_setscheduler(0, libc::SCHED_FIFO, &param) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(PriorityGuard {
+                restore: Restore::Scheduler(policy, previous),
+            })
+        },
+        #[cfg(not(target_os = "linux"))]
+        Priority::Fifo(_) => Err(unsupported()),
+    }
+}
+
+/// Elevates the scheduling priority until the returned guard is dropped.
+/// Raising the priority usually requires elevated permissions.
+#[cfg(not(unix))]
+pub fn elevate(_priority: Priority) -> io::Result<PriorityGuard> {
+    Err(unsupported())
+}
+
+#[cfg(unix)]
+impl Drop for PriorityGuard {
+    fn drop(&mut self) {
+        match &self.restore {
+            Restore::Nice(nice) => unsafe {
+                libc::setpriority(libc::PRIO_PROCESS, 0, *nice);
+            },
+            #[cfg(target_os = "linux")]
+            Restore::Scheduler(policy, param) => unsafe {
+                libc::sched_setscheduler(0, *policy, param);
+            },
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn errno() -> *mut libc::c_int {
+    libc::__errno_location()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+unsafe fn errno() -> *mut libc::c_int {
+    libc::__error()
+}
+
+#[allow(dead_code)]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "changing the scheduling priority is not supported on this platform",
+    )
+}
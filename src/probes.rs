@@ -0,0 +1,72 @@
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Mutex, RwLock};
+use std::thread;
+
+use crate::benching::Bencher;
+
+/// Runs probe benchmarks of synchronization primitives to characterize the
+/// machine. Every primitive is measured uncontended on the benchmarking
+/// thread and contended by the given number of threads.
+#[track_caller]
+pub fn run(bencher: &mut Bencher, threads: usize) {
+    let threads = threads.max(2);
+
+    let mutex = Mutex::new(0usize);
+    bencher.bench("probe: mutex (uncontended)", || {
+        *mutex.lock().unwrap() += 1;
+    });
+    bencher.bench_concurrent(
+        &format!("probe: mutex ({} threads)", threads),
+        threads,
+        || *mutex.lock().unwrap() += 1,
+    );
+
+    let rwlock = RwLock::new(0usize);
+    bencher.bench("probe: rwlock read (uncontended)", || {
+        black_box(*rwlock.read().unwrap())
+    });
+    bencher.bench_concurrent(
+        &format!("probe: rwlock read ({} threads)", threads),
+        threads,
+        || black_box(*rwlock.read().unwrap()),
+    );
+    bencher.bench_concurrent(
+        &format!("probe: rwlock write ({} threads)", threads),
+        threads,
+        || *rwlock.write().unwrap() += 1,
+    );
+
+    let atomic = AtomicUsize::new(0);
+    let compare_and_swap = || {
+        let mut current = atomic.load(Ordering::Relaxed);
+        while let Err(actual) =
+            atomic.compare_exchange_weak(current, current + 1, Ordering::AcqRel, Ordering::Relaxed)
+        {
+            current = actual;
+        }
+    };
+    bencher.bench("probe: atomic cas (uncontended)", compare_and_swap);
+    bencher.bench_concurrent(
+        &format!("probe: atomic cas ({} threads)", threads),
+        threads,
+        compare_and_swap,
+    );
+
+    let (ping, pings) = mpsc::channel::<usize>();
+    let (pong, pongs) = mpsc::channel::<usize>();
+    let echo = thread::spawn(move || {
+        for value in pings {
+            if pong.send(value).is_err() {
+                break;
+            }
+        }
+    });
+    bencher.bench("probe: channel ping-pong", || {
+        ping.send(1).unwrap();
+        pongs.recv().unwrap()
+    });
+    drop(ping);
+    let _ = echo.join();
+}
@@ -0,0 +1,145 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::benching::BenchVec;
+use crate::ci;
+use crate::json;
+
+/// The time to wait for the collector to accept the connection and to answer
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Describes the run and the machine the results were measured on,
+/// so the collector can tell the reports of several machines apart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunMetadata {
+    /// The start of the upload in seconds since the unix epoch
+    pub timestamp: u64,
+    pub hostname: String,
+    pub os: String,
+    pub arch: String,
+    pub cpus: usize,
+    pub ci: bool,
+    pub version: String,
+}
+
+impl RunMetadata {
+    /// Collects the metadata of the current run
+    pub fn collect() -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            hostname: hostname(),
+            os: env::consts::OS.to_string(),
+            arch: env::consts::ARCH.to_string(),
+            cpus: thread::available_parallelism().map_or(1, |n| n.get()),
+            ci: ci::is_ci(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Formats the metadata as JSON object
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"timestamp\":{},\"hostname\":{},\"os\":{},\"arch\":{},\"cpus\":{},\"ci\":{},\"version\":{}}}",
+            self.timestamp,
+            json::string(&self.hostname),
+            json::string(&self.os),
+            json::string(&self.arch),
+            self.cpus,
+            self.ci,
+            json::string(&self.version)
+        )
+    }
+}
+
+/// Returns the name of the machine or an empty string if it's unknown
+fn hostname() -> String {
+    env::var("HOSTNAME")
+        .or_else(|_| env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Formats the metadata and the results as the JSON document that is pushed
+/// to the collector, with the results formatted like the JSON lines output
+pub fn results_document(metadata: &RunMetadata, results: &[(&str, &BenchVec)]) -> String {
+    let results = results
+        .iter()
+        .map(|(name, durations)| json::result_record(name, durations))
+        .collect::<Vec<String>>();
+
+    format!(
+        "{{\"metadata\":{},\"results\":[{}]}}",
+        metadata.to_json(),
+        results.join(",")
+    )
+}
+
+/// Sends the JSON document to the URL with a POST request.
+/// Only plain `http://` URLs are supported, so the collector has to be
+/// reachable without TLS, e.g. in the lab network or behind a proxy.
+/// Fails if the collector doesn't answer with a 2xx status.
+pub fn post(url: &str, body: &str) -> io::Result<()> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message.to_string());
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| invalid("only http:// URLs are supported"))?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return Err(invalid("the URL has no host"));
+    }
+    let address = if authority.contains(':') && !authority.ends_with(']') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+    let address = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| invalid("the host could not be resolved"))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: benchlib-rs/{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        authority,
+        env!("CARGO_PKG_VERSION"),
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the collector sent an invalid response",
+            )
+        })?;
+    if (200..300).contains(&status) {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "the collector answered with {}",
+            status_line.trim()
+        )))
+    }
+}
@@ -0,0 +1,627 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::benching::{BenchVec, DurationDifference, Verdict};
+use crate::ci;
+use crate::clock::Instant;
+use crate::terminal::{self, Style, Theme};
+use crate::units::{Format, FormatWith};
+
+/// The kind of a message that is reported besides the results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// The title of a sweep or another group of results
+    Heading,
+    /// The title of the settings
+    Section,
+    /// The result line of a benchmark
+    Result,
+    /// A result or a detail of a result
+    Info,
+    /// A remark that is less important than the results
+    Note,
+    /// A problem that potentially makes a result unreliable
+    Warning,
+}
+
+/// Receives the events of a Bencher to present them to the user.
+/// All methods do nothing by default, so a reporter only needs
+/// to implement the events it is interested in. Reporters are Send,
+/// so a Bencher can be moved to another thread.
+pub trait Reporter: Send {
+    /// Called when the color choice of the Bencher is changed
+    fn set_color(&mut self, _choice: ColorChoice) {}
+
+    /// Called when the theme of the Bencher is changed
+    fn set_theme(&mut self, _theme: Theme) {}
+
+    /// Called when the Bencher enables or disables the compact output
+    /// with a single line per benchmark
+    fn set_compact(&mut self, _enabled: bool) {}
+
+    /// Called when the Bencher enables or disables the non-interactive
+    /// output for CI services
+    fn set_ci(&mut self, _enabled: bool) {}
+
+    /// Called when the unit, precision or separators of the Bencher
+    /// are changed and when the reporter is attached to it
+    fn set_format(&mut self, _format: Format) {}
+
+    /// Called before the first sample of a benchmark is measured
+    fn on_bench_start(&mut self, _name: &str) {}
+
+    /// Called right before a sample of a benchmark is measured
+    fn on_sample_start(&mut self, _name: &str) {}
+
+    /// Called after every measured sample of a benchmark
+    fn on_sample(&mut self, _name: &str, _duration: Duration) {}
+
+    /// Called after every sample with the number of finished and planned
+    /// iterations and the time that passed since the benchmark started
+    fn on_progress(&mut self, _name: &str, _done: usize, _total: usize, _elapsed: Duration) {}
+
+    /// Called after every sample while the number of iterations is chosen
+    /// automatically, with the durations measured so far and the time that
+    /// passed since the benchmark started
+    fn on_statistics(&mut self, _name: &str, _durations: &BenchVec, _elapsed: Duration) {}
+
+    /// Called with the durations of a finished benchmark
+    fn on_bench_complete(&mut self, _name: &str, _durations: &BenchVec) {}
+
+    /// Called when a benchmark ends without durations, because it failed,
+    /// ran under cachegrind or runs in the child process of an isolated run
+    fn on_bench_end(&mut self, _name: &str) {}
+
+    /// Called when the last benchmark is compared to the one before it
+    fn on_compare(&mut self, _name: &str, _previous: &str, _difference: &DurationDifference) {}
+
+    /// Called when a note is attached to a benchmark
+    fn on_annotate(&mut self, _name: &str, _text: &str) {}
+
+    /// Called with the formatted results, details, remarks and warnings
+    fn on_message(&mut self, _level: Level, _message: &str) {}
+
+    /// Called with a workflow command of the CI service that has to be
+    /// written as a line of its own, like the annotation of a regression
+    fn on_ci_command(&mut self, _command: &str) {}
+
+    /// Called when all benchmarks are finished
+    fn on_finish(&mut self) {}
+}
+
+/// The number of characters of the progress bar
+const PROGRESS_BAR: usize = 30;
+
+/// Creates the reporter a Bencher uses by default. With the logging feature
+/// the events are passed to the log crate, otherwise they're printed,
+/// with the wasm feature on wasm32 to the browser console.
+pub fn default_reporter() -> Box<dyn Reporter> {
+    #[cfg(feature = "logging")]
+    {
+        Box::new(LogReporter::new())
+    }
+    #[cfg(all(not(feature = "logging"), feature = "wasm", target_arch = "wasm32"))]
+    {
+        Box::new(ConsoleReporter::with_writer(
+            crate::wasm::ConsoleWriter::new(),
+        ))
+    }
+    #[cfg(all(
+        not(feature = "logging"),
+        not(all(feature = "wasm", target_arch = "wasm32"))
+    ))]
+    {
+        Box::new(ConsoleReporter::new())
+    }
+}
+
+/// Decides if the console output uses ANSI colors and styles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colors are used if stdout is a terminal and NO_COLOR isn't set
+    Auto,
+    /// Colors are always used
+    Always,
+    /// Colors are never used
+    Never,
+}
+
+impl ColorChoice {
+    /// Returns if colors should be used for stdout
+    pub fn use_color(&self) -> bool {
+        self.use_color_if(terminal::supports_styles())
+    }
+
+    /// Returns if colors should be used for stderr
+    pub fn use_color_on_stderr(&self) -> bool {
+        self.use_color_if(terminal::stderr_supports_styles())
+    }
+
+    fn use_color_if(&self, supported: bool) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && supported
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// The stream the console reporter writes to
+#[derive(Clone)]
+enum Output {
+    Stdout,
+    Stderr,
+    Writer(Arc<Mutex<dyn Write + Send>>),
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Stdout => write!(f, "Stdout"),
+            Output::Stderr => write!(f, "Stderr"),
+            Output::Writer(_) => write!(f, "Writer"),
+        }
+    }
+}
+
+/// Prints the events to the terminal, in color if it supports it.
+/// The colors are created by the terminal backend selected by the
+/// termion (default) or crossterm feature. Instead of the terminal
+/// the output can be written to any writer.
+///
+/// On CI services the output is not interactive. Colors are only used if
+/// they are enabled explicitly, the progress bar is hidden and each
+/// benchmark is condensed to a line with its name and result.
+/// The compact output goes further and only prints an aligned line with
+/// the name, mean, standard deviation and iterations of each benchmark
+/// besides the warnings.
+#[derive(Debug, Clone)]
+pub struct ConsoleReporter {
+    choice: ColorChoice,
+    theme: Theme,
+    ci: bool,
+    compact: bool,
+    color: bool,
+    interactive: bool,
+    output: Output,
+    format: Format,
+    current: Option<String>,
+    progress_drawn: Option<Instant>,
+    statistics_shown: bool,
+}
+
+impl ConsoleReporter {
+    pub fn new() -> Self {
+        Self::with_color(ColorChoice::Auto)
+    }
+
+    /// Creates a reporter that uses colors according to the given choice
+    pub fn with_color(choice: ColorChoice) -> Self {
+        Self::with_output(choice, Output::Stdout)
+    }
+
+    /// Creates a reporter that prints to stderr instead of stdout,
+    /// so stdout is left to the output of another reporter
+    pub fn stderr() -> Self {
+        Self::with_output(ColorChoice::Auto, Output::Stderr)
+    }
+
+    /// Creates a reporter that writes to the given writer, like a file or a
+    /// buffer. The writer is not a terminal, so colors are only used if they
+    /// are enabled explicitly and the progress bar is never shown.
+    pub fn with_writer<W: Write + Send + 'static>(writer: W) -> Self {
+        Self::with_output(
+            ColorChoice::Auto,
+            Output::Writer(Arc::new(Mutex::new(writer))),
+        )
+    }
+
+    fn with_output(choice: ColorChoice, output: Output) -> Self {
+        let mut reporter = Self {
+            choice,
+            theme: Theme::default(),
+            ci: ci::is_ci(),
+            compact: false,
+            color: false,
+            interactive: false,
+            output,
+            format: Format::default(),
+            current: None,
+            progress_drawn: None,
+            statistics_shown: false,
+        };
+        reporter.update();
+
+        reporter
+    }
+
+    /// Decides about the colors and the progress bar for the output
+    fn update(&mut self) {
+        let terminal = match self.output {
+            Output::Stdout => terminal::supports_styles(),
+            Output::Stderr => terminal::stderr_supports_styles(),
+            Output::Writer(_) => false,
+        };
+        self.interactive = terminal && !self.ci;
+        self.color = match self.choice {
+            ColorChoice::Auto if self.ci => false,
+            choice => choice.use_color_if(terminal),
+        };
+    }
+
+    /// Writes the text to the stream of the reporter
+    fn write(&self, text: &str) {
+        match &self.output {
+            Output::Stdout => {
+                print!("{}", text);
+                let _ = io::stdout().flush();
+            }
+            Output::Stderr => eprint!("{}", text),
+            Output::Writer(writer) => {
+                let mut writer = writer.lock().unwrap();
+                let _ = writer.write_all(text.as_bytes());
+                let _ = writer.flush();
+            }
+        }
+    }
+
+    /// Writes the text as a line to the stream of the reporter
+    fn write_line(&self, text: &str) {
+        self.write(&format!("{}\n", text));
+    }
+
+    /// Removes the progress bar from the current line if it's shown
+    fn clear_progress(&mut self) {
+        if self.progress_drawn.take().is_some() {
+            self.write(&format!("\r{}", terminal::clear_line()));
+        }
+    }
+
+    /// Formats the text with the given style if colors are used
+    fn styled(&self, style: Style, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", self.theme.style(style), text, terminal::reset())
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+impl Default for ConsoleReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reporter for ConsoleReporter {
+    fn set_color(&mut self, choice: ColorChoice) {
+        self.choice = choice;
+        self.update();
+    }
+
+    fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    fn set_ci(&mut self, enabled: bool) {
+        self.ci = enabled;
+        self.update();
+    }
+
+    fn set_compact(&mut self, enabled: bool) {
+        self.compact = enabled;
+    }
+
+    fn set_format(&mut self, format: Format) {
+        self.format = format;
+    }
+
+    fn on_bench_start(&mut self, name: &str) {
+        self.statistics_shown = false;
+        if self.compact {
+            return;
+        }
+        if self.ci {
+            self.current = Some(name.to_string());
+        } else {
+            self.on_message(Level::Heading, name);
+        }
+    }
+
+    fn on_progress(&mut self, _name: &str, done: usize, total: usize, elapsed: Duration) {
+        // short benchmarks finish before a bar would be useful and
+        // the status line of the automatic mode replaces the bar
+        if !self.interactive || elapsed < Duration::from_secs(1) || done == 0 {
+            return;
+        }
+        if self.statistics_shown {
+            return;
+        }
+        if let Some(drawn) = self.progress_drawn {
+            if drawn.elapsed() < Duration::from_millis(100) && done < total {
+                return;
+            }
+        }
+        let fraction = (done as f64 / total.max(1) as f64).min(1f64);
+        let filled = (fraction * PROGRESS_BAR as f64) as usize;
+        let remaining = elapsed.mul_f64((total.saturating_sub(done)) as f64 / done as f64);
+        let line = format!(
+            "[{}{}] {}/{} iterations, {}s elapsed, ETA {}s",
+            "=".repeat(filled),
+            " ".repeat(PROGRESS_BAR - filled),
+            self.format.count(done),
+            self.format.count(total),
+            self.format.number(elapsed.as_secs_f64()),
+            self.format.number(remaining.as_secs_f64())
+        );
+        self.write(&format!("\r{}{}", terminal::clear_line(), line));
+        self.progress_drawn = Some(Instant::now());
+    }
+
+    fn on_statistics(&mut self, _name: &str, durations: &BenchVec, elapsed: Duration) {
+        if !self.interactive || elapsed < Duration::from_secs(1) || durations.len() < 2 {
+            return;
+        }
+        if let Some(drawn) = self.progress_drawn {
+            if drawn.elapsed() < Duration::from_millis(250) {
+                return;
+            }
+        }
+        let line = format!(
+            "mean {}, CV {}%, {} iterations, {}s elapsed",
+            self.format.duration(durations.average()),
+            self.format
+                .number(durations.coefficient_of_variation() * 100f64),
+            self.format.count(durations.len()),
+            self.format.number(elapsed.as_secs_f64())
+        );
+        self.write(&format!("\r{}{}", terminal::clear_line(), line));
+        self.progress_drawn = Some(Instant::now());
+        self.statistics_shown = true;
+    }
+
+    fn on_bench_complete(&mut self, name: &str, durations: &BenchVec) {
+        if !self.compact {
+            return;
+        }
+        self.clear_progress();
+        let line = format!(
+            "{:<40} {:>12} ±{:>12} {:>8} iterations",
+            name,
+            self.format.duration(durations.average()),
+            self.format.nanos(durations.standard_deviation()),
+            self.format.count(durations.len())
+        );
+        self.write_line(&self.styled(Style::Result, &line));
+    }
+
+    fn on_annotate(&mut self, name: &str, text: &str) {
+        self.on_message(Level::Note, &format!("Note on {}: {}", name, text));
+    }
+
+    fn on_compare(&mut self, _name: &str, _previous: &str, difference: &DurationDifference) {
+        if self.compact {
+            return;
+        }
+        self.clear_progress();
+        let style = match difference.verdict {
+            Verdict::Improvement => Style::Improvement,
+            Verdict::Minor => Style::Minor,
+            Verdict::Regression => Style::Regression,
+            Verdict::Insignificant => Style::Insignificant,
+        };
+        self.write_line(&format!(
+            "Difference: {}",
+            self.styled(style, &difference.with_format(self.format).to_string())
+        ));
+    }
+
+    fn on_message(&mut self, level: Level, message: &str) {
+        if self.compact && level != Level::Warning {
+            return;
+        }
+        self.clear_progress();
+        let line = match level {
+            Level::Heading => format!("\n{}", self.styled(Style::Heading, message)),
+            Level::Section => format!("\n{}", self.styled(Style::Section, message)),
+            Level::Result => match self.current.take() {
+                Some(name) if self.ci => {
+                    self.styled(Style::Result, &format!("{}: {}", name, message))
+                }
+                _ => self.styled(Style::Result, message),
+            },
+            Level::Info => message.to_string(),
+            Level::Note => self.styled(Style::Note, message),
+            Level::Warning => self.styled(Style::Warning, &format!("Warning: {}", message)),
+        };
+        self.write_line(&line);
+    }
+
+    fn on_ci_command(&mut self, command: &str) {
+        self.clear_progress();
+        self.write_line(command);
+    }
+}
+
+/// Passes the events to the log crate with the target "benchlib", so the
+/// application decides about their destination, filtering and format.
+/// Subscribers of tracing receive them via its log compatibility layer.
+#[cfg(feature = "logging")]
+#[derive(Debug, Clone, Default)]
+pub struct LogReporter {
+    format: Format,
+}
+
+#[cfg(feature = "logging")]
+impl LogReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "logging")]
+impl Reporter for LogReporter {
+    fn set_format(&mut self, format: Format) {
+        self.format = format;
+    }
+
+    fn on_bench_start(&mut self, name: &str) {
+        log::info!(target: "benchlib", "Benchmarking {}", name);
+    }
+
+    fn on_sample(&mut self, name: &str, duration: Duration) {
+        log::trace!(target: "benchlib", "Sample of {}: {:?}", name, duration);
+    }
+
+    fn on_annotate(&mut self, name: &str, text: &str) {
+        log::info!(target: "benchlib", "Note on {}: {}", name, text);
+    }
+
+    fn on_compare(&mut self, name: &str, previous: &str, difference: &DurationDifference) {
+        log::info!(
+            target: "benchlib",
+            "Difference of {} to {}: {}",
+            name,
+            previous,
+            difference.with_format(self.format)
+        );
+    }
+
+    fn on_message(&mut self, level: Level, message: &str) {
+        match level {
+            Level::Heading | Level::Section | Level::Result | Level::Info => {
+                log::info!(target: "benchlib", "{}", message)
+            }
+            Level::Note => log::debug!(target: "benchlib", "{}", message),
+            Level::Warning => log::warn!(target: "benchlib", "{}", message),
+        }
+    }
+}
+
+/// Emits a span with the target "benchlib" for each benchmark that is entered
+/// while it runs and records its iterations, mean, standard deviation,
+/// minimum and maximum in nanoseconds when it's complete. Optionally every
+/// sample is emitted as debug span that is entered while the closure runs
+/// and records the measured duration, so applications with a tracing
+/// subscriber get the benchmark telemetry in their existing pipeline.
+#[cfg(feature = "tracing-spans")]
+#[derive(Debug, Default)]
+pub struct TracingReporter {
+    samples: bool,
+    span: Option<EnteredSpan>,
+    sample: Option<EnteredSpan>,
+}
+
+/// A span that is entered until it's dropped. Unlike the guard of tracing
+/// it's Send, so the reporter is. The Bencher enters and exits it on the
+/// thread that runs the benchmark.
+#[cfg(feature = "tracing-spans")]
+#[derive(Debug)]
+struct EnteredSpan(tracing::Span);
+
+#[cfg(feature = "tracing-spans")]
+impl EnteredSpan {
+    fn enter(span: tracing::Span) -> Self {
+        span.with_subscriber(|(id, dispatch)| dispatch.enter(id));
+
+        Self(span)
+    }
+}
+
+#[cfg(feature = "tracing-spans")]
+impl std::ops::Deref for EnteredSpan {
+    type Target = tracing::Span;
+
+    fn deref(&self) -> &tracing::Span {
+        &self.0
+    }
+}
+
+#[cfg(feature = "tracing-spans")]
+impl Drop for EnteredSpan {
+    fn drop(&mut self) {
+        self.0.with_subscriber(|(id, dispatch)| dispatch.exit(id));
+    }
+}
+
+#[cfg(feature = "tracing-spans")]
+impl TracingReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a reporter that also emits a span for every sample
+    pub fn with_samples() -> Self {
+        Self {
+            samples: true,
+            span: None,
+            sample: None,
+        }
+    }
+}
+
+#[cfg(feature = "tracing-spans")]
+impl Reporter for TracingReporter {
+    fn on_bench_start(&mut self, name: &str) {
+        // the span of the previous benchmark is exited first,
+        // so the new span isn't its child
+        self.sample = None;
+        self.span = None;
+        self.span = Some(EnteredSpan::enter(tracing::info_span!(
+            target: "benchlib",
+            "benchmark",
+            name,
+            iterations = tracing::field::Empty,
+            mean_ns = tracing::field::Empty,
+            stddev_ns = tracing::field::Empty,
+            min_ns = tracing::field::Empty,
+            max_ns = tracing::field::Empty,
+        )));
+    }
+
+    fn on_sample_start(&mut self, name: &str) {
+        if self.samples {
+            self.sample = Some(EnteredSpan::enter(tracing::debug_span!(
+                target: "benchlib",
+                "sample",
+                name,
+                duration_ns = tracing::field::Empty,
+            )));
+        }
+    }
+
+    fn on_sample(&mut self, _name: &str, duration: Duration) {
+        if let Some(sample) = self.sample.take() {
+            sample.record("duration_ns", duration.as_nanos() as u64);
+        }
+    }
+
+    fn on_bench_complete(&mut self, _name: &str, durations: &BenchVec) {
+        if let Some(span) = self.span.take() {
+            span.record("iterations", durations.len() as u64);
+            span.record("mean_ns", durations.average().as_nanos() as u64);
+            span.record("stddev_ns", durations.standard_deviation());
+            span.record("min_ns", durations.min().as_nanos() as u64);
+            span.record("max_ns", durations.max().as_nanos() as u64);
+        }
+    }
+
+    fn on_bench_end(&mut self, _name: &str) {
+        self.sample = None;
+        self.span = None;
+    }
+
+    fn on_message(&mut self, level: Level, message: &str) {
+        if level == Level::Warning {
+            tracing::warn!(target: "benchlib", "{}", message);
+        }
+    }
+
+    fn on_finish(&mut self) {
+        self.span = None;
+    }
+}
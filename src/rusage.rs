@@ -0,0 +1,77 @@
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use crate::units::{Format, FormatWith};
+
+/// Resource usage statistics of the process as reported by `getrusage`
+#[derive(Debug, Clone, Default)]
+pub struct ResourceUsage {
+    pub minor_page_faults: u64,
+    pub major_page_faults: u64,
+    pub voluntary_context_switches: u64,
+    pub involuntary_context_switches: u64,
+    pub user_time: Duration,
+    pub system_time: Duration,
+}
+
+impl ResourceUsage {
+    /// Returns the current resource usage of the process
+    pub fn now() -> Self {
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+
+        Self {
+            minor_page_faults: usage.ru_minflt as u64,
+            major_page_faults: usage.ru_majflt as u64,
+            voluntary_context_switches: usage.ru_nvcsw as u64,
+            involuntary_context_switches: usage.ru_nivcsw as u64,
+            user_time: timeval_to_duration(&usage.ru_utime),
+            system_time: timeval_to_duration(&usage.ru_stime),
+        }
+    }
+
+    /// Returns the resource usage since the given snapshot
+    pub fn since(&self, start: &Self) -> Self {
+        Self {
+            minor_page_faults: self.minor_page_faults - start.minor_page_faults,
+            major_page_faults: self.major_page_faults - start.major_page_faults,
+            voluntary_context_switches: self.voluntary_context_switches
+                - start.voluntary_context_switches,
+            involuntary_context_switches: self.involuntary_context_switches
+                - start.involuntary_context_switches,
+            user_time: self
+                .user_time
+                .checked_sub(start.user_time)
+                .unwrap_or_default(),
+            system_time: self
+                .system_time
+                .checked_sub(start.system_time)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Display for ResourceUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for ResourceUsage {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(
+            f,
+            "Page faults: {} minor, {} major\nContext switches: {} voluntary, {} involuntary\nUser time: {}, System time: {}",
+            self.minor_page_faults,
+            self.major_page_faults,
+            self.voluntary_context_switches,
+            self.involuntary_context_switches,
+            format.duration(self.user_time),
+            format.duration(self.system_time)
+        )
+    }
+}
+
+fn timeval_to_duration(time: &libc::timeval) -> Duration {
+    Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000)
+}
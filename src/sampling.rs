@@ -0,0 +1,215 @@
+// The measurement core shared by BenchVec and builds without the std feature,
+// which only contain this module. It may only use `core` and no allocations.
+// Float functions like `sqrt` need the std library, so without it they are
+// implemented here.
+use core::hint::black_box;
+use core::time::Duration;
+
+/// A source of timestamps supplied by the user, like the cycle counter
+/// or a hardware timer of a microcontroller
+pub trait Timer {
+    /// Returns the current value of the timer in ticks
+    fn now(&mut self) -> u64;
+
+    /// Returns the frequency of the timer
+    fn ticks_per_second(&self) -> u64;
+
+    /// Converts a number of ticks into a duration
+    fn to_duration(&self, ticks: u64) -> Duration {
+        let nanos = ticks as u128 * 1_000_000_000 / self.ticks_per_second().max(1) as u128;
+
+        Duration::from_nanos(nanos as u64)
+    }
+}
+
+/// A fixed number of samples stored without allocations
+#[derive(Debug, Clone)]
+pub struct Samples<const N: usize> {
+    durations: [Duration; N],
+    len: usize,
+}
+
+impl<const N: usize> Samples<N> {
+    /// Creates an empty buffer for up to N samples
+    pub fn new() -> Self {
+        Self {
+            durations: [Duration::from_secs(0); N],
+            len: 0,
+        }
+    }
+
+    /// Adds a sample and returns false if the buffer is full
+    pub fn push(&mut self, duration: Duration) -> bool {
+        if self.len == N {
+            return false;
+        }
+        self.durations[self.len] = duration;
+        self.len += 1;
+
+        true
+    }
+
+    /// Returns the stored samples
+    pub fn as_slice(&self) -> &[Duration] {
+        &self.durations[..self.len]
+    }
+
+    /// Returns the number of stored samples
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns if no sample is stored
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all samples
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Measures the closure with the timer until the buffer is full.
+    /// The overhead of reading the timer is contained in every sample.
+    pub fn collect<T: Timer, F: FnMut() -> R, R>(&mut self, timer: &mut T, mut func: F) {
+        while self.len < N {
+            let start = timer.now();
+            black_box(func());
+            let end = timer.now();
+            self.push(timer.to_duration(end.wrapping_sub(start)));
+        }
+    }
+
+    /// Calculates the statistics of the stored samples
+    pub fn summary(&self) -> Option<Summary> {
+        let mut sorted = self.durations;
+        Summary::of(&mut sorted[..self.len])
+    }
+}
+
+impl<const N: usize> Default for Samples<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The statistics of a set of samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub samples: usize,
+    pub mean: Duration,
+    pub std_dev: Duration,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Summary {
+    /// Calculates the statistics of the durations, which are sorted in place.
+    /// Returns None if there are no durations.
+    pub fn of(durations: &mut [Duration]) -> Option<Self> {
+        durations.sort_unstable();
+
+        Some(Self {
+            samples: durations.len(),
+            mean: average(durations)?,
+            std_dev: Duration::from_nanos(standard_deviation(durations) as u64),
+            min: *durations.first()?,
+            median: nearest_rank(durations, 50f64)?,
+            max: *durations.last()?,
+        })
+    }
+}
+
+/// Returns the sum of the durations
+pub fn sum(durations: &[Duration]) -> Duration {
+    durations.iter().sum()
+}
+
+/// Returns the average of the durations or None if there are none
+pub fn average(durations: &[Duration]) -> Option<Duration> {
+    if durations.is_empty() {
+        return None;
+    }
+
+    Some(sum(durations) / durations.len() as u32)
+}
+
+/// Returns the sample standard deviation of the durations in nanoseconds
+pub fn standard_deviation(durations: &[Duration]) -> f64 {
+    sqrt(sample_variance(
+        durations.iter().map(|d| d.as_nanos() as f64),
+    ))
+}
+
+/// Returns the arithmetic mean of the values
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Returns the unbiased sample variance of the values
+pub fn variance(values: &[f64]) -> f64 {
+    sample_variance(values.iter().copied())
+}
+
+/// Returns the unbiased sample variance or 0 for less than two values
+fn sample_variance<I: Iterator<Item = f64> + Clone>(values: I) -> f64 {
+    let (count, total) = values
+        .clone()
+        .fold((0usize, 0f64), |(count, total), v| (count + 1, total + v));
+    if count < 2 {
+        return 0f64;
+    }
+    let mean = total / count as f64;
+
+    values.map(|v| (v - mean) * (v - mean)).sum::<f64>() / (count - 1) as f64
+}
+
+/// Returns the given percentile (0 to 100) of the sorted durations using
+/// the nearest rank or None if there are no durations
+pub fn nearest_rank(sorted: &[Duration], percentile: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let position = (percentile / 100f64) * sorted.len() as f64 - 1e-9;
+    let mut rank = position.max(0f64) as usize;
+    if (rank as f64) < position {
+        rank += 1;
+    }
+
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Returns the square root of the value
+pub fn sqrt(value: f64) -> f64 {
+    #[cfg(feature = "std")]
+    {
+        value.sqrt()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        newton_sqrt(value)
+    }
+}
+
+/// Approximates the square root with Newton's method for builds without
+/// the std library, starting with the exponent of the value halved
+#[cfg_attr(feature = "std", allow(dead_code))]
+pub(crate) fn newton_sqrt(value: f64) -> f64 {
+    if value.is_nan() || value < 0f64 {
+        return f64::NAN;
+    }
+    if value == 0f64 || value.is_infinite() {
+        return value;
+    }
+    let mut root = f64::from_bits((value.to_bits() >> 1) + (1023 << 51));
+    for _ in 0..64 {
+        let next = (root + value / root) / 2f64;
+        if next == root {
+            break;
+        }
+        root = next;
+    }
+
+    root
+}
@@ -0,0 +1,528 @@
+#[cfg(all(feature = "perf", target_os = "linux"))]
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use crate::benching::{file_stem, BenchVec};
+#[cfg(all(feature = "perf", target_os = "linux"))]
+use crate::perf::PerfCounts;
+use crate::throughput::Throughput;
+use crate::topology::{CpuTopology, Occupancy, Placement};
+use crate::units::{Format, FormatWith};
+
+/// The measurement of a scaling sweep for one thread count
+#[derive(Debug, Clone)]
+pub struct ScalingPoint {
+    pub threads: usize,
+    pub durations: BenchVec,
+    /// The busy time of each worker thread if it was recorded
+    pub thread_times: Vec<Duration>,
+}
+
+/// The regime of a scaling sweep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalingMode {
+    /// The problem size stays fixed while the thread count grows
+    Strong,
+    /// The problem size is `base_size` multiplied with the thread count
+    Weak { base_size: usize },
+}
+
+impl ScalingMode {
+    /// Returns the problem size for the given thread count in weak scaling
+    pub fn problem_size(&self, threads: usize) -> Option<usize> {
+        match self {
+            ScalingMode::Strong => None,
+            ScalingMode::Weak { base_size } => Some(base_size * threads),
+        }
+    }
+}
+
+impl Display for ScalingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScalingMode::Strong => write!(f, "strong"),
+            ScalingMode::Weak { .. } => write!(f, "weak"),
+        }
+    }
+}
+
+/// The measurements of a benchmark for a series of thread counts
+#[derive(Debug, Clone)]
+pub struct ScalingResult {
+    pub name: String,
+    pub points: Vec<ScalingPoint>,
+    pub mode: ScalingMode,
+    /// The topology of the machine the sweep was run on
+    pub topology: Option<CpuTopology>,
+    /// The placement of the worker threads if they were pinned
+    pub placement: Option<Placement>,
+    /// The amount of data processed by one iteration with a single thread
+    pub throughput: Option<Throughput>,
+    /// The hardware events counted by each worker thread per thread count
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    pub thread_counters: BTreeMap<usize, Vec<PerfCounts>>,
+}
+
+impl ScalingResult {
+    /// Creates a new empty strong scaling result
+    pub fn new(name: &str) -> Self {
+        Self::with_mode(name, ScalingMode::Strong)
+    }
+
+    /// Creates a new empty scaling result for the given regime
+    pub fn with_mode(name: &str, mode: ScalingMode) -> Self {
+        Self {
+            name: name.to_string(),
+            points: Vec::new(),
+            mode,
+            topology: None,
+            placement: None,
+            throughput: None,
+            #[cfg(all(feature = "perf", target_os = "linux"))]
+            thread_counters: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the problem size that is used for the given thread count
+    pub fn problem_size(&self, threads: usize) -> Option<usize> {
+        self.mode.problem_size(threads)
+    }
+
+    /// Sets the topology of the machine the sweep is run on
+    pub fn set_topology(&mut self, topology: CpuTopology) -> &mut Self {
+        self.topology = Some(topology);
+
+        self
+    }
+
+    /// Sets the placement the worker threads of the sweep are pinned with
+    pub fn set_placement(&mut self, placement: Placement) -> &mut Self {
+        self.placement = Some(placement);
+
+        self
+    }
+
+    /// Sets the amount of data processed by one iteration with a single thread
+    pub fn set_throughput(&mut self, throughput: Throughput) -> &mut Self {
+        self.throughput = Some(throughput);
+
+        self
+    }
+
+    /// Formats the cycles and cache misses per iteration
+    /// of each worker thread for every thread count
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    pub fn thread_counters_report(&self) -> String {
+        self.thread_counters_report_with(&Format::default())
+    }
+
+    /// Formats the counters of the worker threads like `thread_counters_report`
+    /// with the given format
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    pub fn thread_counters_report_with(&self, format: &Format) -> String {
+        self.thread_counters
+            .iter()
+            .filter_map(|(threads, counts)| {
+                let iterations = self.point(*threads)?.durations.len();
+                let workers = counts
+                    .iter()
+                    .enumerate()
+                    .map(|(worker, counts)| {
+                        let [_, cycles, cache_misses, _] = counts.per_iteration(iterations);
+                        format!(
+                            "#{} {} cycles, {} cache misses",
+                            worker,
+                            format.number(cycles),
+                            format.number(cache_misses)
+                        )
+                    })
+                    .collect::<Vec<String>>();
+                Some(format!("{} threads: {}\n", threads, workers.join("; ")))
+            })
+            .collect()
+    }
+
+    /// Returns how the given thread count occupies the CPUs of the machine
+    /// if the topology is known
+    pub fn occupancy(&self, threads: usize) -> Option<Occupancy> {
+        self.topology
+            .as_ref()
+            .map(|topology| topology.occupancy(threads))
+    }
+
+    /// Adds the measurement for a thread count
+    pub fn push(&mut self, threads: usize, durations: BenchVec) -> &mut Self {
+        self.push_with_thread_times(threads, durations, Vec::new())
+    }
+
+    /// Adds the measurement for a thread count with the busy time of each worker thread
+    pub fn push_with_thread_times(
+        &mut self,
+        threads: usize,
+        durations: BenchVec,
+        thread_times: Vec<Duration>,
+    ) -> &mut Self {
+        self.points.push(ScalingPoint {
+            threads,
+            durations,
+            thread_times,
+        });
+
+        self
+    }
+
+    /// Returns the measurement for the given thread count
+    pub fn point(&self, threads: usize) -> Option<&ScalingPoint> {
+        self.points.iter().find(|p| p.threads == threads)
+    }
+
+    /// Returns the single-thread measurement the other points are compared to
+    pub fn baseline(&self) -> Option<&ScalingPoint> {
+        self.point(1)
+    }
+
+    /// Returns the speedup S(p) = T(1) / T(p) for the given thread count.
+    /// For weak scaling this is the scaled speedup S(p) = p * T(1) / T(p)
+    /// of Gustafson's law since p times the work is done in T(p).
+    pub fn speedup(&self, threads: usize) -> Option<f64> {
+        let baseline = self.baseline()?.durations.average().as_nanos() as f64;
+        let duration = self.point(threads)?.durations.average().as_nanos() as f64;
+        let work = if self.mode != ScalingMode::Strong {
+            threads as f64
+        } else {
+            1f64
+        };
+
+        Some(work * baseline / duration)
+    }
+
+    /// Returns the parallel efficiency E(p) = S(p) / p for the given thread count
+    pub fn efficiency(&self, threads: usize) -> Option<f64> {
+        Some(self.speedup(threads)? / threads as f64)
+    }
+
+    /// Returns the experimentally determined serial fraction of Karp and Flatt
+    /// e(p) = (1 / S(p) - 1 / p) / (1 - 1 / p) for the given thread count.
+    /// A fraction growing with p points at parallel overhead instead of
+    /// a fixed serial part. Only defined for strong scaling and p > 1.
+    pub fn karp_flatt(&self, threads: usize) -> Option<f64> {
+        if threads < 2 || self.mode != ScalingMode::Strong {
+            return None;
+        }
+        let inverse = 1f64 / threads as f64;
+
+        Some((1f64 / self.speedup(threads)? - inverse) / (1f64 - inverse))
+    }
+
+    /// Returns the load imbalance factor max / mean of the busy times of the
+    /// worker threads for the given thread count. Threads that didn't record
+    /// any time are counted as idle, so 1 means a perfectly even distribution.
+    pub fn load_imbalance(&self, threads: usize) -> Option<f64> {
+        let times = &self.point(threads)?.thread_times;
+        let max = times.iter().max()?.as_nanos() as f64;
+        let mean =
+            times.iter().sum::<Duration>().as_nanos() as f64 / times.len().max(threads) as f64;
+        if mean > 0f64 {
+            Some(max / mean)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the average duration in which the amount of data of a
+    /// single thread iteration is processed for the given thread count.
+    /// For weak scaling p times the amount is processed in one iteration.
+    fn duration_per_amount(&self, threads: usize) -> Option<Duration> {
+        let duration = self.point(threads)?.durations.average();
+        if self.mode != ScalingMode::Strong {
+            Some(duration / threads.max(1) as u32)
+        } else {
+            Some(duration)
+        }
+    }
+
+    /// Returns the processed amount per second for the given thread count
+    /// if the throughput is set
+    pub fn rate(&self, threads: usize) -> Option<f64> {
+        Some(
+            self.throughput?
+                .per_second(self.duration_per_amount(threads)?),
+        )
+    }
+
+    /// Returns the thread count after which adding threads yields diminishing
+    /// returns. That is the last point before the rate gained per added thread
+    /// drops below half of the rate of a single thread of the first point.
+    /// Returns None if the returns never diminish or the throughput isn't set.
+    pub fn knee(&self) -> Option<usize> {
+        let mut points = self
+            .points
+            .iter()
+            .filter_map(|p| Some((p.threads, self.rate(p.threads)?)))
+            .collect::<Vec<(usize, f64)>>();
+        points.sort_by_key(|(threads, _)| *threads);
+        let (first_threads, first_rate) = *points.first()?;
+        let per_thread = first_rate / first_threads.max(1) as f64;
+
+        points.windows(2).find_map(|pair| {
+            let ((threads, rate), (next_threads, next_rate)) = (pair[0], pair[1]);
+            let gain = (next_rate - rate) / (next_threads - threads).max(1) as f64;
+            if gain < per_thread / 2f64 {
+                Some(threads)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Formats the rate of each thread count and the point of diminishing returns
+    /// if the throughput is set
+    pub fn throughput_report(&self) -> Option<String> {
+        self.throughput_report_with(&Format::default())
+    }
+
+    /// Formats the rates like `throughput_report` with the given format
+    pub fn throughput_report_with(&self, format: &Format) -> Option<String> {
+        let throughput = self.throughput?;
+        let rates = self
+            .points
+            .iter()
+            .filter_map(|p| {
+                let rate =
+                    throughput.format_rate_with(self.duration_per_amount(p.threads)?, format);
+                Some(format!("{} threads {}", p.threads, rate))
+            })
+            .collect::<Vec<String>>();
+        let knee = match self.knee() {
+            Some(threads) => format!("diminishing returns beyond {} threads", threads),
+            None => "no diminishing returns".to_string(),
+        };
+
+        Some(format!("Throughput: {} ({})", rates.join(", "), knee))
+    }
+
+    /// Fits Amdahl's law to the measurements with a least squares regression
+    /// of the serial fraction. Requires the single-thread measurement and
+    /// at least one measurement with more threads.
+    /// Amdahl's law assumes a fixed problem size, so weak scaling results
+    /// are not fitted.
+    pub fn amdahl_fit(&self) -> Option<AmdahlFit> {
+        if self.mode != ScalingMode::Strong {
+            return None;
+        }
+        let baseline = self.baseline()?.durations.average().as_nanos() as f64;
+        let points = self
+            .points
+            .iter()
+            .filter(|p| p.threads > 1)
+            .map(|p| {
+                let inverse = 1f64 / p.threads as f64;
+                let relative = p.durations.average().as_nanos() as f64 / baseline;
+                (1f64 - inverse, relative - inverse, inverse, relative)
+            })
+            .collect::<Vec<(f64, f64, f64, f64)>>();
+        if points.is_empty() {
+            return None;
+        }
+        let serial_fraction = (points.iter().map(|(x, y, _, _)| x * y).sum::<f64>()
+            / points.iter().map(|(x, _, _, _)| x * x).sum::<f64>())
+        .clamp(0f64, 1f64);
+        let mean = points.iter().map(|(_, _, _, t)| t).sum::<f64>() / points.len() as f64;
+        let residuals = points
+            .iter()
+            .map(|(_, _, inverse, t)| {
+                (t - (serial_fraction + (1f64 - serial_fraction) * inverse)).powi(2)
+            })
+            .sum::<f64>();
+        let total = points
+            .iter()
+            .map(|(_, _, _, t)| (t - mean).powi(2))
+            .sum::<f64>();
+        let r_squared = if total > 0f64 {
+            1f64 - residuals / total
+        } else {
+            1f64
+        };
+
+        Some(AmdahlFit {
+            serial_fraction,
+            r_squared,
+        })
+    }
+
+    /// Formats the points as tab separated lines for the scaling output file
+    pub fn to_tsv(&self) -> String {
+        self.to_tsv_with(&Format::default())
+    }
+
+    /// Formats the points as tab separated lines for the scaling output file
+    /// with the precision of the given format
+    pub fn to_tsv_with(&self, format: &Format) -> String {
+        self.points
+            .iter()
+            .map(|point| {
+                format!(
+                    "{}\t{}\t{}ns\t{}ns\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    self.name,
+                    point.threads,
+                    point.durations.average().as_nanos(),
+                    format.exported(point.durations.standard_deviation()),
+                    format_optional(self.speedup(point.threads), |v| format.exported(v)),
+                    format_optional(self.efficiency(point.threads), |v| format.exported(v)),
+                    self.mode,
+                    format_optional(self.karp_flatt(point.threads), |v| format.exported(v)),
+                    format_optional(self.load_imbalance(point.threads), |v| format.exported(v)),
+                    self.format_occupancy(point.threads),
+                    self.placement
+                        .map(|placement| placement.to_string())
+                        .unwrap_or_else(|| "-".to_string())
+                )
+            })
+            .collect()
+    }
+
+    /// Formats the occupancy of the given thread count or a dash if it's unknown
+    fn format_occupancy(&self, threads: usize) -> String {
+        self.occupancy(threads)
+            .map(|occupancy| occupancy.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    /// Returns the file name of the plot for this sweep without an extension
+    pub fn plot_name(&self) -> String {
+        file_stem(&self.name)
+    }
+
+    /// Creates a gnuplot script that plots the speedup against the thread count
+    /// together with the ideal linear speedup into an SVG file of the given path
+    pub fn to_gnuplot(&self, output: &str) -> String {
+        self.to_gnuplot_with(output, &Format::default())
+    }
+
+    /// Creates the gnuplot script like `to_gnuplot` and writes the speedups
+    /// with the precision of the given format
+    pub fn to_gnuplot_with(&self, output: &str, format: &Format) -> String {
+        let speedup = if self.mode != ScalingMode::Strong {
+            "scaled speedup"
+        } else {
+            "speedup"
+        };
+        let data = self
+            .points
+            .iter()
+            .filter_map(|point| {
+                self.speedup(point.threads)
+                    .map(|speedup| format!("{} {}\n", point.threads, format.exported(speedup)))
+            })
+            .collect::<String>();
+        let max_threads = self.points.iter().map(|p| p.threads).max().unwrap_or(1);
+
+        format!(
+            "set terminal svg size 800,600\n\
+             set output \"{}\"\n\
+             set title \"{} ({} scaling)\"\n\
+             set xlabel \"threads\"\n\
+             set ylabel \"{}\"\n\
+             set key left top\n\
+             set grid\n\
+             $data << EOD\n{}EOD\n\
+             plot [1:{}] $data using 1:2 with linespoints title \"measured\", \\\n    \
+             x with lines dashtype 2 title \"ideal\"\n",
+            output,
+            self.name.replace('"', "'"),
+            self.mode,
+            speedup,
+            data,
+            max_threads.max(2)
+        )
+    }
+}
+
+/// The result of fitting Amdahl's law T(p) = T(1) * (s + (1 - s) / p)
+/// to the measurements of a scaling sweep
+#[derive(Debug, Clone, Copy)]
+pub struct AmdahlFit {
+    /// The estimated serial fraction s of the workload
+    pub serial_fraction: f64,
+    /// The coefficient of determination of the fitted durations
+    pub r_squared: f64,
+}
+
+impl AmdahlFit {
+    /// Returns the speedup limit 1 / s for an infinite number of threads
+    pub fn max_speedup(&self) -> f64 {
+        1f64 / self.serial_fraction
+    }
+}
+
+impl Display for AmdahlFit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for AmdahlFit {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(
+            f,
+            "Amdahl serial fraction: {} (R² = {}, max speedup {}x)",
+            format.number(self.serial_fraction),
+            format.number(self.r_squared),
+            format.number(self.max_speedup())
+        )
+    }
+}
+
+/// Formats an optional value with the given function or a dash if it's missing
+fn format_optional(value: Option<f64>, format: impl Fn(f64) -> String) -> String {
+    value.map(format).unwrap_or_else(|| "-".to_string())
+}
+
+impl Display for ScalingResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for ScalingResult {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        let speedup = if self.mode != ScalingMode::Strong {
+            "scaled speedup"
+        } else {
+            "speedup"
+        };
+        writeln!(
+            f,
+            "{:>8}  {:>12}  {:>16}  {:>16}  {:>14}  {:>10}  {:>10}  {:>10}  {:>14}",
+            "threads",
+            "size",
+            "duration",
+            "std deviation",
+            speedup,
+            "efficiency",
+            "karp-flatt",
+            "imbalance",
+            "occupancy"
+        )?;
+        for point in &self.points {
+            writeln!(
+                f,
+                "{:>8}  {:>12}  {:>16}  {:>16}  {:>14}  {:>10}  {:>10}  {:>10}  {:>14}",
+                point.threads,
+                self.problem_size(point.threads)
+                    .map(|size| size.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                format.duration(point.durations.average()),
+                format.nanos(point.durations.standard_deviation()),
+                format_optional(self.speedup(point.threads), |v| format.number(v)),
+                format_optional(self.efficiency(point.threads), |v| format.number(v)),
+                format_optional(self.karp_flatt(point.threads), |v| format.number(v)),
+                format_optional(self.load_imbalance(point.threads), |v| format.number(v)),
+                self.format_occupancy(point.threads)
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::units::{Format, FormatWith};
+
+static JOBS: AtomicU64 = AtomicU64::new(0);
+static STEALS: AtomicU64 = AtomicU64::new(0);
+
+/// Records the given number of jobs that were handed to the rayon scheduler.
+/// Can be used to report counters that were collected by the benchmarked code.
+pub fn record_jobs(jobs: u64) {
+    JOBS.fetch_add(jobs, Ordering::Relaxed);
+}
+
+/// Records the given number of jobs that were stolen by another worker thread
+pub fn record_steals(steals: u64) {
+    STEALS.fetch_add(steals, Ordering::Relaxed);
+}
+
+/// A counting replacement for `rayon::join`.
+/// The second closure is the job that is offered to other workers,
+/// so it is counted as stolen if it runs on a different thread than the caller.
+pub fn join<A, B, RA, RB>(oper_a: A, oper_b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    let caller = rayon::current_thread_index();
+    record_jobs(1);
+    rayon::join(oper_a, move || {
+        if rayon::current_thread_index() != caller {
+            record_steals(1);
+        }
+        oper_b()
+    })
+}
+
+/// A snapshot of the counted scheduler events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchedulerStats {
+    pub jobs: u64,
+    pub steals: u64,
+}
+
+impl SchedulerStats {
+    /// Takes a snapshot of the current scheduler counters
+    pub fn now() -> Self {
+        Self {
+            jobs: JOBS.load(Ordering::Relaxed),
+            steals: STEALS.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the events counted since the given snapshot
+    pub fn since(&self, start: &Self) -> Self {
+        Self {
+            jobs: self.jobs - start.jobs,
+            steals: self.steals - start.steals,
+        }
+    }
+
+    /// Returns the fraction of jobs that were stolen
+    pub fn steal_ratio(&self) -> f64 {
+        if self.jobs == 0 {
+            0f64
+        } else {
+            self.steals as f64 / self.jobs as f64
+        }
+    }
+}
+
+impl Display for SchedulerStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &Format::default())
+    }
+}
+
+impl FormatWith for SchedulerStats {
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result {
+        write!(
+            f,
+            "Scheduler: {} jobs, {} steals ({}% stolen)",
+            format.count(self.jobs as usize),
+            format.count(self.steals as usize),
+            format.number(self.steal_ratio() * 100f64)
+        )
+    }
+}
@@ -0,0 +1,213 @@
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use rayon::prelude::*;
+
+use crate::benching::BenchVec;
+
+/// Returns the durations of a BenchVec in nanoseconds
+pub fn nanos(durations: &BenchVec) -> Vec<f64> {
+    durations
+        .inner
+        .iter()
+        .map(|d| d.as_nanos() as f64)
+        .collect()
+}
+
+pub use crate::sampling::{mean, variance};
+
+/// Returns the quantile function of the standard normal distribution
+/// using Acklam's rational approximation
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    let p_low = 0.02425;
+
+    if p <= p_low {
+        let q = (-2f64 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1f64)
+    } else if p < 1f64 - p_low {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1f64)
+    } else {
+        -normal_quantile(1f64 - p)
+    }
+}
+
+/// Returns the number of samples per benchmark needed to detect the given
+/// difference of means with a two-sided test at the given significance level and power
+pub fn required_sample_size(
+    variance: f64,
+    difference: f64,
+    significance: f64,
+    power: f64,
+) -> usize {
+    let z = normal_quantile(1f64 - significance / 2f64) + normal_quantile(power);
+
+    (2f64 * z.powi(2) * variance / difference.powi(2))
+        .ceil()
+        .max(2f64) as usize
+}
+
+/// Returns the number of iterations needed to detect the given difference
+/// based on the variance of a pilot run
+pub fn required_iterations(
+    pilot: &BenchVec,
+    difference: Duration,
+    significance: f64,
+    power: f64,
+) -> usize {
+    required_sample_size(
+        variance(&nanos(pilot)),
+        difference.as_nanos() as f64,
+        significance,
+        power,
+    )
+}
+
+/// A monotonic trend detected in a series of samples
+#[derive(Debug, Clone, Copy)]
+pub struct Drift {
+    /// The normalized Mann-Kendall statistic of the trend
+    pub z: f64,
+    /// The change over the whole run according to a linear fit relative to the mean
+    pub relative_change: f64,
+}
+
+/// The maximum number of points the trend test compares pairwise. Longer
+/// runs are averaged into this many consecutive bins, so the test stays
+/// cheap compared to the benchmark in the automatic mode.
+pub const MAX_TREND_POINTS: usize = 1000;
+
+/// Returns the means of the values in the given number of consecutive bins
+/// of about equal size or the values if there are fewer of them
+pub fn bin_means(values: &[f64], bins: usize) -> Vec<f64> {
+    let n = values.len();
+    if n <= bins {
+        return values.to_vec();
+    }
+
+    (0..bins)
+        .map(|bin| mean(&values[bin * n / bins..(bin + 1) * n / bins]))
+        .collect()
+}
+
+/// Returns the normalized Mann-Kendall trend statistic of the values
+pub fn mann_kendall(values: &[f64]) -> f64 {
+    let n = values.len();
+    if n < 3 {
+        return 0f64;
+    }
+    let s = (0..n - 1)
+        .into_par_iter()
+        .map(|i| {
+            values[i + 1..]
+                .iter()
+                .map(|v| match v.partial_cmp(&values[i]) {
+                    Some(Ordering::Greater) => 1,
+                    Some(Ordering::Less) => -1,
+                    _ => 0,
+                })
+                .sum::<i64>()
+        })
+        .sum::<i64>() as f64;
+    let n = n as f64;
+    let variance = n * (n - 1f64) * (2f64 * n + 5f64) / 18f64;
+
+    if s > 0f64 {
+        (s - 1f64) / variance.sqrt()
+    } else if s < 0f64 {
+        (s + 1f64) / variance.sqrt()
+    } else {
+        0f64
+    }
+}
+
+/// Returns the slope of a least squares fit of the values over their index
+pub fn linear_slope(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    let mean_x = (n - 1f64) / 2f64;
+    let mean_y = mean(values);
+    let covariance = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i as f64 - mean_x) * (v - mean_y))
+        .sum::<f64>();
+    let variance = (0..values.len())
+        .map(|i| (i as f64 - mean_x).powi(2))
+        .sum::<f64>();
+
+    if variance > 0f64 {
+        covariance / variance
+    } else {
+        0f64
+    }
+}
+
+/// Detects a significant (1% level) monotonic trend in the durations
+/// that changes them by more than 5% over the run. The trend test runs on
+/// at most `MAX_TREND_POINTS` bin means.
+pub fn detect_drift(durations: &BenchVec) -> Option<Drift> {
+    if durations.len() < 10 {
+        return None;
+    }
+    let values = nanos(durations);
+    let z = mann_kendall(&bin_means(&values, MAX_TREND_POINTS));
+    let relative_change = linear_slope(&values) * (values.len() - 1) as f64 / mean(&values);
+
+    if z.abs() > 2.576 && relative_change.abs() > 0.05 {
+        Some(Drift { z, relative_change })
+    } else {
+        None
+    }
+}
+
+/// Returns the t statistic of Welch's test for a difference of the means of
+/// the two samples. For samples of the size of benchmarks the statistic is
+/// compared to the quantiles of the normal distribution.
+pub fn welch_t(left: &[f64], right: &[f64]) -> f64 {
+    if left.is_empty() || right.is_empty() {
+        return 0f64;
+    }
+    let difference = mean(left) - mean(right);
+    let standard_error =
+        (variance(left) / left.len() as f64 + variance(right) / right.len() as f64).sqrt();
+
+    if standard_error > 0f64 {
+        difference / standard_error
+    } else if difference == 0f64 {
+        0f64
+    } else {
+        difference.signum() * f64::INFINITY
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt::{self, Display};
+
+use crate::benching::BenchVec;
+use crate::units::Format;
+
+/// A statistic of a benchmark that can be placed in a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Mean,
+    Stddev,
+    Cv,
+    Iters,
+    Min,
+    Median,
+    P90,
+    P99,
+    Max,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(Field::Name),
+            "mean" => Some(Field::Mean),
+            "stddev" => Some(Field::Stddev),
+            "cv" => Some(Field::Cv),
+            "iters" => Some(Field::Iters),
+            "min" => Some(Field::Min),
+            "median" => Some(Field::Median),
+            "p90" => Some(Field::P90),
+            "p99" => Some(Field::P99),
+            "max" => Some(Field::Max),
+            _ => None,
+        }
+    }
+
+    fn value(&self, name: &str, durations: &BenchVec, format: &Format) -> String {
+        match self {
+            Field::Name => name.to_string(),
+            Field::Mean => format.duration(durations.average()),
+            Field::Stddev => format.nanos(durations.standard_deviation()),
+            Field::Cv => format!(
+                "{}%",
+                format.number(durations.coefficient_of_variation() * 100f64)
+            ),
+            Field::Iters => format.count(durations.len()),
+            Field::Min => format.duration(durations.min()),
+            Field::Median => format.duration(durations.percentile(50f64)),
+            Field::P90 => format.duration(durations.percentile(90f64)),
+            Field::P99 => format.duration(durations.percentile(99f64)),
+            Field::Max => format.duration(durations.max()),
+        }
+    }
+}
+
+/// An error in the syntax of a template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// The placeholder names a field that doesn't exist
+    UnknownField(String),
+    /// The format of the placeholder is not an alignment followed by a width
+    InvalidFormat(String),
+    /// A brace is not closed or not escaped
+    UnmatchedBrace,
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnknownField(field) => write!(f, "unknown field {}", field),
+            TemplateError::InvalidFormat(format) => write!(f, "invalid format {}", format),
+            TemplateError::UnmatchedBrace => write!(f, "unmatched brace"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field {
+        field: Field,
+        align: char,
+        width: usize,
+    },
+}
+
+/// A template for the line of a result like `{name:<30} {mean:>10} ±{stddev} ({iters} iters)`.
+/// Placeholders name a field and optionally an alignment (`<`, `>` or `^`) with
+/// a width. The fields are name, mean, stddev, cv, iters, min, median, p90, p99
+/// and max. Literal braces are written as `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultTemplate {
+    parts: Vec<Part>,
+}
+
+impl ResultTemplate {
+    /// Parses the given template
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(TemplateError::UnmatchedBrace),
+                        }
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Self::parse_placeholder(&placeholder)?);
+                }
+                '}' => return Err(TemplateError::UnmatchedBrace),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Self { parts })
+    }
+
+    fn parse_placeholder(placeholder: &str) -> Result<Part, TemplateError> {
+        let (name, format) = match placeholder.split_once(':') {
+            Some((name, format)) => (name.trim(), format),
+            None => (placeholder.trim(), ""),
+        };
+        let field =
+            Field::parse(name).ok_or_else(|| TemplateError::UnknownField(name.to_string()))?;
+        let (align, width) = match format.chars().next() {
+            Some(align @ ('<' | '>' | '^')) => (align, &format[1..]),
+            _ => ('<', format),
+        };
+        let width = if width.is_empty() {
+            0
+        } else {
+            width
+                .parse::<usize>()
+                .map_err(|_| TemplateError::InvalidFormat(format.to_string()))?
+        };
+
+        Ok(Part::Field {
+            field,
+            align,
+            width,
+        })
+    }
+
+    /// Renders the template with the statistics of the given benchmark
+    /// formatted in the given format
+    pub fn render(&self, name: &str, durations: &BenchVec, format: &Format) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Field {
+                    field,
+                    align,
+                    width,
+                } => {
+                    let value = field.value(name, durations, format);
+                    match align {
+                        '>' => format!("{:>width$}", value, width = width),
+                        '^' => format!("{:^width$}", value, width = width),
+                        _ => format!("{:<width$}", value, width = width),
+                    }
+                }
+            })
+            .collect()
+    }
+}
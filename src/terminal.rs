@@ -0,0 +1,417 @@
+use std::io::{self, IsTerminal};
+
+/// The styles of the console output. The colors and attributes of each
+/// style are defined by a theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// The name of a benchmark or the title of a group of results
+    Heading,
+    /// The title of the settings and summaries
+    Section,
+    /// The result line of a benchmark
+    Result,
+    /// A remark that is less important than the results
+    Note,
+    /// A problem that potentially makes a result unreliable
+    Warning,
+    /// A comparison with faster results
+    Improvement,
+    /// A comparison with small but significant changes
+    Minor,
+    /// A comparison with slower results
+    Regression,
+    /// A comparison with changes that are not significant
+    Insignificant,
+}
+
+/// The foreground colors of a theme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Grey,
+    LightRed,
+    LightGreen,
+    LightYellow,
+    LightBlue,
+    LightMagenta,
+    LightCyan,
+}
+
+/// The color and attributes of a style
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ThemeStyle {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub underline: bool,
+    pub dim: bool,
+}
+
+impl ThemeStyle {
+    /// Creates a style without color and attributes
+    pub const fn plain() -> Self {
+        Self {
+            color: None,
+            bold: false,
+            underline: false,
+            dim: false,
+        }
+    }
+
+    /// Creates a style with the given color
+    pub const fn color(color: Color) -> Self {
+        Self {
+            color: Some(color),
+            ..Self::plain()
+        }
+    }
+
+    /// Makes the style bold
+    pub const fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    /// Makes the style underlined
+    pub const fn underline(self) -> Self {
+        Self {
+            underline: true,
+            ..self
+        }
+    }
+
+    /// Makes the style faint
+    pub const fn dim(self) -> Self {
+        Self { dim: true, ..self }
+    }
+
+    /// Returns the escape sequence that starts the style.
+    /// With crossterm the sequences are created by crossterm, with termion
+    /// by termion. Without a terminal backend the output is not styled.
+    pub fn escape(&self) -> String {
+        format!(
+            "{}{}",
+            self.color.map(foreground).unwrap_or_default(),
+            self.attributes()
+        )
+    }
+
+    /// Returns the escape sequences of the attributes
+    fn attributes(&self) -> String {
+        #[cfg(feature = "crossterm")]
+        {
+            use crossterm::style::{Attribute, SetAttribute};
+            [
+                (self.bold, Attribute::Bold),
+                (self.underline, Attribute::Underlined),
+                (self.dim, Attribute::Dim),
+            ]
+            .iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, attribute)| SetAttribute(*attribute).to_string())
+            .collect()
+        }
+        #[cfg(all(not(feature = "crossterm"), feature = "termion", unix))]
+        {
+            use termion::style;
+            [
+                (self.bold, style::Bold.to_string()),
+                (self.underline, style::Underline.to_string()),
+                (self.dim, style::Faint.to_string()),
+            ]
+            .iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, attribute)| attribute.as_str())
+            .collect()
+        }
+        #[cfg(not(any(feature = "crossterm", all(feature = "termion", unix))))]
+        {
+            String::new()
+        }
+    }
+}
+
+/// Returns the escape sequence that sets the foreground color
+fn foreground(color: Color) -> String {
+    #[cfg(feature = "crossterm")]
+    {
+        use crossterm::style::{Color as C, SetForegroundColor};
+        let color = match color {
+            Color::Black => C::Black,
+            Color::Red => C::DarkRed,
+            Color::Green => C::DarkGreen,
+            Color::Yellow => C::DarkYellow,
+            Color::Blue => C::DarkBlue,
+            Color::Magenta => C::DarkMagenta,
+            Color::Cyan => C::DarkCyan,
+            Color::White => C::Grey,
+            Color::Grey => C::DarkGrey,
+            Color::LightRed => C::Red,
+            Color::LightGreen => C::Green,
+            Color::LightYellow => C::Yellow,
+            Color::LightBlue => C::Blue,
+            Color::LightMagenta => C::Magenta,
+            Color::LightCyan => C::Cyan,
+        };
+        SetForegroundColor(color).to_string()
+    }
+    #[cfg(all(not(feature = "crossterm"), feature = "termion", unix))]
+    {
+        use termion::color::{self as c, Fg};
+        match color {
+            Color::Black => Fg(c::Black).to_string(),
+            Color::Red => Fg(c::Red).to_string(),
+            Color::Green => Fg(c::Green).to_string(),
+            Color::Yellow => Fg(c::Yellow).to_string(),
+            Color::Blue => Fg(c::Blue).to_string(),
+            Color::Magenta => Fg(c::Magenta).to_string(),
+            Color::Cyan => Fg(c::Cyan).to_string(),
+            Color::White => Fg(c::White).to_string(),
+            Color::Grey => Fg(c::LightBlack).to_string(),
+            Color::LightRed => Fg(c::LightRed).to_string(),
+            Color::LightGreen => Fg(c::LightGreen).to_string(),
+            Color::LightYellow => Fg(c::LightYellow).to_string(),
+            Color::LightBlue => Fg(c::LightBlue).to_string(),
+            Color::LightMagenta => Fg(c::LightMagenta).to_string(),
+            Color::LightCyan => Fg(c::LightCyan).to_string(),
+        }
+    }
+    #[cfg(not(any(feature = "crossterm", all(feature = "termion", unix))))]
+    {
+        let _ = color;
+        String::new()
+    }
+}
+
+/// The styles of the console output. The default theme is made for dark
+/// backgrounds, `light` for light backgrounds and `monochrome` only uses
+/// attributes instead of colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub heading: ThemeStyle,
+    pub section: ThemeStyle,
+    pub result: ThemeStyle,
+    pub note: ThemeStyle,
+    pub warning: ThemeStyle,
+    pub improvement: ThemeStyle,
+    pub minor: ThemeStyle,
+    pub regression: ThemeStyle,
+    pub insignificant: ThemeStyle,
+}
+
+impl Theme {
+    /// Creates a theme that is readable on light backgrounds
+    pub const fn light() -> Self {
+        Self {
+            heading: ThemeStyle::color(Color::Blue).bold(),
+            section: ThemeStyle::color(Color::Magenta).underline(),
+            result: ThemeStyle::plain(),
+            note: ThemeStyle::color(Color::Grey),
+            warning: ThemeStyle::color(Color::Red),
+            improvement: ThemeStyle::color(Color::Green),
+            minor: ThemeStyle::color(Color::Magenta),
+            regression: ThemeStyle::color(Color::Red).bold(),
+            insignificant: ThemeStyle::color(Color::Grey),
+        }
+    }
+
+    /// Creates a theme without colors that only uses attributes
+    pub const fn monochrome() -> Self {
+        Self {
+            heading: ThemeStyle::plain().bold(),
+            section: ThemeStyle::plain().underline(),
+            result: ThemeStyle::plain(),
+            note: ThemeStyle::plain().dim(),
+            warning: ThemeStyle::plain().bold(),
+            improvement: ThemeStyle::plain(),
+            minor: ThemeStyle::plain(),
+            regression: ThemeStyle::plain().bold(),
+            insignificant: ThemeStyle::plain().dim(),
+        }
+    }
+
+    /// Returns the color and attributes of the given style
+    pub fn get(&self, style: Style) -> ThemeStyle {
+        match style {
+            Style::Heading => self.heading,
+            Style::Section => self.section,
+            Style::Result => self.result,
+            Style::Note => self.note,
+            Style::Warning => self.warning,
+            Style::Improvement => self.improvement,
+            Style::Minor => self.minor,
+            Style::Regression => self.regression,
+            Style::Insignificant => self.insignificant,
+        }
+    }
+
+    /// Returns the escape sequence that starts the given style
+    pub fn style(&self, style: Style) -> String {
+        self.get(style).escape()
+    }
+}
+
+impl Default for Theme {
+    /// Creates the theme for dark backgrounds
+    fn default() -> Self {
+        Self {
+            heading: ThemeStyle::color(Color::LightBlue).bold(),
+            section: ThemeStyle::color(Color::Green).underline(),
+            result: ThemeStyle::plain(),
+            note: ThemeStyle::plain().dim(),
+            warning: ThemeStyle::color(Color::Yellow),
+            improvement: ThemeStyle::color(Color::Green),
+            minor: ThemeStyle::color(Color::Yellow),
+            regression: ThemeStyle::color(Color::Red),
+            insignificant: ThemeStyle::color(Color::Grey),
+        }
+    }
+}
+
+/// Returns the escape sequence that starts the given style of the default theme
+pub fn style(style: Style) -> String {
+    Theme::default().style(style)
+}
+
+/// Returns the escape sequence that resets all styles
+pub fn reset() -> String {
+    #[cfg(feature = "crossterm")]
+    {
+        crossterm::style::SetAttribute(crossterm::style::Attribute::Reset).to_string()
+    }
+    #[cfg(all(not(feature = "crossterm"), feature = "termion", unix))]
+    {
+        termion::style::Reset.to_string()
+    }
+    #[cfg(not(any(feature = "crossterm", all(feature = "termion", unix))))]
+    {
+        String::new()
+    }
+}
+
+/// Returns the escape sequence that clears the current line
+pub fn clear_line() -> String {
+    #[cfg(feature = "crossterm")]
+    {
+        crossterm::terminal::Clear(crossterm::terminal::ClearType::CurrentLine).to_string()
+    }
+    #[cfg(all(not(feature = "crossterm"), feature = "termion", unix))]
+    {
+        termion::clear::CurrentLine.to_string()
+    }
+    #[cfg(not(any(feature = "crossterm", all(feature = "termion", unix))))]
+    {
+        String::new()
+    }
+}
+
+/// Returns the escape sequences that switch to the alternate screen
+/// and hide the cursor for a full-screen view
+pub fn enter_screen() -> String {
+    #[cfg(feature = "crossterm")]
+    {
+        use crossterm::Command;
+        let mut sequence = String::new();
+        let _ = crossterm::terminal::EnterAlternateScreen.write_ansi(&mut sequence);
+        format!("{}{}", sequence, crossterm::cursor::Hide)
+    }
+    #[cfg(all(not(feature = "crossterm"), feature = "termion", unix))]
+    {
+        format!(
+            "{}{}",
+            termion::screen::ToAlternateScreen,
+            termion::cursor::Hide
+        )
+    }
+    #[cfg(not(any(feature = "crossterm", all(feature = "termion", unix))))]
+    {
+        String::new()
+    }
+}
+
+/// Returns the escape sequences that show the cursor
+/// and switch back from the alternate screen
+pub fn leave_screen() -> String {
+    #[cfg(feature = "crossterm")]
+    {
+        use crossterm::Command;
+        let mut sequence = String::new();
+        let _ = crossterm::terminal::LeaveAlternateScreen.write_ansi(&mut sequence);
+        format!("{}{}", crossterm::cursor::Show, sequence)
+    }
+    #[cfg(all(not(feature = "crossterm"), feature = "termion", unix))]
+    {
+        format!("{}{}", termion::cursor::Show, termion::screen::ToMainScreen)
+    }
+    #[cfg(not(any(feature = "crossterm", all(feature = "termion", unix))))]
+    {
+        String::new()
+    }
+}
+
+/// Returns the escape sequences that clear the screen
+/// and move the cursor to its top left corner
+pub fn clear_screen() -> String {
+    #[cfg(feature = "crossterm")]
+    {
+        format!(
+            "{}{}",
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+            crossterm::cursor::MoveTo(0, 0)
+        )
+    }
+    #[cfg(all(not(feature = "crossterm"), feature = "termion", unix))]
+    {
+        format!("{}{}", termion::clear::All, termion::cursor::Goto(1, 1))
+    }
+    #[cfg(not(any(feature = "crossterm", all(feature = "termion", unix))))]
+    {
+        String::new()
+    }
+}
+
+/// Returns the number of columns and rows of the terminal
+pub fn size() -> Option<(u16, u16)> {
+    #[cfg(feature = "crossterm")]
+    {
+        crossterm::terminal::size().ok()
+    }
+    #[cfg(all(not(feature = "crossterm"), feature = "termion", unix))]
+    {
+        termion::terminal_size().ok()
+    }
+    #[cfg(not(any(feature = "crossterm", all(feature = "termion", unix))))]
+    {
+        None
+    }
+}
+
+/// Returns if stdout is a terminal that is able to display the styles.
+/// On Windows the processing of escape sequences is enabled by crossterm.
+pub fn supports_styles() -> bool {
+    styles_supported(io::stdout().is_terminal())
+}
+
+/// Returns if stderr is a terminal that supports styles
+pub fn stderr_supports_styles() -> bool {
+    styles_supported(io::stderr().is_terminal())
+}
+
+fn styles_supported(is_terminal: bool) -> bool {
+    if !is_terminal {
+        return false;
+    }
+    #[cfg(all(feature = "crossterm", windows))]
+    {
+        crossterm::ansi_support::supports_ansi()
+    }
+    #[cfg(not(all(feature = "crossterm", windows)))]
+    {
+        cfg!(any(feature = "crossterm", all(feature = "termion", unix)))
+    }
+}
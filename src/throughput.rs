@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use crate::units::Format;
+
+/// The amount of data processed by one iteration of a benchmark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Throughput {
+    /// The number of bytes processed per iteration
+    Bytes(u64),
+    /// The number of elements processed per iteration
+    Elements(u64),
+}
+
+impl Throughput {
+    /// Returns the processed amount per second for the given iteration time
+    pub fn per_second(&self, duration: Duration) -> f64 {
+        let amount = match self {
+            Throughput::Bytes(bytes) => *bytes,
+            Throughput::Elements(elements) => *elements,
+        };
+
+        amount as f64 / duration.as_secs_f64()
+    }
+
+    /// Formats the rate for the given iteration time as GB/s or Melem/s
+    pub fn format_rate(&self, duration: Duration) -> String {
+        self.format_rate_with(duration, &Format::default())
+    }
+
+    /// Formats the rate for the given iteration time as GB/s or Melem/s
+    /// with the given format
+    pub fn format_rate_with(&self, duration: Duration, format: &Format) -> String {
+        let rate = self.per_second(duration);
+        match self {
+            Throughput::Bytes(_) => format!("{} GB/s", format.number(rate / 1e9)),
+            Throughput::Elements(_) => format!("{} Melem/s", format.number(rate / 1e6)),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+const CPU_PATH: &str = "/sys/devices/system/cpu";
+
+/// A logical CPU with the physical core and the socket it belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cpu {
+    pub id: usize,
+    pub core: usize,
+    pub socket: usize,
+}
+
+/// How the worker threads of a measurement occupy the CPUs of the machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occupancy {
+    /// Every thread can run on its own physical core
+    Physical,
+    /// Some threads share a physical core with its SMT siblings
+    Smt,
+    /// There are more threads than logical CPUs
+    Oversubscribed,
+}
+
+impl Display for Occupancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Occupancy::Physical => write!(f, "physical"),
+            Occupancy::Smt => write!(f, "smt"),
+            Occupancy::Oversubscribed => write!(f, "oversubscribed"),
+        }
+    }
+}
+
+/// The order in which the worker threads of a sweep are placed on the CPUs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Fill the physical cores of one socket before using the next socket
+    Compact,
+    /// Distribute the threads round-robin across the sockets
+    Spread,
+}
+
+impl Placement {
+    /// All placements, to sweep over them
+    pub const ALL: [Placement; 2] = [Placement::Compact, Placement::Spread];
+}
+
+impl Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placement::Compact => write!(f, "compact"),
+            Placement::Spread => write!(f, "spread"),
+        }
+    }
+}
+
+/// The logical CPUs of the machine with their cores and sockets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuTopology {
+    pub cpus: Vec<Cpu>,
+}
+
+impl CpuTopology {
+    /// Creates a topology from the given logical CPUs
+    pub fn from_cpus(cpus: Vec<Cpu>) -> Self {
+        Self { cpus }
+    }
+
+    /// Reads the topology from sysfs. If it can't be read every logical CPU
+    /// is assumed to be a physical core of a single socket.
+    pub fn detect() -> Self {
+        let mut cpus = fs::read_dir(CPU_PATH)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter_map(|entry| {
+                        let name = entry.file_name();
+                        let id = name.to_string_lossy().strip_prefix("cpu")?.parse().ok()?;
+                        let topology = entry.path().join("topology");
+
+                        Some(Cpu {
+                            id,
+                            core: read_value(&topology.join("core_id"))?,
+                            socket: read_value(&topology.join("physical_package_id"))?,
+                        })
+                    })
+                    .collect::<Vec<Cpu>>()
+            })
+            .unwrap_or_default();
+        if cpus.is_empty() {
+            let logical = thread::available_parallelism().map_or(1, |n| n.get());
+            cpus = (0..logical)
+                .map(|id| Cpu {
+                    id,
+                    core: id,
+                    socket: 0,
+                })
+                .collect();
+        }
+        cpus.sort_by_key(|cpu| cpu.id);
+
+        Self { cpus }
+    }
+
+    /// Returns the number of logical CPUs
+    pub fn logical_cpus(&self) -> usize {
+        self.cpus.len()
+    }
+
+    /// Returns the number of physical cores
+    pub fn physical_cores(&self) -> usize {
+        self.cpus
+            .iter()
+            .map(|cpu| (cpu.socket, cpu.core))
+            .collect::<BTreeSet<(usize, usize)>>()
+            .len()
+    }
+
+    /// Returns the number of sockets
+    pub fn sockets(&self) -> usize {
+        self.cpus
+            .iter()
+            .map(|cpu| cpu.socket)
+            .collect::<BTreeSet<usize>>()
+            .len()
+    }
+
+    /// Returns if physical cores have more than one logical CPU
+    pub fn has_smt(&self) -> bool {
+        self.logical_cpus() > self.physical_cores()
+    }
+
+    /// Returns how the given number of threads occupies the CPUs
+    pub fn occupancy(&self, threads: usize) -> Occupancy {
+        if threads <= self.physical_cores() {
+            Occupancy::Physical
+        } else if threads <= self.logical_cpus() {
+            Occupancy::Smt
+        } else {
+            Occupancy::Oversubscribed
+        }
+    }
+
+    /// Returns thread counts for a sweep up to the given multiple of the
+    /// logical CPUs, so the effects of SMT and oversubscription can be observed.
+    /// It contains the powers of two and the numbers of physical cores and logical CPUs.
+    pub fn oversubscribed_sweep(&self, factor: usize) -> Vec<usize> {
+        let max = self.logical_cpus() * factor.max(1);
+        let mut threads = (0..)
+            .map(|exponent| 1usize << exponent)
+            .take_while(|&threads| threads <= max)
+            .collect::<BTreeSet<usize>>();
+        threads.insert(self.physical_cores());
+        threads.insert(self.logical_cpus());
+        threads.insert(max);
+
+        threads.into_iter().collect()
+    }
+
+    /// Returns the topology with only the given logical CPUs, e.g. the
+    /// affinity mask of the process, so placements don't use CPUs
+    /// the process isn't allowed to run on
+    pub fn restricted_to(&self, allowed: &[usize]) -> Self {
+        Self {
+            cpus: self
+                .cpus
+                .iter()
+                .filter(|cpu| allowed.contains(&cpu.id))
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Returns the logical CPUs the given number of threads are pinned to with
+    /// the given placement. SMT siblings are only used once every physical core
+    /// is occupied and the CPUs are reused if there are more threads than CPUs.
+    pub fn placement(&self, placement: Placement, threads: usize) -> Vec<usize> {
+        let mut keyed = self
+            .cpus
+            .iter()
+            .map(|cpu| {
+                let sibling = self
+                    .cpus
+                    .iter()
+                    .filter(|c| c.socket == cpu.socket && c.core == cpu.core && c.id < cpu.id)
+                    .count();
+                let core_rank = self
+                    .cpus
+                    .iter()
+                    .filter(|c| c.socket == cpu.socket && c.core < cpu.core)
+                    .map(|c| c.core)
+                    .collect::<BTreeSet<usize>>()
+                    .len();
+                let key = match placement {
+                    Placement::Compact => (sibling, cpu.socket, core_rank),
+                    Placement::Spread => (sibling, core_rank, cpu.socket),
+                };
+                (key, cpu.id)
+            })
+            .collect::<Vec<((usize, usize, usize), usize)>>();
+        keyed.sort();
+
+        keyed
+            .iter()
+            .map(|(_, id)| *id)
+            .cycle()
+            .take(threads)
+            .collect()
+    }
+}
+
+impl Display for CpuTopology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} sockets, {} physical cores, {} logical CPUs{}",
+            self.sockets(),
+            self.physical_cores(),
+            self.logical_cpus(),
+            if self.has_smt() { " (SMT)" } else { "" }
+        )
+    }
+}
+
+fn read_value(path: &Path) -> Option<usize> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
@@ -0,0 +1,268 @@
+use std::fmt;
+use std::time::Duration;
+
+/// The separators of the numbers in the console output. Exported
+/// numbers are always written without thousands separators and
+/// with a dot as decimal separator, so they can be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub thousands: Option<char>,
+    pub decimal: char,
+}
+
+impl NumberFormat {
+    /// Creates a format without thousands separators like 1234567.89
+    pub const fn plain() -> Self {
+        Self {
+            thousands: None,
+            decimal: '.',
+        }
+    }
+
+    /// Creates a format with the given separators
+    pub const fn new(thousands: char, decimal: char) -> Self {
+        Self {
+            thousands: Some(thousands),
+            decimal,
+        }
+    }
+
+    /// Creates the format of the locale in LC_ALL, LC_NUMERIC or LANG,
+    /// e.g. 1,234,567.89 for en_US and 1.234.567,89 for de_DE.
+    /// Unknown locales use the format of english.
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Self::for_locale(&locale)
+    }
+
+    /// Creates the format of the given locale like de_DE.UTF-8
+    pub fn for_locale(locale: &str) -> Self {
+        let mut parts = locale.split(['_', '-', '.', '@']);
+        let language = parts.next().unwrap_or_default();
+        let country = parts.next().unwrap_or_default();
+        match (language, country) {
+            ("C" | "POSIX", _) => Self::plain(),
+            ("de" | "it", "CH") => Self::new('\'', '.'),
+            ("de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el", _) => {
+                Self::new('.', ',')
+            }
+            ("fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu", _) => {
+                Self::new(' ', ',')
+            }
+            _ => Self::new(',', '.'),
+        }
+    }
+
+    /// Applies the separators to a number formatted by Rust like -1234567.89
+    pub fn apply(&self, number: &str) -> String {
+        let (sign, digits) = match number.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", number),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (digits, None),
+        };
+        let mut formatted = sign.to_string();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                if let Some(separator) = self.thousands {
+                    formatted.push(separator);
+                }
+            }
+            formatted.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push(self.decimal);
+            formatted.push_str(fraction);
+        }
+
+        formatted
+    }
+}
+
+/// The precision statistics are printed and exported with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// A fixed number of decimal places
+    Decimals(usize),
+    /// A fixed number of significant digits. Whole digits are never dropped.
+    Significant(usize),
+}
+
+impl Precision {
+    /// Returns the number of decimal places the given value is formatted with
+    pub fn decimals_for(&self, value: f64) -> usize {
+        match *self {
+            Precision::Decimals(decimals) => decimals,
+            Precision::Significant(digits) => {
+                let magnitude = if value == 0f64 || !value.is_finite() {
+                    0
+                } else {
+                    value.abs().log10().floor() as i64
+                };
+                (digits.max(1) as i64 - 1 - magnitude).max(0) as usize
+            }
+        }
+    }
+
+    /// Formats the given value with the precision
+    pub fn format(&self, value: f64) -> String {
+        format!("{:.*}", self.decimals_for(value), value)
+    }
+}
+
+/// The unit durations are reported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// The most readable unit for each value
+    Auto,
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+}
+
+impl TimeUnit {
+    /// Returns the most readable unit for the given number of nanoseconds
+    pub fn for_nanos(nanos: f64) -> Self {
+        match nanos.abs() {
+            n if n < 1e3 => TimeUnit::Nanoseconds,
+            n if n < 1e6 => TimeUnit::Microseconds,
+            n if n < 1e9 => TimeUnit::Milliseconds,
+            _ => TimeUnit::Seconds,
+        }
+    }
+
+    /// Returns the number of nanoseconds of the unit and its symbol
+    fn scale(&self) -> (f64, &'static str) {
+        match self {
+            TimeUnit::Auto | TimeUnit::Nanoseconds => (1f64, "ns"),
+            TimeUnit::Microseconds => (1e3, "µs"),
+            TimeUnit::Milliseconds => (1e6, "ms"),
+            TimeUnit::Seconds => (1e9, "s"),
+        }
+    }
+}
+
+/// The unit, precision and separators that a Bencher formats the
+/// reported numbers with. Every Bencher has its own format and passes
+/// it to its reporters, so Benchers with different settings don't
+/// interfere with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Format {
+    pub time_unit: TimeUnit,
+    pub precision: Precision,
+    pub number_format: NumberFormat,
+}
+
+impl Format {
+    /// Formats the given number for the console with the precision and
+    /// separators
+    pub fn number(&self, value: f64) -> String {
+        self.number_format.apply(&self.precision.format(value))
+    }
+
+    /// Formats the given number for exported files with the precision
+    /// but without separators
+    pub fn exported(&self, value: f64) -> String {
+        self.precision.format(value)
+    }
+
+    /// Formats a count like the number of iterations for the console
+    pub fn count(&self, count: usize) -> String {
+        self.number_format.apply(&count.to_string())
+    }
+
+    /// Formats the given number of nanoseconds in the time unit
+    pub fn nanos(&self, nanos: f64) -> String {
+        let unit = match self.time_unit {
+            TimeUnit::Auto => TimeUnit::for_nanos(nanos),
+            unit => unit,
+        };
+        let (scale, symbol) = unit.scale();
+
+        format!("{}{}", self.number(nanos / scale), symbol)
+    }
+
+    /// Formats the given duration in the time unit
+    pub fn duration(&self, duration: Duration) -> String {
+        self.nanos(duration.as_nanos() as f64)
+    }
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            time_unit: TimeUnit::Auto,
+            precision: Precision::Decimals(2),
+            number_format: NumberFormat::plain(),
+        }
+    }
+}
+
+/// Formats a value with the settings of a Bencher. The `Display`
+/// implementation of these values uses the default format.
+pub trait FormatWith {
+    /// Formats the value with the given settings
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, format: &Format) -> fmt::Result;
+
+    /// Returns a wrapper that displays the value with the given settings
+    fn with_format(&self, format: Format) -> WithFormat<'_, Self> {
+        WithFormat {
+            value: self,
+            format,
+        }
+    }
+}
+
+/// A value that is displayed with the given format
+#[derive(Debug, Clone, Copy)]
+pub struct WithFormat<'a, T: ?Sized> {
+    value: &'a T,
+    format: Format,
+}
+
+impl<T: FormatWith + ?Sized> fmt::Display for WithFormat<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_with(f, &self.format)
+    }
+}
+
+/// Formats the given number of nanoseconds in the given unit
+pub fn format_nanos_in(nanos: f64, unit: TimeUnit) -> String {
+    Format {
+        time_unit: unit,
+        ..Format::default()
+    }
+    .nanos(nanos)
+}
+
+/// Formats the given number for the console with the default format
+pub fn format_number(value: f64) -> String {
+    Format::default().number(value)
+}
+
+/// Formats the given number for exported files with the default precision
+pub fn format_exported(value: f64) -> String {
+    Format::default().exported(value)
+}
+
+/// Formats a count like the number of iterations with the default format
+pub fn format_count(count: usize) -> String {
+    Format::default().count(count)
+}
+
+/// Formats the given number of nanoseconds with the default format
+pub fn format_nanos(nanos: f64) -> String {
+    Format::default().nanos(nanos)
+}
+
+/// Formats the given duration with the default format
+pub fn format_duration(duration: Duration) -> String {
+    Format::default().duration(duration)
+}
@@ -0,0 +1,106 @@
+use std::io::{self, Write};
+use std::ops::{Add, Sub};
+use std::time::Duration;
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    /// `performance.now()` of the window or worker in milliseconds
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+
+    #[wasm_bindgen(js_namespace = console, js_name = log)]
+    fn console_log(line: &str);
+}
+
+/// Returns the time since the time origin of the page or worker.
+/// Browsers coarsen the timer, usually to 5µs to 100µs, unless the
+/// page is cross-origin isolated.
+pub fn now() -> Duration {
+    Duration::from_secs_f64(performance_now().max(0f64) / 1000f64)
+}
+
+/// A monotonic instant based on `performance.now()`, which replaces
+/// `std::time::Instant` that panics on wasm32-unknown-unknown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(Duration);
+
+impl Instant {
+    pub fn now() -> Self {
+        Self(now())
+    }
+
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.0.checked_sub(earlier.0).unwrap_or_default()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, other: Duration) -> Instant {
+        Instant(self.0 + other)
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, other: Duration) -> Instant {
+        Instant(self.0.checked_sub(other).unwrap_or_default())
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, other: Instant) -> Duration {
+        self.duration_since(other)
+    }
+}
+
+/// Writes every complete line to the browser console with `console.log`.
+/// Flushing keeps an incomplete line until it's finished, so the reporter
+/// output isn't split into several console messages.
+#[derive(Debug, Default)]
+pub struct ConsoleWriter {
+    line: Vec<u8>,
+}
+
+impl ConsoleWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Write for ConsoleWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for byte in buf {
+            if *byte == b'\n' {
+                console_log(&String::from_utf8_lossy(&self.line));
+                self.line.clear();
+            } else {
+                self.line.push(*byte);
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ConsoleWriter {
+    fn drop(&mut self) {
+        if !self.line.is_empty() {
+            console_log(&String::from_utf8_lossy(&self.line));
+        }
+    }
+}